@@ -0,0 +1,115 @@
+#!/usr/bin/env python3
+"""Mock REST backend for the todo list's sync feature.
+
+Run `python3 mock_server.py [port]` (default 8787) and enter
+http://localhost:8787 under "Sync settings" in the app.
+
+    GET    /todos                          list all todos
+    PUT    /todos/<id>                     create or update, body carries base_version
+    DELETE /todos/<id>?base_version=<n>    delete
+
+Writes whose base_version doesn't match the stored version get 409 with the
+current server copy. Set MOCK_FAIL_RATE=0.3 to make a share of requests fail
+with 503 and exercise the retry path.
+"""
+
+import json
+import os
+import random
+import sys
+from http.server import BaseHTTPRequestHandler, ThreadingHTTPServer
+from urllib.parse import parse_qs, urlparse
+
+TODOS = {}
+FAIL_RATE = float(os.environ.get("MOCK_FAIL_RATE", "0"))
+
+
+class Handler(BaseHTTPRequestHandler):
+    def _send(self, status, body=None):
+        payload = b"" if body is None else json.dumps(body).encode()
+        self.send_response(status)
+        self.send_header("Access-Control-Allow-Origin", "*")
+        self.send_header("Access-Control-Allow-Methods", "GET, PUT, DELETE, OPTIONS")
+        self.send_header("Access-Control-Allow-Headers", "Content-Type")
+        if body is not None:
+            self.send_header("Content-Type", "application/json")
+        self.send_header("Content-Length", str(len(payload)))
+        self.end_headers()
+        self.wfile.write(payload)
+
+    def _todo_id(self, path):
+        parts = path.strip("/").split("/")
+        if len(parts) == 2 and parts[0] == "todos" and parts[1].isdigit():
+            return int(parts[1])
+        return None
+
+    def _maybe_fail(self):
+        if random.random() < FAIL_RATE:
+            self._send(503, {"error": "simulated outage"})
+            return True
+        return False
+
+    def do_OPTIONS(self):
+        self._send(204)
+
+    def do_GET(self):
+        if self._maybe_fail():
+            return
+        if urlparse(self.path).path.rstrip("/") == "/todos":
+            self._send(200, list(TODOS.values()))
+        else:
+            self._send(404, {"error": "not found"})
+
+    def do_PUT(self):
+        if self._maybe_fail():
+            return
+        todo_id = self._todo_id(urlparse(self.path).path)
+        if todo_id is None:
+            self._send(404, {"error": "not found"})
+            return
+        length = int(self.headers.get("Content-Length", 0))
+        try:
+            body = json.loads(self.rfile.read(length) or b"{}")
+        except json.JSONDecodeError:
+            self._send(400, {"error": "invalid json"})
+            return
+
+        base_version = body.get("base_version", 0)
+        current = TODOS.get(todo_id)
+        if current is None and base_version > 0:
+            self._send(404, {"error": "deleted on server"})
+            return
+        if current is not None and current["version"] != base_version:
+            self._send(409, current)
+            return
+
+        TODOS[todo_id] = {
+            "id": todo_id,
+            "text": body.get("text", ""),
+            "completed": bool(body.get("completed", False)),
+            "tags": body.get("tags", []),
+            "version": (current["version"] if current else 0) + 1,
+        }
+        self._send(200, TODOS[todo_id])
+
+    def do_DELETE(self):
+        if self._maybe_fail():
+            return
+        url = urlparse(self.path)
+        todo_id = self._todo_id(url.path)
+        current = TODOS.get(todo_id)
+        if current is None:
+            self._send(404, {"error": "not found"})
+            return
+        base_version = int(parse_qs(url.query).get("base_version", ["0"])[0])
+        if current["version"] != base_version:
+            self._send(409, current)
+            return
+        del TODOS[todo_id]
+        self._send(204)
+
+
+if __name__ == "__main__":
+    port = int(sys.argv[1]) if len(sys.argv) > 1 else 8787
+    print(f"Mock todo backend on http://localhost:{port}")
+    ThreadingHTTPServer(("", port), Handler).serve_forever()
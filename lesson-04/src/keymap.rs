@@ -0,0 +1,239 @@
+use leptos::ev;
+use leptos::prelude::*;
+use wasm_bindgen::JsCast;
+
+#[derive(Clone)]
+pub struct Shortcut {
+    pub keys: &'static [&'static str],
+    pub label: &'static str,
+    pub description: &'static str,
+    pub action: Callback<()>,
+}
+
+impl Shortcut {
+    pub fn new(
+        keys: &'static [&'static str],
+        label: &'static str,
+        description: &'static str,
+        action: impl Fn() + Send + Sync + 'static,
+    ) -> Self {
+        Self {
+            keys,
+            label,
+            description,
+            action: Callback::new(move |_| action()),
+        }
+    }
+}
+
+/// The parts of a keydown event that decide what it does.
+#[derive(Clone, Debug, Default)]
+struct Chord<'a> {
+    key: &'a str,
+    /// Ctrl, Meta or Alt was held; Shift is part of `key` (`?` is Shift+/).
+    modifier: bool,
+    /// The event comes from a field that takes the keystroke itself.
+    typing: bool,
+    /// Some other handler already took the event.
+    handled: bool,
+}
+
+impl<'a> Chord<'a> {
+    fn from_event(event: &web_sys::KeyboardEvent, key: &'a str) -> Self {
+        Self {
+            key,
+            modifier: event.ctrl_key() || event.meta_key() || event.alt_key(),
+            typing: is_typing_target(event),
+            handled: event.default_prevented(),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+enum KeyAction {
+    Ignore,
+    OpenHelp,
+    CloseHelp,
+    /// Runs the shortcut at this index of the table.
+    Run(usize),
+}
+
+/// Decides what a chord does given whether the help is open and the keys of
+/// each shortcut. While the help is open only Escape and `?` do anything,
+/// even from a text field, and close it.
+fn resolve<'k>(chord: &Chord, help_open: bool, table: impl IntoIterator<Item = &'k [&'k str]>) -> KeyAction {
+    if chord.modifier || chord.handled {
+        return KeyAction::Ignore;
+    }
+    if help_open {
+        return if chord.key == "Escape" || chord.key == "?" {
+            KeyAction::CloseHelp
+        } else {
+            KeyAction::Ignore
+        };
+    }
+    if chord.typing {
+        return KeyAction::Ignore;
+    }
+    if chord.key == "?" {
+        return KeyAction::OpenHelp;
+    }
+    table
+        .into_iter()
+        .position(|keys| keys.contains(&chord.key))
+        .map_or(KeyAction::Ignore, KeyAction::Run)
+}
+
+/// Returns true when the key event comes from a control that needs the
+/// keystroke itself, such as a text field.
+fn is_typing_target(event: &web_sys::KeyboardEvent) -> bool {
+    let Some(target) = event.target() else {
+        return false;
+    };
+    if let Some(input) = target.dyn_ref::<web_sys::HtmlInputElement>() {
+        return !matches!(input.type_().as_str(), "checkbox" | "radio" | "button" | "submit");
+    }
+    if target.dyn_ref::<web_sys::HtmlTextAreaElement>().is_some()
+        || target.dyn_ref::<web_sys::HtmlSelectElement>().is_some()
+    {
+        return true;
+    }
+    target
+        .dyn_ref::<web_sys::HtmlElement>()
+        .is_some_and(|element| element.is_content_editable())
+}
+
+/// Registers a window-wide keydown listener for the given shortcuts and
+/// renders a help overlay listing them, toggled with `?`.
+#[component]
+pub fn KeyMap(shortcuts: Vec<Shortcut>) -> impl IntoView {
+    let (show_help, set_show_help) = signal(false);
+    let help_shortcuts = shortcuts.clone();
+    let dialog_ref = NodeRef::<leptos::html::Div>::new();
+    // The element that had focus before the help opened, focused again on
+    // close.
+    let opener = StoredValue::new_local(None::<web_sys::HtmlElement>);
+
+    let open_help = move || {
+        let active = document().active_element().and_then(|e| e.dyn_into::<web_sys::HtmlElement>().ok());
+        opener.set_value(active);
+        set_show_help.set(true);
+    };
+    let close_help = move || {
+        set_show_help.set(false);
+        if let Some(element) = opener.get_value() {
+            let _ = element.focus();
+        }
+    };
+
+    Effect::new(move |_| {
+        if let Some(dialog) = dialog_ref.get() {
+            let _ = dialog.focus();
+        }
+    });
+
+    let handle = window_event_listener(ev::keydown, move |event| {
+        let key = event.key();
+        let chord = Chord::from_event(&event, &key);
+        let action = resolve(&chord, show_help.get_untracked(), shortcuts.iter().map(|s| s.keys));
+        match action {
+            KeyAction::Ignore => return,
+            KeyAction::OpenHelp => open_help(),
+            KeyAction::CloseHelp => close_help(),
+            KeyAction::Run(index) => shortcuts[index].action.run(()),
+        }
+        event.prevent_default();
+    });
+    on_cleanup(move || handle.remove());
+
+    view! {
+        <Show when=move || show_help.get()>
+            <div class="dialog-backdrop" on:click=move |_| close_help()>
+                <div
+                    class="dialog shortcut-help"
+                    node_ref=dialog_ref
+                    tabindex="-1"
+                    role="dialog"
+                    aria-modal="true"
+                    aria-labelledby="shortcut-help-title"
+                    on:click=|ev| ev.stop_propagation()
+                >
+                    <h2 id="shortcut-help-title">"Keyboard Shortcuts"</h2>
+                    <dl class="shortcut-list">
+                        {help_shortcuts.iter().map(|shortcut| view! {
+                            <dt><kbd>{shortcut.label}</kbd></dt>
+                            <dd>{shortcut.description}</dd>
+                        }).collect_view()}
+                        <dt><kbd>"?"</kbd></dt>
+                        <dd>"Show or hide this help"</dd>
+                    </dl>
+                    <div class="dialog-actions">
+                        <button class="transfer-btn" on:click=move |_| close_help()>
+                            "Close"
+                        </button>
+                    </div>
+                </div>
+            </div>
+        </Show>
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TABLE: &[&[&str]] = &[&["j", "ArrowDown"], &["k", "ArrowUp"], &["Escape"], &["/"]];
+
+    fn press(key: &str) -> Chord<'_> {
+        Chord { key, ..Chord::default() }
+    }
+
+    fn run(chord: &Chord, help_open: bool) -> KeyAction {
+        resolve(chord, help_open, TABLE.iter().copied())
+    }
+
+    #[test]
+    fn keys_run_the_first_shortcut_listing_them() {
+        assert_eq!(run(&press("j"), false), KeyAction::Run(0));
+        assert_eq!(run(&press("ArrowDown"), false), KeyAction::Run(0));
+        assert_eq!(run(&press("ArrowUp"), false), KeyAction::Run(1));
+        assert_eq!(run(&press("/"), false), KeyAction::Run(3));
+        assert_eq!(run(&press("J"), false), KeyAction::Ignore);
+        assert_eq!(run(&press("q"), false), KeyAction::Ignore);
+    }
+
+    #[test]
+    fn modified_or_handled_keys_are_left_alone() {
+        let ctrl_j = Chord { modifier: true, ..press("j") };
+        assert_eq!(run(&ctrl_j, false), KeyAction::Ignore);
+        let ctrl_question = Chord { modifier: true, ..press("?") };
+        assert_eq!(run(&ctrl_question, false), KeyAction::Ignore);
+        assert_eq!(run(&Chord { modifier: true, ..press("Escape") }, true), KeyAction::Ignore);
+        assert_eq!(run(&Chord { handled: true, ..press("j") }, false), KeyAction::Ignore);
+    }
+
+    #[test]
+    fn keys_typed_in_fields_are_not_shortcuts() {
+        let typing = |key| Chord { typing: true, ..press(key) };
+        assert_eq!(run(&typing("j"), false), KeyAction::Ignore);
+        assert_eq!(run(&typing("/"), false), KeyAction::Ignore);
+        assert_eq!(run(&typing("?"), false), KeyAction::Ignore);
+        assert_eq!(run(&typing("Escape"), false), KeyAction::Ignore);
+    }
+
+    #[test]
+    fn question_mark_toggles_the_help() {
+        assert_eq!(run(&press("?"), false), KeyAction::OpenHelp);
+        assert_eq!(run(&press("?"), true), KeyAction::CloseHelp);
+    }
+
+    #[test]
+    fn escape_closes_the_help_before_anything_else() {
+        assert_eq!(run(&press("Escape"), true), KeyAction::CloseHelp);
+        assert_eq!(run(&Chord { typing: true, ..press("Escape") }, true), KeyAction::CloseHelp);
+        // Without the help open, Escape is an ordinary shortcut key.
+        assert_eq!(run(&press("Escape"), false), KeyAction::Run(2));
+        // Shortcuts don't fire behind the open help.
+        assert_eq!(run(&press("j"), true), KeyAction::Ignore);
+    }
+}
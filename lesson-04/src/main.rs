@@ -1,32 +1,315 @@
 use leptos::prelude::*;
 use leptos_meta::*;
 
-#[derive(Clone)]
+mod keymap;
+mod remote_sync;
+mod search;
+mod store;
+mod tab_sync;
+mod transfer;
+
+use keymap::{KeyMap, Shortcut};
+use leptos::html;
+use leptos::task::spawn_local;
+use remote_sync::{Conflict, PendingOp, PushResult, RestClient, SyncStatus};
+use search::{extract_tags, fuzzy_match, highlight_segments, FilterState};
+use serde::{Deserialize, Serialize};
+use store::{new_todo_id, now, Snapshot};
+use tab_sync::TabSync;
+use transfer::{Format, ImportedTodo, ParseReport};
+use wasm_bindgen::JsCast;
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 struct Todo {
     id: u32,
     text: String,
     completed: bool,
+    tags: Vec<String>,
+    updated_at: f64,
+    #[serde(default)]
+    version: u64,
 }
 
 #[component]
 fn TodoApp() -> impl IntoView {
-    let (todos, set_todos) = create_signal(Vec::new());
-    let (input_text, set_input_text) = create_signal(String::new());
-    let (filter, set_filter) = create_signal("all".to_string());
-    let next_id = RwSignal::new(1);
+    let stored = store::load();
+    let (todos, set_todos) = signal(stored.todos.clone());
+    let (tombstones, set_tombstones) = signal(stored.tombstones.clone());
+    let (input_text, set_input_text) = signal(String::new());
+    let initial_filters = FilterState::from_query_string(
+        &window().location().search().unwrap_or_default(),
+    );
+    let (filter, set_filter) = signal(initial_filters.filter);
+    let (search_query, set_search_query) = signal(initial_filters.query);
+    let (selected_tags, set_selected_tags) = signal(initial_filters.tags);
+    let last_published = StoredValue::new(stored.clone());
+    let sync_base = StoredValue::new(stored);
+    let tab_id = new_todo_id();
+    let outbox = RwSignal::new(remote_sync::adopt_outboxes(tab_id));
+    let conflicts = RwSignal::new(Vec::<Conflict>::new());
+    let (sync_endpoint, set_sync_endpoint) = signal(remote_sync::load_endpoint());
+    let (sync_status, set_sync_status) = signal(SyncStatus::Disabled);
+    let sync_running = StoredValue::new(false);
+
+    let tab_sync = StoredValue::new_local(TabSync::connect(tab_id, move |remote| {
+        let local = Snapshot {
+            todos: todos.get_untracked(),
+            tombstones: tombstones.get_untracked(),
+        };
+        let merged = store::merge(&local, &remote, now());
+        if merged != local {
+            last_published.set_value(merged.clone());
+            sync_base.set_value(merged.clone());
+            set_todos.set(merged.todos);
+            set_tombstones.set(merged.tombstones);
+        }
+    }));
+
+    Effect::new(move |_| {
+        let snapshot = Snapshot {
+            todos: todos.get(),
+            tombstones: tombstones.get(),
+        };
+        store::save(&snapshot);
+        if last_published.with_value(|published| *published != snapshot) {
+            tab_sync.with_value(|sync| {
+                if let Some(sync) = sync {
+                    sync.publish(&snapshot);
+                }
+            });
+            last_published.set_value(snapshot.clone());
+        }
+
+        let ops = sync_base.with_value(|base| remote_sync::diff(base, &snapshot));
+        sync_base.set_value(snapshot);
+        if !ops.is_empty() {
+            outbox.update(|outbox| {
+                for op in ops {
+                    remote_sync::enqueue(outbox, op);
+                }
+            });
+        }
+    });
+
+    let current_snapshot = move || Snapshot {
+        todos: todos.get_untracked(),
+        tombstones: tombstones.get_untracked(),
+    };
+
+    let apply_from_server = move |snapshot: Snapshot| {
+        sync_base.set_value(snapshot.clone());
+        set_todos.set(snapshot.todos);
+        set_tombstones.set(snapshot.tombstones);
+    };
+
+    let resolve_with_server = move |id: u32, server: Option<remote_sync::RemoteTodo>| {
+        let mut snapshot = current_snapshot();
+        match server {
+            Some(server) => {
+                snapshot.tombstones.retain(|t| t.id != id);
+                match snapshot.todos.iter_mut().find(|t| t.id == id) {
+                    Some(todo) => remote_sync::apply_remote(todo, server, now()),
+                    None => snapshot.todos.push(remote_sync::from_remote(server, now())),
+                }
+            }
+            None => {
+                snapshot.todos.retain(|t| t.id != id);
+                store::record_deletion(&mut snapshot.tombstones, id, now());
+            }
+        }
+        apply_from_server(snapshot);
+    };
+
+    let run_sync = move || {
+        let endpoint = sync_endpoint.get_untracked();
+        if endpoint.trim().is_empty() {
+            set_sync_status.set(SyncStatus::Disabled);
+            return;
+        }
+        if sync_running.get_value() {
+            return;
+        }
+        sync_running.set_value(true);
+
+        spawn_local(async move {
+            let mut endpoint = endpoint;
+            let mut client = RestClient::new(&endpoint);
+            let mut attempt = 0;
+
+            loop {
+                let latest = sync_endpoint.get_untracked();
+                if latest.trim().is_empty() {
+                    break;
+                }
+                if latest != endpoint {
+                    endpoint = latest;
+                    client = RestClient::new(&endpoint);
+                }
+                let Some(op) = outbox.with_untracked(|outbox| outbox.first().cloned()) else {
+                    set_sync_status.set(SyncStatus::Syncing);
+                    match client.pull().await {
+                        Ok(remote) => {
+                            let current = current_snapshot();
+                            let pulled = outbox.with_untracked(|outbox| {
+                                remote_sync::apply_pull(&current, remote, outbox, now())
+                            });
+                            if pulled != current {
+                                apply_from_server(pulled);
+                            }
+                            set_sync_status.set(SyncStatus::Synced);
+                        }
+                        Err(err) => set_sync_status.set(SyncStatus::Error(err)),
+                    }
+                    if outbox.with_untracked(|outbox| outbox.is_empty()) {
+                        break;
+                    }
+                    continue;
+                };
+
+                set_sync_status.set(SyncStatus::Syncing);
+                match client.push(&op).await {
+                    Ok(result) => {
+                        attempt = 0;
+                        let id = op.id();
+                        match result {
+                            PushResult::Applied(server) => {
+                                let version = server.map(|s| s.version);
+                                outbox.update(|outbox| remote_sync::acknowledge(outbox, &op, version));
+                                if let Some(version) = version {
+                                    let mut snapshot = current_snapshot();
+                                    if let Some(todo) = snapshot.todos.iter_mut().find(|t| t.id == id) {
+                                        todo.version = version;
+                                    }
+                                    apply_from_server(snapshot);
+                                }
+                            }
+                            PushResult::Conflict(server) => {
+                                outbox.update(|outbox| outbox.retain(|p| p.id() != id));
+                                let local = match &op {
+                                    PendingOp::Upsert { todo, .. } => Some(todo.clone()),
+                                    PendingOp::Delete { .. } => None,
+                                };
+                                conflicts.update(|conflicts| {
+                                    conflicts.retain(|c| c.id != id);
+                                    conflicts.push(Conflict { id, local, server: server.clone() });
+                                });
+                                resolve_with_server(id, server);
+                            }
+                            PushResult::Rejected(message) => {
+                                outbox.update(|outbox| {
+                                    if outbox.first() == Some(&op) {
+                                        outbox.remove(0);
+                                    }
+                                });
+                                set_sync_status.set(SyncStatus::Error(message));
+                            }
+                        }
+                    }
+                    Err(_) => {
+                        attempt += 1;
+                        let retry_in = remote_sync::backoff_delay(attempt);
+                        set_sync_status.set(SyncStatus::Offline { attempt, retry_in });
+                        gloo_timers::future::sleep(retry_in).await;
+                    }
+                }
+            }
+
+            sync_running.set_value(false);
+        });
+    };
+
+    Effect::new(move |_| {
+        sync_endpoint.track();
+        outbox.with(|outbox| remote_sync::save_outbox(tab_id, outbox));
+        run_sync();
+    });
+
+    // Keeps this tab's outbox marked as live and picks up operations left
+    // behind by tabs that closed or crashed before sending them.
+    let heartbeat = set_interval_with_handle(
+        move || {
+            let adopted = remote_sync::adopt_outboxes(tab_id);
+            if adopted.is_empty() {
+                outbox.with_untracked(|outbox| remote_sync::save_outbox(tab_id, outbox));
+            } else {
+                outbox.update(|outbox| remote_sync::merge_adopted(outbox, adopted));
+            }
+        },
+        std::time::Duration::from_millis(remote_sync::OUTBOX_HEARTBEAT_MS.into()),
+    )
+    .ok();
+    let pagehide_handle = window_event_listener(leptos::ev::pagehide, move |_| {
+        outbox.with_untracked(|outbox| remote_sync::release_outbox(tab_id, outbox));
+    });
+    on_cleanup(move || {
+        if let Some(heartbeat) = heartbeat {
+            heartbeat.clear();
+        }
+        pagehide_handle.remove();
+    });
+
+    let online_handle = window_event_listener(leptos::ev::online, move |_| run_sync());
+    on_cleanup(move || online_handle.remove());
+
+    let keep_local = move |conflict: Conflict| {
+        conflicts.update(|conflicts| conflicts.retain(|c| c.id != conflict.id));
+        match conflict.local {
+            Some(local) => {
+                let version = conflict.server.map(|s| s.version).unwrap_or(0);
+                set_tombstones.update(|tombstones| tombstones.retain(|t| t.id != local.id));
+                set_todos.update(|todos| {
+                    let todo = Todo { version, updated_at: now(), ..local };
+                    match todos.iter_mut().find(|t| t.id == todo.id) {
+                        Some(existing) => *existing = todo,
+                        None => todos.push(todo),
+                    }
+                });
+            }
+            None => {
+                let id = conflict.id;
+                set_todos.update(|todos| todos.retain(|t| t.id != id));
+                set_tombstones.update(|tombstones| store::record_deletion(tombstones, id, now()));
+            }
+        }
+    };
+
+    let save_endpoint = move |endpoint: String| {
+        let endpoint = endpoint.trim().to_string();
+        remote_sync::save_endpoint(&endpoint);
+        set_sync_endpoint.set(endpoint);
+    };
+
+    Effect::new(move |_| {
+        let state = FilterState {
+            filter: filter.get(),
+            query: search_query.get(),
+            tags: selected_tags.get(),
+        };
+        let location = window().location();
+        let url = format!(
+            "{}{}",
+            location.pathname().unwrap_or_default(),
+            state.to_query_string()
+        );
+        if let Ok(history) = window().history() {
+            let _ = history.replace_state_with_url(&wasm_bindgen::JsValue::NULL, "", Some(&url));
+        }
+    });
 
     let add_todo = move |_ev: leptos::ev::MouseEvent| {
         let text = input_text.get().trim().to_string();
         if !text.is_empty() {
             set_todos.update(|todos| {
                 todos.push(Todo {
-                    id: next_id.get(),
+                    id: new_todo_id(),
                     text: text.clone(),
                     completed: false,
+                    tags: extract_tags(&text),
+                    updated_at: now(),
+                    version: 0,
                 });
             });
             set_input_text.set(String::new());
-            next_id.update(|id| *id += 1);
         }
     };
 
@@ -34,6 +317,7 @@ fn TodoApp() -> impl IntoView {
         set_todos.update(|todos| {
             if let Some(todo) = todos.iter_mut().find(|t| t.id == id) {
                 todo.completed = !todo.completed;
+                todo.updated_at = now();
             }
         });
     };
@@ -42,35 +326,341 @@ fn TodoApp() -> impl IntoView {
         set_todos.update(|todos| {
             todos.retain(|todo| todo.id != id);
         });
+        set_tombstones.update(|tombstones| store::record_deletion(tombstones, id, now()));
     };
 
-    let filtered_todos = move || {
-        todos.get().into_iter()
+    let searched_todos = move || {
+        let query = search_query.get();
+        let mut matches = todos.get().into_iter()
             .filter(|todo| match filter.get().as_str() {
                 "active" => !todo.completed,
                 "completed" => todo.completed,
                 _ => true,
             })
+            .filter_map(|todo| fuzzy_match(&query, &todo.text).map(|m| (todo, m.score)))
+            .collect::<Vec<_>>();
+        if !query.trim().is_empty() {
+            matches.sort_by_key(|m| std::cmp::Reverse(m.1));
+        }
+        matches.into_iter().map(|(todo, _)| todo).collect::<Vec<_>>()
+    };
+
+    let filtered_todos = move || {
+        let tags = selected_tags.get();
+        searched_todos().into_iter()
+            .filter(|todo| tags.iter().all(|tag| todo.tags.contains(tag)))
             .collect::<Vec<_>>()
     };
 
+    let tag_facets = move || {
+        let mut counts = std::collections::BTreeMap::new();
+        for todo in todos.get().iter() {
+            for tag in todo.tags.iter() {
+                counts.entry(tag.clone()).or_insert(0);
+            }
+        }
+        for todo in searched_todos().iter() {
+            for tag in todo.tags.iter() {
+                *counts.entry(tag.clone()).or_insert(0) += 1;
+            }
+        }
+        counts.into_iter().collect::<Vec<(String, usize)>>()
+    };
+
+    let toggle_tag = move |tag: String| {
+        set_selected_tags.update(|tags| {
+            if let Some(pos) = tags.iter().position(|t| *t == tag) {
+                tags.remove(pos);
+            } else {
+                tags.push(tag);
+            }
+        });
+    };
+
+    let clear_search = move |_| {
+        set_search_query.set(String::new());
+        set_selected_tags.set(Vec::new());
+    };
+
     let total_todos = move || todos.get().len();
     let completed_todos = move || todos.get().iter().filter(|todo| todo.completed).count();
     let active_todos = move || total_todos() - completed_todos();
 
     let clear_completed = move |_| {
+        let deleted_at = now();
+        let removed: Vec<u32> = todos.get().iter()
+            .filter(|todo| todo.completed)
+            .map(|todo| todo.id)
+            .collect();
         set_todos.update(|todos| {
             todos.retain(|todo| !todo.completed);
         });
+        set_tombstones.update(|tombstones| {
+            for id in removed {
+                store::record_deletion(tombstones, id, deleted_at);
+            }
+        });
     };
 
+    let (import_report, set_import_report) = signal(None::<ParseReport>);
+    let (import_error, set_import_error) = signal(None::<String>);
+    let (replace_duplicates, set_replace_duplicates) = signal(false);
+
+    let normalize = |text: &str| text.trim().to_lowercase();
+
+    let import_preview = move || {
+        let existing: Vec<String> = todos.get().iter().map(|todo| normalize(&todo.text)).collect();
+        let mut seen: Vec<String> = Vec::new();
+        import_report.get()
+            .map(|report| report.todos)
+            .unwrap_or_default()
+            .into_iter()
+            .map(|todo| {
+                let key = normalize(&todo.text);
+                let duplicate = existing.contains(&key) || seen.contains(&key);
+                seen.push(key);
+                (todo, duplicate)
+            })
+            .collect::<Vec<(ImportedTodo, bool)>>()
+    };
+
+    let export_todos = move |format: Format| {
+        let contents = transfer::export(format, &todos.get());
+        if transfer::download(format, &contents).is_err() {
+            set_import_error.set(Some("Export failed".to_string()));
+        }
+    };
+
+    let on_import_file = move |ev: leptos::ev::Event| {
+        let input: web_sys::HtmlInputElement = event_target(&ev);
+        let Some(file) = input.files().and_then(|files| files.get(0)) else {
+            return;
+        };
+        input.set_value("");
+
+        let Some(format) = Format::from_file_name(&file.name()) else {
+            set_import_error.set(Some(format!(
+                "Unsupported file type: {} (expected .json, .csv or .txt)",
+                file.name()
+            )));
+            return;
+        };
+
+        spawn_local(async move {
+            match transfer::read_file(file).await {
+                Ok(contents) => {
+                    set_import_error.set(None);
+                    set_import_report.set(Some(transfer::parse(format, &contents)));
+                }
+                Err(err) => set_import_error.set(Some(err)),
+            }
+        });
+    };
+
+    let confirm_import = move |_| {
+        let replace = replace_duplicates.get();
+        let preview = import_preview();
+        let mut seen: Vec<String> = Vec::new();
+        set_todos.update(|todos| {
+            for imported in preview.into_iter().map(|(todo, _)| todo) {
+                let key = normalize(&imported.text);
+                if seen.contains(&key) {
+                    continue;
+                }
+                seen.push(key.clone());
+
+                if let Some(existing) = todos.iter_mut().find(|t| normalize(&t.text) == key) {
+                    if replace {
+                        existing.completed = imported.completed;
+                        existing.tags = imported.tags;
+                        existing.updated_at = now();
+                    }
+                } else {
+                    todos.push(Todo {
+                        id: new_todo_id(),
+                        text: imported.text,
+                        completed: imported.completed,
+                        tags: imported.tags,
+                        updated_at: now(),
+                        version: 0,
+                    });
+                }
+            }
+        });
+        set_import_report.set(None);
+    };
+
+    let focused_id = RwSignal::new(None::<u32>);
+    let editing_id = RwSignal::new(None::<u32>);
+    let edit_text = RwSignal::new(String::new());
+    let search_ref = NodeRef::<html::Input>::new();
+
+    let todo_completed = move |id: u32| {
+        todos.with(|todos| todos.iter().find(|t| t.id == id).is_some_and(|t| t.completed))
+    };
+    let todo_text = move |id: u32| {
+        todos.with(|todos| todos.iter().find(|t| t.id == id).map(|t| t.text.clone()))
+            .unwrap_or_default()
+    };
+
+    let focus_element = |element_id: String| {
+        request_animation_frame(move || {
+            if let Some(element) = document()
+                .get_element_by_id(&element_id)
+                .and_then(|el| el.dyn_into::<web_sys::HtmlElement>().ok())
+            {
+                let _ = element.focus();
+            }
+        });
+    };
+
+    let focus_todo = move |id: u32| {
+        focused_id.set(Some(id));
+        focus_element(format!("todo-{}", id));
+    };
+
+    let move_focus = move |delta: isize| {
+        let visible = filtered_todos();
+        if visible.is_empty() {
+            return;
+        }
+        let current = focused_id.get_untracked()
+            .and_then(|id| visible.iter().position(|t| t.id == id));
+        let next = match current {
+            Some(index) => (index as isize + delta).clamp(0, visible.len() as isize - 1) as usize,
+            None if delta < 0 => visible.len() - 1,
+            None => 0,
+        };
+        focus_todo(visible[next].id);
+    };
+
+    let start_edit = move |id: u32| {
+        edit_text.set(todo_text(id));
+        editing_id.set(Some(id));
+        focus_element(format!("todo-edit-{}", id));
+    };
+
+    let finish_edit = move |save: bool| {
+        let Some(id) = editing_id.get_untracked() else {
+            return;
+        };
+        let text = edit_text.get_untracked().trim().to_string();
+        if save && !text.is_empty() {
+            set_todos.update(|todos| {
+                if let Some(todo) = todos.iter_mut().find(|t| t.id == id) {
+                    todo.tags = extract_tags(&text);
+                    todo.text = text;
+                    todo.updated_at = now();
+                }
+            });
+        }
+        editing_id.set(None);
+        focus_todo(id);
+    };
+
+    let delete_focused = move || {
+        let Some(id) = focused_id.get_untracked() else {
+            return;
+        };
+        let visible = filtered_todos();
+        let index = visible.iter().position(|t| t.id == id);
+        delete_todo(id);
+        let remaining: Vec<u32> = visible.iter().map(|t| t.id).filter(|t| *t != id).collect();
+        match index.and_then(|i| remaining.get(i.min(remaining.len().saturating_sub(1)))) {
+            Some(next) => focus_todo(*next),
+            None => focused_id.set(None),
+        }
+    };
+
+    let shortcuts = vec![
+        Shortcut::new(&["j", "ArrowDown"], "j", "Move to the next task", move || move_focus(1)),
+        Shortcut::new(&["k", "ArrowUp"], "k", "Move to the previous task", move || move_focus(-1)),
+        Shortcut::new(&["x"], "x", "Toggle the focused task", move || {
+            if let Some(id) = focused_id.get_untracked() {
+                toggle_todo(id);
+            }
+        }),
+        Shortcut::new(&["e"], "e", "Edit the focused task", move || {
+            if let Some(id) = focused_id.get_untracked() {
+                start_edit(id);
+            }
+        }),
+        Shortcut::new(&["Delete"], "Delete", "Remove the focused task", delete_focused),
+        Shortcut::new(&["/"], "/", "Focus the search box", move || {
+            if let Some(input) = search_ref.get_untracked() {
+                let _ = input.focus();
+            }
+        }),
+    ];
+
     view! {
         <div class="app-container">
+            <KeyMap shortcuts=shortcuts/>
             <div class="header">
                 <h1>"Todo List"</h1>
                 <p>"Organize your tasks efficiently"</p>
+                <div
+                    class=move || format!("sync-status {}", sync_status.get().css_class())
+                    role="status"
+                    aria-live="polite"
+                >
+                    {move || sync_status.get().label()}
+                    {move || {
+                        let pending = outbox.with(|outbox| outbox.len());
+                        (pending > 0).then(|| format!(" ({} queued)", pending))
+                    }}
+                </div>
             </div>
 
+            <details class="sync-settings">
+                <summary>"Sync settings"</summary>
+                <div class="sync-settings-row">
+                    <input
+                        class="search-input"
+                        type="url"
+                        placeholder="http://localhost:8787"
+                        prop:value=sync_endpoint
+                        on:change=move |ev| save_endpoint(event_target_value(&ev))
+                    />
+                    <button class="transfer-btn" on:click=move |_| run_sync()>
+                        "Sync now"
+                    </button>
+                </div>
+                <p class="sync-hint">"Leave empty to keep todos on this device only."</p>
+            </details>
+
+            <Show when=move || !conflicts.get().is_empty()>
+                <div class="sync-conflicts" role="alert">
+                    <h3>"Sync conflicts"</h3>
+                    <p>"These tasks changed on the server while you were editing. The server's version is shown now."</p>
+                    <ul>
+                        {move || conflicts.get().into_iter().map(|conflict| {
+                            let description = match (&conflict.local, &conflict.server) {
+                                (Some(local), Some(server)) => format!("\"{}\" vs server \"{}\"", local.text, server.text),
+                                (Some(local), None) => format!("\"{}\" was deleted on the server", local.text),
+                                (None, Some(server)) => format!("You deleted \"{}\" but it changed on the server", server.text),
+                                (None, None) => "Unknown change".to_string(),
+                            };
+                            let id = conflict.id;
+                            view! {
+                                <li class="sync-conflict">
+                                    <span>{description}</span>
+                                    <button class="transfer-btn" on:click=move |_| keep_local(conflict.clone())>
+                                        "Keep mine"
+                                    </button>
+                                    <button
+                                        class="transfer-btn"
+                                        on:click=move |_| conflicts.update(|conflicts| conflicts.retain(|c| c.id != id))
+                                    >
+                                        "Use server"
+                                    </button>
+                                </li>
+                            }
+                        }).collect_view()}
+                    </ul>
+                </div>
+            </Show>
+
             <div class="add-todo">
                 <input
                     class="todo-input"
@@ -84,13 +674,15 @@ fn TodoApp() -> impl IntoView {
                             if !text.is_empty() {
                                 set_todos.update(|todos| {
                                     todos.push(Todo {
-                                        id: next_id.get(),
+                                        id: new_todo_id(),
                                         text: text.clone(),
                                         completed: false,
+                                        tags: extract_tags(&text),
+                                        updated_at: now(),
+                                        version: 0,
                                     });
                                 });
                                 set_input_text.set(String::new());
-                                next_id.update(|id| *id += 1);
                             }
                         }
                     }
@@ -100,6 +692,54 @@ fn TodoApp() -> impl IntoView {
                 </button>
             </div>
 
+            <div class="search-bar">
+                <input
+                    class="search-input"
+                    type="search"
+                    node_ref=search_ref
+                    aria-keyshortcuts="/"
+                    placeholder="Search tasks..."
+                    prop:value=search_query
+                    on:input=move |ev| set_search_query.set(event_target_value(&ev))
+                    on:keydown=move |ev| {
+                        if ev.key() == "Escape"
+                            && let Some(input) = search_ref.get_untracked()
+                        {
+                            let _ = input.blur();
+                        }
+                    }
+                />
+                <Show when=move || !search_query.get().is_empty() || !selected_tags.get().is_empty()>
+                    <button class="clear-search-btn" on:click=clear_search>
+                        "Clear"
+                    </button>
+                </Show>
+            </div>
+
+            <Show when=move || !tag_facets().is_empty()>
+                <div class="tag-facets">
+                    <For
+                        each=tag_facets
+                        key=|(tag, count)| (tag.clone(), *count)
+                        children=move |(tag, count)| {
+                            let tag_for_class = tag.clone();
+                            let tag_for_click = tag.clone();
+                            view! {
+                                <button
+                                    class="tag-facet"
+                                    class:active=move || selected_tags.get().contains(&tag_for_class)
+                                    disabled={count == 0}
+                                    on:click=move |_| toggle_tag(tag_for_click.clone())
+                                >
+                                    {format!("#{}", tag)}
+                                    <span class="tag-count">{count}</span>
+                                </button>
+                            }
+                        }
+                    />
+                </div>
+            </Show>
+
             <div class="filters">
                 <button
                     class="filter-btn"
@@ -124,21 +764,86 @@ fn TodoApp() -> impl IntoView {
                 </button>
             </div>
 
-            <ul class="todo-list">
+            <ul
+                class="todo-list"
+                role="listbox"
+                aria-label="Tasks"
+                aria-keyshortcuts="j k x e Delete"
+            >
                 <For
                     each=filtered_todos
                     key=|todo| todo.id
                     children=move |todo| {
+                        let id = todo.id;
+                        let is_focused = move || focused_id.get() == Some(id);
                         view! {
-                            <li class="todo-item" class:completed=todo.completed>
+                            <li
+                                id=format!("todo-{}", id)
+                                class="todo-item"
+                                class:completed=move || todo_completed(id)
+                                class:focused=is_focused
+                                role="option"
+                                aria-selected=move || is_focused().to_string()
+                                aria-checked=move || todo_completed(id).to_string()
+                                tabindex=move || {
+                                    let first = filtered_todos().first().map(|t| t.id);
+                                    if is_focused() || (focused_id.get().is_none() && first == Some(id)) {
+                                        "0"
+                                    } else {
+                                        "-1"
+                                    }
+                                }
+                                on:focus=move |_| focused_id.set(Some(id))
+                            >
                                 <input
                                     class="todo-checkbox"
                                     type="checkbox"
-                                    checked=todo.completed
-                                    on:change=move |_| toggle_todo(todo.id)
+                                    tabindex="-1"
+                                    aria-label="Toggle task"
+                                    prop:checked=move || todo_completed(id)
+                                    on:change=move |_| toggle_todo(id)
                                 />
-                                <span class="todo-text">{todo.text.clone()}</span>
-                                <button class="delete-btn" on:click=move |_| delete_todo(todo.id)>
+                                <Show
+                                    when=move || editing_id.get() == Some(id)
+                                    fallback=move || view! {
+                                        <span class="todo-text" on:dblclick=move |_| start_edit(id)>
+                                            {move || {
+                                                let text = todo_text(id);
+                                                let indices = fuzzy_match(&search_query.get(), &text)
+                                                    .map(|m| m.indices)
+                                                    .unwrap_or_default();
+                                                highlight_segments(&text, &indices)
+                                                    .into_iter()
+                                                    .map(|(segment, highlighted)| if highlighted {
+                                                        view! { <mark>{segment}</mark> }.into_any()
+                                                    } else {
+                                                        segment.into_any()
+                                                    })
+                                                    .collect_view()
+                                            }}
+                                        </span>
+                                    }
+                                >
+                                    <input
+                                        id=format!("todo-edit-{}", id)
+                                        class="todo-edit-input"
+                                        type="text"
+                                        aria-label="Edit task"
+                                        prop:value=move || edit_text.get()
+                                        on:input=move |ev| edit_text.set(event_target_value(&ev))
+                                        on:keydown=move |ev| match ev.key().as_str() {
+                                            "Enter" => finish_edit(true),
+                                            "Escape" => finish_edit(false),
+                                            _ => {}
+                                        }
+                                        on:blur=move |_| {
+                                            if editing_id.get_untracked() == Some(id) {
+                                                finish_edit(true);
+                                            }
+                                        }
+                                    />
+                                </Show>
+                                <button class="delete-btn" tabindex="-1" on:click=move |_| delete_todo(id)>
                                     "Delete"
                                 </button>
                             </li>
@@ -147,14 +852,20 @@ fn TodoApp() -> impl IntoView {
                 />
             </ul>
 
+            <p class="shortcut-hint">"Press " <kbd>"?"</kbd> " for keyboard shortcuts"</p>
+
             {move || if filtered_todos().is_empty() {
                 Some(view! {
                     <div class="empty-state">
                         <div class="empty-text">
-                            {match filter.get().as_str() {
-                                "active" => "No active tasks!",
-                                "completed" => "No completed tasks!",
-                                _ => "No tasks yet. Add one above!",
+                            {if !search_query.get().is_empty() || !selected_tags.get().is_empty() {
+                                "No tasks match your search."
+                            } else {
+                                match filter.get().as_str() {
+                                    "active" => "No active tasks!",
+                                    "completed" => "No completed tasks!",
+                                    _ => "No tasks yet. Add one above!",
+                                }
                             }}
                         </div>
                     </div>
@@ -186,6 +897,95 @@ fn TodoApp() -> impl IntoView {
                 None
             }}
 
+            <div class="transfer-bar">
+                <div class="transfer-group">
+                    <span class="transfer-label">"Export:"</span>
+                    <button class="transfer-btn" on:click=move |_| export_todos(Format::Json)>"JSON"</button>
+                    <button class="transfer-btn" on:click=move |_| export_todos(Format::Csv)>"CSV"</button>
+                    <button class="transfer-btn" on:click=move |_| export_todos(Format::TodoTxt)>"todo.txt"</button>
+                </div>
+                <label class="transfer-btn import-btn">
+                    "Import..."
+                    <input
+                        type="file"
+                        accept=".json,.csv,.txt"
+                        style="display: none;"
+                        on:change=on_import_file
+                    />
+                </label>
+            </div>
+
+            {move || import_error.get().map(|err| view! {
+                <div class="transfer-error">{err}</div>
+            })}
+
+            <Show when=move || import_report.get().is_some()>
+                <div class="dialog-backdrop">
+                    <div class="dialog" role="dialog" aria-modal="true" aria-labelledby="import-title">
+                        <h2 id="import-title">"Import Preview"</h2>
+                        <p class="dialog-summary">
+                            {move || {
+                                let preview = import_preview();
+                                let duplicates = preview.iter().filter(|(_, duplicate)| *duplicate).count();
+                                let errors = import_report.get().map(|r| r.errors.len()).unwrap_or(0);
+                                format!(
+                                    "{} tasks found: {} new, {} duplicates, {} errors",
+                                    preview.len(),
+                                    preview.len() - duplicates,
+                                    duplicates,
+                                    errors
+                                )
+                            }}
+                        </p>
+
+                        <ul class="import-list">
+                            {move || import_preview().into_iter().map(|(todo, duplicate)| view! {
+                                <li class="import-item" class:duplicate=duplicate>
+                                    <span class="import-status">
+                                        {if duplicate { "Duplicate" } else { "New" }}
+                                    </span>
+                                    <span class="import-text">{todo.text}</span>
+                                    {todo.completed.then(|| view! { <span class="import-done">"done"</span> })}
+                                </li>
+                            }).collect_view()}
+                        </ul>
+
+                        {move || {
+                            let errors = import_report.get().map(|r| r.errors).unwrap_or_default();
+                            (!errors.is_empty()).then(|| view! {
+                                <ul class="import-errors">
+                                    {errors.into_iter().map(|err| view! {
+                                        <li>{format!("{}: {}", err.location, err.message)}</li>
+                                    }).collect_view()}
+                                </ul>
+                            })
+                        }}
+
+                        <label class="dialog-option">
+                            <input
+                                type="checkbox"
+                                prop:checked=replace_duplicates
+                                on:change=move |ev| set_replace_duplicates.set(event_target_checked(&ev))
+                            />
+                            "Update existing tasks with imported status and tags"
+                        </label>
+
+                        <div class="dialog-actions">
+                            <button class="transfer-btn" on:click=move |_| set_import_report.set(None)>
+                                "Cancel"
+                            </button>
+                            <button
+                                class="add-btn"
+                                disabled=move || import_preview().is_empty()
+                                on:click=confirm_import
+                            >
+                                "Import"
+                            </button>
+                        </div>
+                    </div>
+                </div>
+            </Show>
+
             <div class="stats">
                 <div class="stat">
                     <span class="stat-value">{total_todos}</span>
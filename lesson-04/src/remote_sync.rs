@@ -0,0 +1,565 @@
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+
+use crate::store::Snapshot;
+use crate::Todo;
+
+const ENDPOINT_KEY: &str = "lesson-04.sync-endpoint";
+const LEGACY_OUTBOX_KEY: &str = "lesson-04.outbox";
+const OUTBOX_PREFIX: &str = "lesson-04.outbox.";
+/// How long a tab's outbox may go without a heartbeat before another tab
+/// takes its operations over.
+pub const OUTBOX_HEARTBEAT_MS: u32 = 10_000;
+const OUTBOX_STALE_MS: f64 = 3.0 * OUTBOX_HEARTBEAT_MS as f64;
+const BASE_RETRY_MS: u64 = 1_000;
+const MAX_RETRY_MS: u64 = 60_000;
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum SyncStatus {
+    Disabled,
+    Synced,
+    Syncing,
+    Offline { attempt: u32, retry_in: Duration },
+    Error(String),
+}
+
+impl SyncStatus {
+    pub fn label(&self) -> String {
+        match self {
+            SyncStatus::Disabled => "Sync off".to_string(),
+            SyncStatus::Synced => "Synced".to_string(),
+            SyncStatus::Syncing => "Syncing...".to_string(),
+            SyncStatus::Offline { attempt, retry_in } => format!(
+                "Offline, retry {} in {}s",
+                attempt,
+                retry_in.as_secs().max(1)
+            ),
+            SyncStatus::Error(message) => format!("Sync error: {}", message),
+        }
+    }
+
+    pub fn css_class(&self) -> &'static str {
+        match self {
+            SyncStatus::Disabled => "sync-disabled",
+            SyncStatus::Synced => "sync-ok",
+            SyncStatus::Syncing => "sync-busy",
+            SyncStatus::Offline { .. } => "sync-offline",
+            SyncStatus::Error(_) => "sync-error",
+        }
+    }
+}
+
+/// A todo as the server stores it. `version` is bumped by the server on
+/// every accepted write.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct RemoteTodo {
+    pub id: u32,
+    pub text: String,
+    pub completed: bool,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    pub version: u64,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum PendingOp {
+    Upsert { todo: Todo, base_version: u64 },
+    Delete { id: u32, base_version: u64 },
+}
+
+impl PendingOp {
+    pub fn id(&self) -> u32 {
+        match self {
+            PendingOp::Upsert { todo, .. } => todo.id,
+            PendingOp::Delete { id, .. } => *id,
+        }
+    }
+
+    pub fn rebase(&mut self, version: u64) {
+        match self {
+            PendingOp::Upsert { base_version, .. } | PendingOp::Delete { base_version, .. } => {
+                *base_version = version;
+            }
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Conflict {
+    pub id: u32,
+    pub local: Option<Todo>,
+    pub server: Option<RemoteTodo>,
+}
+
+pub enum PushResult {
+    Applied(Option<RemoteTodo>),
+    Conflict(Option<RemoteTodo>),
+    Rejected(String),
+}
+
+#[derive(Serialize)]
+struct UpsertBody<'a> {
+    text: &'a str,
+    completed: bool,
+    tags: &'a [String],
+    base_version: u64,
+}
+
+fn local_storage() -> Option<web_sys::Storage> {
+    leptos::prelude::window().local_storage().ok().flatten()
+}
+
+pub fn load_endpoint() -> String {
+    local_storage()
+        .and_then(|storage| storage.get_item(ENDPOINT_KEY).ok().flatten())
+        .unwrap_or_default()
+}
+
+pub fn save_endpoint(endpoint: &str) {
+    if let Some(storage) = local_storage() {
+        let _ = storage.set_item(ENDPOINT_KEY, endpoint);
+    }
+}
+
+/// A tab's pending operations as stored. `heartbeat` is refreshed while the
+/// tab is open and zeroed when it goes away.
+#[derive(Serialize, Deserialize)]
+struct StoredOutbox {
+    heartbeat: f64,
+    ops: Vec<PendingOp>,
+}
+
+fn outbox_key(tab_id: u32) -> String {
+    format!("{}{}", OUTBOX_PREFIX, tab_id)
+}
+
+/// Stores this tab's outbox under its own key, so tabs never overwrite
+/// each other's pending operations.
+pub fn save_outbox(tab_id: u32, outbox: &[PendingOp]) {
+    write_outbox(tab_id, outbox, crate::store::now());
+}
+
+/// Marks this tab's outbox as abandoned so the next tab to look takes it
+/// over straight away, e.g. when the tab is closed or reloaded.
+pub fn release_outbox(tab_id: u32, outbox: &[PendingOp]) {
+    write_outbox(tab_id, outbox, 0.0);
+}
+
+fn write_outbox(tab_id: u32, ops: &[PendingOp], heartbeat: f64) {
+    let Some(storage) = local_storage() else {
+        return;
+    };
+    let key = outbox_key(tab_id);
+    if ops.is_empty() {
+        let _ = storage.remove_item(&key);
+        return;
+    }
+    let stored = StoredOutbox {
+        heartbeat,
+        ops: ops.to_vec(),
+    };
+    if let Ok(json) = serde_json::to_string(&stored) {
+        let _ = storage.set_item(&key, &json);
+    }
+}
+
+/// Removes and returns the operations of tabs that have stopped sending
+/// heartbeats, oldest first, so whichever tab is still open sends them.
+pub fn adopt_outboxes(tab_id: u32) -> Vec<PendingOp> {
+    let Some(storage) = local_storage() else {
+        return Vec::new();
+    };
+    let own = outbox_key(tab_id);
+    let keys: Vec<String> = (0..storage.length().unwrap_or(0))
+        .filter_map(|i| storage.key(i).ok().flatten())
+        .filter(|key| key.starts_with(OUTBOX_PREFIX) && *key != own)
+        .collect();
+
+    let cutoff = crate::store::now() - OUTBOX_STALE_MS;
+    let mut abandoned: Vec<StoredOutbox> = keys
+        .iter()
+        .filter_map(|key| {
+            let json = storage.get_item(key).ok().flatten()?;
+            match serde_json::from_str::<StoredOutbox>(&json) {
+                Ok(stored) if stored.heartbeat > cutoff => None,
+                stored => {
+                    let _ = storage.remove_item(key);
+                    stored.ok()
+                }
+            }
+        })
+        .collect();
+    // Outboxes written before they were kept per tab.
+    if let Some(json) = storage.get_item(LEGACY_OUTBOX_KEY).ok().flatten() {
+        let _ = storage.remove_item(LEGACY_OUTBOX_KEY);
+        if let Ok(ops) = serde_json::from_str(&json) {
+            abandoned.push(StoredOutbox { heartbeat: 0.0, ops });
+        }
+    }
+    abandoned.sort_by(|a, b| a.heartbeat.total_cmp(&b.heartbeat));
+    abandoned.into_iter().flat_map(|stored| stored.ops).collect()
+}
+
+/// Puts adopted operations ahead of this tab's own, which are newer.
+pub fn merge_adopted(outbox: &mut Vec<PendingOp>, adopted: Vec<PendingOp>) {
+    let own = std::mem::take(outbox);
+    for op in adopted.into_iter().chain(own) {
+        enqueue(outbox, op);
+    }
+}
+
+fn same_content(a: &Todo, b: &Todo) -> bool {
+    a.text == b.text && a.completed == b.completed && a.tags == b.tags
+}
+
+/// Lists the operations needed to bring the server from `base` to
+/// `current`. Changes to versions or timestamps alone are not operations.
+pub fn diff(base: &Snapshot, current: &Snapshot) -> Vec<PendingOp> {
+    let mut ops = Vec::new();
+    for todo in current.todos.iter() {
+        let unchanged = base
+            .todos
+            .iter()
+            .find(|t| t.id == todo.id)
+            .is_some_and(|previous| same_content(previous, todo));
+        if !unchanged {
+            ops.push(PendingOp::Upsert {
+                todo: todo.clone(),
+                base_version: todo.version,
+            });
+        }
+    }
+    for todo in base.todos.iter() {
+        if !current.todos.iter().any(|t| t.id == todo.id) {
+            ops.push(PendingOp::Delete {
+                id: todo.id,
+                base_version: todo.version,
+            });
+        }
+    }
+    ops
+}
+
+/// Adds `op` to the outbox, collapsing it with any pending operation for
+/// the same todo so only the latest intent is sent. The head of the outbox
+/// may already be on its way to the server, so it is never rewritten; new
+/// operations for its todo queue up behind it instead.
+pub fn enqueue(outbox: &mut Vec<PendingOp>, op: PendingOp) {
+    let index = match outbox.iter().rposition(|pending| pending.id() == op.id()) {
+        Some(index) if index > 0 => index,
+        _ => {
+            outbox.push(op);
+            return;
+        }
+    };
+    let pending_base = match &outbox[index] {
+        PendingOp::Upsert { base_version, .. } => Some(*base_version),
+        PendingOp::Delete { .. } => None,
+    };
+    match (pending_base, op) {
+        (Some(0), PendingOp::Delete { .. }) => {
+            outbox.remove(index);
+        }
+        (Some(base_version), PendingOp::Upsert { todo, .. }) => {
+            outbox[index] = PendingOp::Upsert { todo, base_version };
+        }
+        (_, op) => outbox[index] = op,
+    }
+}
+
+/// Drops `op` from the outbox once the server applied it and moves the
+/// operations queued behind it for the same todo onto `version`.
+pub fn acknowledge(outbox: &mut Vec<PendingOp>, op: &PendingOp, version: Option<u64>) {
+    if let Some(index) = outbox.iter().position(|pending| pending == op) {
+        outbox.remove(index);
+    }
+    if let Some(version) = version {
+        for pending in outbox.iter_mut().filter(|pending| pending.id() == op.id()) {
+            pending.rebase(version);
+        }
+    }
+}
+
+/// Folds the server's list into the local snapshot, stamping changed todos
+/// with `now`. Todos with pending operations are left alone; the outbox will
+/// settle them.
+pub fn apply_pull(local: &Snapshot, remote: Vec<RemoteTodo>, outbox: &[PendingOp], now: f64) -> Snapshot {
+    let pending = |id: u32| outbox.iter().any(|op| op.id() == id);
+    let mut snapshot = local.clone();
+
+    snapshot.todos.retain(|todo| {
+        pending(todo.id) || todo.version == 0 || remote.iter().any(|r| r.id == todo.id)
+    });
+
+    for server in remote {
+        if pending(server.id) || snapshot.tombstones.iter().any(|t| t.id == server.id) {
+            continue;
+        }
+        match snapshot.todos.iter_mut().find(|t| t.id == server.id) {
+            Some(todo) if server.version > todo.version => apply_remote(todo, server, now),
+            Some(_) => {}
+            None => snapshot.todos.push(from_remote(server, now)),
+        }
+    }
+    snapshot
+}
+
+pub fn apply_remote(todo: &mut Todo, server: RemoteTodo, now: f64) {
+    todo.text = server.text;
+    todo.completed = server.completed;
+    todo.tags = server.tags;
+    todo.version = server.version;
+    todo.updated_at = now;
+}
+
+pub fn from_remote(server: RemoteTodo, now: f64) -> Todo {
+    let mut todo = Todo {
+        id: server.id,
+        text: String::new(),
+        completed: false,
+        tags: Vec::new(),
+        updated_at: 0.0,
+        version: 0,
+    };
+    apply_remote(&mut todo, server, now);
+    todo
+}
+
+/// Exponential backoff with a little jitter so several tabs coming back
+/// online don't retry in lockstep.
+pub fn backoff_delay(attempt: u32) -> Duration {
+    let exponential = BASE_RETRY_MS.saturating_mul(1 << attempt.min(16));
+    let capped = exponential.min(MAX_RETRY_MS);
+    let jitter = (js_sys::Math::random() * capped as f64 * 0.2) as u64;
+    Duration::from_millis(capped + jitter)
+}
+
+pub struct RestClient {
+    endpoint: String,
+    client: reqwest::Client,
+}
+
+impl RestClient {
+    pub fn new(endpoint: &str) -> Self {
+        Self {
+            endpoint: endpoint.trim_end_matches('/').to_string(),
+            client: reqwest::Client::new(),
+        }
+    }
+
+    pub async fn pull(&self) -> Result<Vec<RemoteTodo>, String> {
+        let response = self
+            .client
+            .get(format!("{}/todos", self.endpoint))
+            .send()
+            .await
+            .map_err(|e| format!("Network error: {}", e))?;
+
+        if !response.status().is_success() {
+            return Err(format!("Server error: {}", response.status()));
+        }
+
+        response
+            .json::<Vec<RemoteTodo>>()
+            .await
+            .map_err(|e| format!("Failed to parse response: {}", e))
+    }
+
+    /// Sends one outbox entry. `Err` means the request never got an
+    /// answer and should be retried.
+    pub async fn push(&self, op: &PendingOp) -> Result<PushResult, String> {
+        let request = match op {
+            PendingOp::Upsert { todo, base_version } => self
+                .client
+                .put(format!("{}/todos/{}", self.endpoint, todo.id))
+                .json(&UpsertBody {
+                    text: &todo.text,
+                    completed: todo.completed,
+                    tags: &todo.tags,
+                    base_version: *base_version,
+                }),
+            PendingOp::Delete { id, base_version } => self.client.delete(format!(
+                "{}/todos/{}?base_version={}",
+                self.endpoint, id, base_version
+            )),
+        };
+
+        let response = request
+            .send()
+            .await
+            .map_err(|e| format!("Network error: {}", e))?;
+        let status = response.status();
+
+        if status.is_server_error() {
+            return Err(format!("Server error: {}", status));
+        }
+        if status == reqwest::StatusCode::CONFLICT {
+            return Ok(PushResult::Conflict(response.json::<RemoteTodo>().await.ok()));
+        }
+        if status == reqwest::StatusCode::NOT_FOUND {
+            return Ok(match op {
+                PendingOp::Delete { .. } => PushResult::Applied(None),
+                PendingOp::Upsert { .. } => PushResult::Conflict(None),
+            });
+        }
+        if !status.is_success() {
+            return Ok(PushResult::Rejected(format!("Server rejected change: {}", status)));
+        }
+
+        Ok(PushResult::Applied(response.json::<RemoteTodo>().await.ok()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn adopted_operations_go_before_the_tabs_own() {
+        let mut outbox = vec![PendingOp::Delete { id: 1, base_version: 3 }];
+        let adopted = vec![
+            PendingOp::Delete { id: 2, base_version: 1 },
+            PendingOp::Delete { id: 1, base_version: 2 },
+        ];
+        merge_adopted(&mut outbox, adopted);
+        assert_eq!(
+            outbox,
+            vec![
+                PendingOp::Delete { id: 2, base_version: 1 },
+                PendingOp::Delete { id: 1, base_version: 3 },
+            ]
+        );
+    }
+
+    fn todo(id: u32, text: &str, version: u64) -> Todo {
+        Todo {
+            id,
+            text: text.to_string(),
+            completed: false,
+            tags: Vec::new(),
+            updated_at: 1.0,
+            version,
+        }
+    }
+
+    fn remote(id: u32, text: &str, version: u64) -> RemoteTodo {
+        RemoteTodo {
+            id,
+            text: text.to_string(),
+            completed: false,
+            tags: Vec::new(),
+            version,
+        }
+    }
+
+    fn snapshot(todos: Vec<Todo>) -> Snapshot {
+        Snapshot { todos, tombstones: Vec::new() }
+    }
+
+    #[test]
+    fn diff_lists_content_changes_only() {
+        let base = snapshot(vec![todo(1, "same", 2), todo(2, "old", 2), todo(3, "gone", 4)]);
+        let current = snapshot(vec![todo(1, "same", 5), todo(2, "new", 2), todo(4, "added", 0)]);
+        assert_eq!(
+            diff(&base, &current),
+            vec![
+                PendingOp::Upsert { todo: todo(2, "new", 2), base_version: 2 },
+                PendingOp::Upsert { todo: todo(4, "added", 0), base_version: 0 },
+                PendingOp::Delete { id: 3, base_version: 4 },
+            ]
+        );
+    }
+
+    #[test]
+    fn enqueue_collapses_queued_operations() {
+        let head = PendingOp::Delete { id: 9, base_version: 1 };
+
+        // An edit after an unsent edit keeps the first one's base version.
+        let mut outbox = vec![head.clone(), PendingOp::Upsert { todo: todo(1, "a", 0), base_version: 3 }];
+        enqueue(&mut outbox, PendingOp::Upsert { todo: todo(1, "b", 3), base_version: 3 });
+        assert_eq!(outbox[1], PendingOp::Upsert { todo: todo(1, "b", 3), base_version: 3 });
+        assert_eq!(outbox.len(), 2);
+
+        // Deleting an edited todo only sends the delete.
+        enqueue(&mut outbox, PendingOp::Delete { id: 1, base_version: 3 });
+        assert_eq!(outbox, vec![head.clone(), PendingOp::Delete { id: 1, base_version: 3 }]);
+
+        // Deleting a todo the server has never seen sends nothing.
+        let mut outbox = vec![head.clone(), PendingOp::Upsert { todo: todo(2, "new", 0), base_version: 0 }];
+        enqueue(&mut outbox, PendingOp::Delete { id: 2, base_version: 0 });
+        assert_eq!(outbox, vec![head]);
+    }
+
+    #[test]
+    fn delete_during_create_push_reaches_the_server() {
+        let created = todo(1, "new", 0);
+        let empty = snapshot(Vec::new());
+        let with_todo = snapshot(vec![created.clone()]);
+
+        let mut outbox = Vec::new();
+        for op in diff(&empty, &with_todo) {
+            enqueue(&mut outbox, op);
+        }
+        // The create at the head is being pushed when the todo is deleted.
+        let in_flight = outbox[0].clone();
+        let mut deleted = empty.clone();
+        crate::store::record_deletion(&mut deleted.tombstones, 1, 2.0);
+        for op in diff(&with_todo, &deleted) {
+            enqueue(&mut outbox, op);
+        }
+        assert_eq!(
+            outbox,
+            vec![in_flight.clone(), PendingOp::Delete { id: 1, base_version: 0 }]
+        );
+
+        // The server created it as version 1; the delete follows on top.
+        acknowledge(&mut outbox, &in_flight, Some(1));
+        assert_eq!(outbox, vec![PendingOp::Delete { id: 1, base_version: 1 }]);
+
+        // A pull in between doesn't bring it back.
+        let pulled = apply_pull(&deleted, vec![remote(1, "new", 1)], &outbox, 3.0);
+        assert_eq!(pulled, deleted);
+    }
+
+    #[test]
+    fn edit_during_push_is_rebased_onto_the_result() {
+        let in_flight = PendingOp::Upsert { todo: todo(1, "a", 2), base_version: 2 };
+        let mut outbox = vec![in_flight.clone()];
+        enqueue(&mut outbox, PendingOp::Upsert { todo: todo(1, "b", 2), base_version: 2 });
+        assert_eq!(outbox.len(), 2);
+        acknowledge(&mut outbox, &in_flight, Some(3));
+        assert_eq!(outbox, vec![PendingOp::Upsert { todo: todo(1, "b", 2), base_version: 3 }]);
+    }
+
+    #[test]
+    fn apply_pull_follows_the_server_except_for_pending_todos() {
+        let mut local = snapshot(vec![
+            todo(1, "stale", 1),
+            todo(2, "removed on server", 1),
+            todo(3, "unsent", 0),
+            todo(4, "pending edit", 1),
+        ]);
+        local.tombstones.push(crate::store::Tombstone { id: 5, deleted_at: 1.0 });
+        let outbox = vec![PendingOp::Upsert { todo: todo(4, "pending edit", 1), base_version: 1 }];
+        let server = vec![
+            remote(1, "fresh", 2),
+            remote(4, "server edit", 2),
+            remote(5, "deleted here", 1),
+            remote(6, "from elsewhere", 1),
+        ];
+
+        let pulled = apply_pull(&local, server, &outbox, 3.0);
+        let texts: Vec<(u32, &str, u64)> =
+            pulled.todos.iter().map(|t| (t.id, t.text.as_str(), t.version)).collect();
+        assert_eq!(
+            texts,
+            vec![
+                (1, "fresh", 2),
+                (3, "unsent", 0),
+                (4, "pending edit", 1),
+                (6, "from elsewhere", 1),
+            ]
+        );
+    }
+}
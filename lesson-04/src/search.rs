@@ -0,0 +1,191 @@
+use web_sys::UrlSearchParams;
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct FuzzyMatch {
+    pub score: i32,
+    pub indices: Vec<usize>,
+}
+
+/// Matches `query` as a case-insensitive subsequence of `text`.
+/// Consecutive characters and matches at word starts score higher.
+pub fn fuzzy_match(query: &str, text: &str) -> Option<FuzzyMatch> {
+    let query: Vec<char> = query
+        .chars()
+        .filter(|c| !c.is_whitespace())
+        .flat_map(char::to_lowercase)
+        .collect();
+    if query.is_empty() {
+        return Some(FuzzyMatch {
+            score: 0,
+            indices: Vec::new(),
+        });
+    }
+
+    let chars: Vec<char> = text.chars().collect();
+    let mut indices = Vec::with_capacity(query.len());
+    let mut score = 0;
+    let mut next = 0;
+
+    for (i, c) in chars.iter().enumerate() {
+        if next == query.len() {
+            break;
+        }
+        if c.to_lowercase().eq(std::iter::once(query[next])) {
+            score += 1;
+            if indices.last().is_some_and(|&last| last + 1 == i) {
+                score += 5;
+            }
+            if i == 0 || !chars[i - 1].is_alphanumeric() {
+                score += 3;
+            }
+            indices.push(i);
+            next += 1;
+        }
+    }
+
+    if next == query.len() {
+        Some(FuzzyMatch { score, indices })
+    } else {
+        None
+    }
+}
+
+/// Splits `text` into `(segment, highlighted)` runs for the given char indices.
+pub fn highlight_segments(text: &str, indices: &[usize]) -> Vec<(String, bool)> {
+    let mut segments: Vec<(String, bool)> = Vec::new();
+    for (i, c) in text.chars().enumerate() {
+        let highlighted = indices.contains(&i);
+        match segments.last_mut() {
+            Some((segment, current)) if *current == highlighted => segment.push(c),
+            _ => segments.push((c.to_string(), highlighted)),
+        }
+    }
+    segments
+}
+
+/// Collects `#tag` words from a todo's text, lowercased and deduplicated.
+pub fn extract_tags(text: &str) -> Vec<String> {
+    let mut tags: Vec<String> = text
+        .split_whitespace()
+        .filter_map(|word| word.strip_prefix('#'))
+        .map(|tag| {
+            tag.trim_end_matches(|c: char| !c.is_alphanumeric() && c != '-' && c != '_')
+                .to_lowercase()
+        })
+        .filter(|tag| !tag.is_empty())
+        .collect();
+    tags.sort();
+    tags.dedup();
+    tags
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct FilterState {
+    pub filter: String,
+    pub query: String,
+    pub tags: Vec<String>,
+}
+
+impl Default for FilterState {
+    fn default() -> Self {
+        Self {
+            filter: "all".to_string(),
+            query: String::new(),
+            tags: Vec::new(),
+        }
+    }
+}
+
+impl FilterState {
+    pub fn from_query_string(search: &str) -> Self {
+        let mut state = Self::default();
+        let Ok(params) = UrlSearchParams::new_with_str(search) else {
+            return state;
+        };
+
+        if let Some(filter) = params.get("filter")
+            && matches!(filter.as_str(), "all" | "active" | "completed")
+        {
+            state.filter = filter;
+        }
+        if let Some(query) = params.get("q") {
+            state.query = query;
+        }
+        if let Some(tags) = params.get("tags") {
+            state.tags = tags
+                .split(',')
+                .map(|tag| tag.trim().to_lowercase())
+                .filter(|tag| !tag.is_empty())
+                .collect();
+        }
+        state
+    }
+
+    pub fn to_query_string(&self) -> String {
+        let Ok(params) = UrlSearchParams::new() else {
+            return String::new();
+        };
+        if self.filter != "all" {
+            params.append("filter", &self.filter);
+        }
+        if !self.query.is_empty() {
+            params.append("q", &self.query);
+        }
+        if !self.tags.is_empty() {
+            params.append("tags", &self.tags.join(","));
+        }
+
+        let encoded = String::from(params.to_string());
+        if encoded.is_empty() {
+            encoded
+        } else {
+            format!("?{}", encoded)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn empty_query_matches_everything() {
+        assert_eq!(
+            fuzzy_match("  ", "anything"),
+            Some(FuzzyMatch {
+                score: 0,
+                indices: Vec::new()
+            })
+        );
+    }
+
+    #[test]
+    fn matches_case_insensitive_subsequence() {
+        let m = fuzzy_match("BMk", "buy milk").unwrap();
+        assert_eq!(m.indices, vec![0, 4, 7]);
+        assert!(fuzzy_match("mb", "buy milk").is_none());
+        assert!(fuzzy_match("milks", "buy milk").is_none());
+    }
+
+    #[test]
+    fn whitespace_in_query_is_ignored() {
+        assert_eq!(fuzzy_match("b m", "buy milk").unwrap().indices, vec![0, 4]);
+    }
+
+    #[test]
+    fn scores_consecutive_and_word_start_matches() {
+        // Each match scores 1, +5 when it follows the previous match and +3
+        // at the start of a word.
+        assert_eq!(fuzzy_match("mi", "milk").unwrap().score, 1 + 3 + 1 + 5);
+        assert_eq!(fuzzy_match("mk", "milk").unwrap().score, 1 + 3 + 1);
+        assert_eq!(fuzzy_match("k", "a-k").unwrap().score, 1 + 3);
+    }
+
+    #[test]
+    fn better_matches_rank_higher() {
+        let score = |text| fuzzy_match("mil", text).unwrap().score;
+        assert!(score("milk") > score("my oil"));
+        assert!(score("buy milk") > score("homily"));
+        assert!(score("homily") > score("m-i-l"));
+    }
+}
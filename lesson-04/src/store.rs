@@ -0,0 +1,195 @@
+use serde::{Deserialize, Serialize};
+
+use crate::Todo;
+
+const STORAGE_KEY: &str = "lesson-04.todos";
+const TOMBSTONE_TTL_MS: f64 = 30.0 * 24.0 * 60.0 * 60.0 * 1000.0;
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Tombstone {
+    pub id: u32,
+    pub deleted_at: f64,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Snapshot {
+    pub todos: Vec<Todo>,
+    #[serde(default)]
+    pub tombstones: Vec<Tombstone>,
+}
+
+pub fn now() -> f64 {
+    js_sys::Date::now()
+}
+
+/// Random ids keep todos created concurrently in different tabs apart.
+pub fn new_todo_id() -> u32 {
+    (js_sys::Math::random() * u32::MAX as f64) as u32
+}
+
+fn local_storage() -> Option<web_sys::Storage> {
+    leptos::prelude::window().local_storage().ok().flatten()
+}
+
+pub fn load() -> Snapshot {
+    local_storage()
+        .and_then(|storage| storage.get_item(STORAGE_KEY).ok().flatten())
+        .and_then(|json| serde_json::from_str(&json).ok())
+        .unwrap_or_default()
+}
+
+pub fn save(snapshot: &Snapshot) {
+    if let (Some(storage), Ok(json)) = (local_storage(), serde_json::to_string(snapshot)) {
+        let _ = storage.set_item(STORAGE_KEY, &json);
+    }
+}
+
+/// Records that `id` was deleted at `deleted_at`, keeping a single
+/// tombstone per id with the latest deletion time.
+pub fn record_deletion(tombstones: &mut Vec<Tombstone>, id: u32, deleted_at: f64) {
+    match tombstones.iter_mut().find(|t| t.id == id) {
+        Some(existing) => existing.deleted_at = existing.deleted_at.max(deleted_at),
+        None => tombstones.push(Tombstone { id, deleted_at }),
+    }
+}
+
+/// Whether `candidate` should replace `current`: the later edit wins, and
+/// edits made at the same moment are ordered by version and then content so
+/// every tab settles on the same one.
+fn supersedes(candidate: &Todo, current: &Todo) -> bool {
+    let key = |todo: &Todo| (todo.version, todo.text.clone(), todo.completed, todo.tags.clone());
+    match candidate.updated_at.total_cmp(&current.updated_at) {
+        std::cmp::Ordering::Equal => key(candidate) > key(current),
+        ordering => ordering.is_gt(),
+    }
+}
+
+/// Merges two snapshots item by item, keeping whichever side modified an
+/// item last. A deletion wins over any edit made before it or at the same
+/// time. Tombstones older than 30 days before `now` are dropped.
+pub fn merge(local: &Snapshot, remote: &Snapshot, now: f64) -> Snapshot {
+    let mut tombstones = local.tombstones.clone();
+    for remote_tombstone in remote.tombstones.iter() {
+        match tombstones.iter_mut().find(|t| t.id == remote_tombstone.id) {
+            Some(existing) => existing.deleted_at = existing.deleted_at.max(remote_tombstone.deleted_at),
+            None => tombstones.push(remote_tombstone.clone()),
+        }
+    }
+    let cutoff = now - TOMBSTONE_TTL_MS;
+    tombstones.retain(|t| t.deleted_at > cutoff);
+
+    let mut todos = local.todos.clone();
+    for remote_todo in remote.todos.iter() {
+        match todos.iter_mut().find(|t| t.id == remote_todo.id) {
+            Some(existing) if supersedes(remote_todo, existing) => {
+                *existing = remote_todo.clone();
+            }
+            Some(_) => {}
+            None => todos.push(remote_todo.clone()),
+        }
+    }
+    todos.retain(|todo| {
+        tombstones
+            .iter()
+            .find(|t| t.id == todo.id)
+            .is_none_or(|t| todo.updated_at > t.deleted_at)
+    });
+
+    Snapshot { todos, tombstones }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn deleting_twice_keeps_one_tombstone() {
+        let mut tombstones = Vec::new();
+        record_deletion(&mut tombstones, 7, 100.0);
+        record_deletion(&mut tombstones, 8, 150.0);
+        record_deletion(&mut tombstones, 7, 200.0);
+        record_deletion(&mut tombstones, 8, 120.0);
+        assert_eq!(
+            tombstones,
+            vec![
+                Tombstone { id: 7, deleted_at: 200.0 },
+                Tombstone { id: 8, deleted_at: 150.0 },
+            ]
+        );
+    }
+
+    fn todo(id: u32, text: &str, updated_at: f64) -> Todo {
+        Todo {
+            id,
+            text: text.to_string(),
+            completed: false,
+            tags: Vec::new(),
+            updated_at,
+            version: 0,
+        }
+    }
+
+    fn snapshot(todos: Vec<Todo>, tombstones: Vec<Tombstone>) -> Snapshot {
+        Snapshot { todos, tombstones }
+    }
+
+    const NOW: f64 = 100.0 * TOMBSTONE_TTL_MS;
+
+    #[test]
+    fn newer_edit_wins_from_either_side() {
+        let old = snapshot(vec![todo(1, "old", 10.0)], Vec::new());
+        let new = snapshot(vec![todo(1, "new", 20.0)], Vec::new());
+        assert_eq!(merge(&old, &new, NOW).todos, new.todos);
+        assert_eq!(merge(&new, &old, NOW).todos, new.todos);
+    }
+
+    #[test]
+    fn todos_on_one_side_only_are_kept() {
+        let local = snapshot(vec![todo(1, "a", 10.0)], Vec::new());
+        let remote = snapshot(vec![todo(2, "b", 10.0)], Vec::new());
+        assert_eq!(merge(&local, &remote, NOW).todos, vec![todo(1, "a", 10.0), todo(2, "b", 10.0)]);
+    }
+
+    #[test]
+    fn tombstone_beats_older_edit_but_not_newer_one() {
+        let deleted = snapshot(Vec::new(), vec![Tombstone { id: 1, deleted_at: NOW - 50.0 }]);
+
+        let older = snapshot(vec![todo(1, "edit", NOW - 60.0)], Vec::new());
+        let merged = merge(&older, &deleted, NOW);
+        assert!(merged.todos.is_empty());
+        assert_eq!(merged.tombstones, deleted.tombstones);
+        assert!(merge(&deleted, &older, NOW).todos.is_empty());
+
+        let same_time = snapshot(vec![todo(1, "edit", NOW - 50.0)], Vec::new());
+        assert!(merge(&same_time, &deleted, NOW).todos.is_empty());
+
+        let newer = snapshot(vec![todo(1, "edit", NOW - 40.0)], Vec::new());
+        assert_eq!(merge(&newer, &deleted, NOW).todos, newer.todos);
+        assert_eq!(merge(&deleted, &newer, NOW).todos, newer.todos);
+    }
+
+    #[test]
+    fn tombstones_expire_after_thirty_days() {
+        let fresh = Tombstone { id: 1, deleted_at: NOW - TOMBSTONE_TTL_MS + 1.0 };
+        let expired = Tombstone { id: 2, deleted_at: NOW - TOMBSTONE_TTL_MS };
+        let local = snapshot(Vec::new(), vec![fresh.clone()]);
+        let remote = snapshot(Vec::new(), vec![expired]);
+        assert_eq!(merge(&local, &remote, NOW).tombstones, vec![fresh]);
+
+        // Once the tombstone is gone, a stale copy of the todo comes back.
+        let stale = snapshot(vec![todo(2, "stale", 0.0)], Vec::new());
+        assert_eq!(merge(&remote, &stale, NOW).todos, stale.todos);
+    }
+
+    #[test]
+    fn equal_timestamps_settle_on_the_same_todo() {
+        let a = snapshot(vec![todo(1, "apples", 10.0)], Vec::new());
+        let b = snapshot(vec![todo(1, "bananas", 10.0)], Vec::new());
+        assert_eq!(merge(&a, &b, NOW), merge(&b, &a, NOW));
+        assert_eq!(merge(&a, &b, NOW).todos, b.todos);
+
+        let versioned = snapshot(vec![Todo { version: 3, ..todo(1, "apples", 10.0) }], Vec::new());
+        assert_eq!(merge(&b, &versioned, NOW).todos, versioned.todos);
+        assert_eq!(merge(&versioned, &b, NOW).todos, versioned.todos);
+    }
+}
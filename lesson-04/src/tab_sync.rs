@@ -0,0 +1,94 @@
+use serde::{Deserialize, Serialize};
+use wasm_bindgen::prelude::*;
+use web_sys::{BroadcastChannel, MessageEvent};
+
+use crate::store::Snapshot;
+
+const CHANNEL_NAME: &str = "lesson-04.todos";
+
+#[derive(Serialize, Deserialize)]
+struct SyncMessage {
+    origin: u32,
+    snapshot: Snapshot,
+}
+
+fn encode(origin: u32, snapshot: &Snapshot) -> Option<String> {
+    let message = SyncMessage {
+        origin,
+        snapshot: snapshot.clone(),
+    };
+    serde_json::to_string(&message).ok()
+}
+
+/// Reads a message from another tab, or `None` if it is malformed or was
+/// sent by `tab_id` itself.
+fn decode(json: &str, tab_id: u32) -> Option<Snapshot> {
+    let message = serde_json::from_str::<SyncMessage>(json).ok()?;
+    (message.origin != tab_id).then_some(message.snapshot)
+}
+
+/// Broadcasts snapshots to other tabs of the app and hands incoming ones to
+/// a callback. Messages this tab sent itself are ignored.
+pub struct TabSync {
+    channel: BroadcastChannel,
+    tab_id: u32,
+    _on_message: Closure<dyn FnMut(MessageEvent)>,
+}
+
+impl TabSync {
+    /// Joins the channel as `tab_id`, the id this tab stamps on everything
+    /// it shares with other tabs.
+    pub fn connect(tab_id: u32, on_snapshot: impl Fn(Snapshot) + 'static) -> Option<Self> {
+        let channel = BroadcastChannel::new(CHANNEL_NAME).ok()?;
+
+        let on_message = Closure::<dyn FnMut(MessageEvent)>::new(move |event: MessageEvent| {
+            if let Some(snapshot) = event.data().as_string().and_then(|json| decode(&json, tab_id)) {
+                on_snapshot(snapshot);
+            }
+        });
+        channel.set_onmessage(Some(on_message.as_ref().unchecked_ref()));
+
+        Some(Self {
+            channel,
+            tab_id,
+            _on_message: on_message,
+        })
+    }
+
+    pub fn publish(&self, snapshot: &Snapshot) {
+        if let Some(json) = encode(self.tab_id, snapshot) {
+            let _ = self.channel.post_message(&JsValue::from_str(&json));
+        }
+    }
+}
+
+impl Drop for TabSync {
+    fn drop(&mut self) {
+        self.channel.set_onmessage(None);
+        self.channel.close();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::store::Tombstone;
+
+    #[test]
+    fn messages_reach_other_tabs_only() {
+        let snapshot = Snapshot {
+            todos: Vec::new(),
+            tombstones: vec![Tombstone { id: 3, deleted_at: 10.0 }],
+        };
+        let json = encode(1, &snapshot).unwrap();
+        assert_eq!(decode(&json, 2), Some(snapshot));
+        assert_eq!(decode(&json, 1), None);
+    }
+
+    #[test]
+    fn malformed_messages_are_dropped() {
+        assert_eq!(decode("", 2), None);
+        assert_eq!(decode("{\"origin\":1}", 2), None);
+        assert_eq!(decode("[1, 2]", 2), None);
+    }
+}
@@ -0,0 +1,584 @@
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+use wasm_bindgen::{JsCast, JsValue};
+use wasm_bindgen_futures::JsFuture;
+
+use crate::search::extract_tags;
+use crate::Todo;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Format {
+    Json,
+    Csv,
+    TodoTxt,
+}
+
+impl Format {
+    pub fn from_file_name(name: &str) -> Option<Self> {
+        let extension = name.rsplit('.').next()?.to_lowercase();
+        match extension.as_str() {
+            "json" => Some(Format::Json),
+            "csv" => Some(Format::Csv),
+            "txt" => Some(Format::TodoTxt),
+            _ => None,
+        }
+    }
+
+    pub fn file_name(&self) -> &'static str {
+        match self {
+            Format::Json => "todos.json",
+            Format::Csv => "todos.csv",
+            Format::TodoTxt => "todo.txt",
+        }
+    }
+
+    pub fn mime_type(&self) -> &'static str {
+        match self {
+            Format::Json => "application/json",
+            Format::Csv => "text/csv",
+            Format::TodoTxt => "text/plain",
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ImportedTodo {
+    pub text: String,
+    #[serde(default)]
+    pub completed: bool,
+    #[serde(default)]
+    pub tags: Vec<String>,
+}
+
+/// Where a problem was found: a line of text, or an element of a JSON array.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Location {
+    Line(usize),
+    Item(usize),
+}
+
+impl fmt::Display for Location {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Location::Line(line) => write!(f, "Line {}", line),
+            Location::Item(item) => write!(f, "Item {}", item),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct ParseError {
+    pub location: Location,
+    pub message: String,
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ParseReport {
+    pub todos: Vec<ImportedTodo>,
+    pub errors: Vec<ParseError>,
+}
+
+pub fn export(format: Format, todos: &[Todo]) -> String {
+    match format {
+        Format::Json => export_json(todos),
+        Format::Csv => export_csv(todos),
+        Format::TodoTxt => export_todo_txt(todos),
+    }
+}
+
+pub fn parse(format: Format, input: &str) -> ParseReport {
+    match format {
+        Format::Json => parse_json(input),
+        Format::Csv => parse_csv(input),
+        Format::TodoTxt => parse_todo_txt(input),
+    }
+}
+
+pub fn download(format: Format, contents: &str) -> Result<(), JsValue> {
+    let parts = js_sys::Array::of1(&JsValue::from_str(contents));
+    let options = web_sys::BlobPropertyBag::new();
+    options.set_type(format.mime_type());
+    let blob = web_sys::Blob::new_with_str_sequence_and_options(&parts, &options)?;
+    let url = web_sys::Url::create_object_url_with_blob(&blob)?;
+
+    let anchor: web_sys::HtmlAnchorElement = leptos::prelude::document()
+        .create_element("a")?
+        .dyn_into()?;
+    anchor.set_href(&url);
+    anchor.set_download(format.file_name());
+    anchor.click();
+    web_sys::Url::revoke_object_url(&url)
+}
+
+pub async fn read_file(file: web_sys::File) -> Result<String, String> {
+    JsFuture::from(file.text())
+        .await
+        .ok()
+        .and_then(|value| value.as_string())
+        .ok_or_else(|| format!("Could not read {}", file.name()))
+}
+
+fn export_json(todos: &[Todo]) -> String {
+    let items: Vec<ImportedTodo> = todos
+        .iter()
+        .map(|todo| ImportedTodo {
+            text: todo.text.clone(),
+            completed: todo.completed,
+            tags: todo.tags.clone(),
+        })
+        .collect();
+    serde_json::to_string_pretty(&items).unwrap_or_else(|_| "[]".to_string())
+}
+
+fn export_csv(todos: &[Todo]) -> String {
+    let mut out = String::from("text,completed,tags\n");
+    for todo in todos {
+        out.push_str(&format!(
+            "{},{},{}\n",
+            csv_escape(&todo.text),
+            todo.completed,
+            csv_escape(&todo.tags.join(" "))
+        ));
+    }
+    out
+}
+
+fn export_todo_txt(todos: &[Todo]) -> String {
+    todos
+        .iter()
+        .map(|todo| {
+            let mut line = String::new();
+            if todo.completed {
+                line.push_str("x ");
+            }
+            // Text that would read as a completion mark, priority or date is
+            // put after a creation date, which the parser strips instead.
+            if todo.text.starts_with("x ") || strip_priority(&todo.text).is_some() || strip_date(&todo.text) != todo.text {
+                let date = iso_date(todo.updated_at);
+                if todo.completed {
+                    line.push_str(&format!("{} ", date));
+                }
+                line.push_str(&format!("{} ", date));
+            }
+            let words: Vec<String> = todo.text.split(' ').map(escape_word).collect();
+            line.push_str(&words.join(" "));
+            let text_tags = extract_tags(&todo.text);
+            for tag in todo.tags.iter() {
+                if !text_tags.contains(&tag.to_lowercase()) {
+                    line.push_str(&format!(" +{}", tag));
+                }
+            }
+            line.push('\n');
+            line
+        })
+        .collect()
+}
+
+fn parse_json(input: &str) -> ParseReport {
+    let mut report = ParseReport::default();
+    let items: Vec<serde_json::Value> = match serde_json::from_str(input) {
+        Ok(items) => items,
+        Err(err) => {
+            report.errors.push(ParseError {
+                location: Location::Line(err.line()),
+                message: err.to_string(),
+            });
+            return report;
+        }
+    };
+
+    for (index, item) in items.into_iter().enumerate() {
+        match serde_json::from_value::<ImportedTodo>(item) {
+            Ok(mut todo) if !todo.text.trim().is_empty() => {
+                todo.text = todo.text.trim().to_string();
+                merge_tags(&mut todo);
+                report.todos.push(todo);
+            }
+            Ok(_) => report.errors.push(ParseError {
+                location: Location::Item(index + 1),
+                message: "item has empty text".to_string(),
+            }),
+            Err(err) => report.errors.push(ParseError {
+                location: Location::Item(index + 1),
+                message: err.to_string(),
+            }),
+        }
+    }
+    report
+}
+
+fn parse_csv(input: &str) -> ParseReport {
+    let mut report = ParseReport::default();
+    let mut records = csv_records(input).into_iter();
+
+    let Some((header_line, header)) = records.next() else {
+        return report;
+    };
+    let columns: Vec<String> = match header {
+        Ok(fields) => fields.iter().map(|f| f.trim().to_lowercase()).collect(),
+        Err(message) => {
+            report.errors.push(ParseError {
+                location: Location::Line(header_line),
+                message,
+            });
+            return report;
+        }
+    };
+    let Some(text_column) = columns.iter().position(|c| c == "text") else {
+        report.errors.push(ParseError {
+            location: Location::Line(header_line),
+            message: "missing \"text\" column in header".to_string(),
+        });
+        return report;
+    };
+    let completed_column = columns.iter().position(|c| c == "completed");
+    let tags_column = columns.iter().position(|c| c == "tags");
+
+    for (line_number, fields) in records {
+        let fields = match fields {
+            Ok(fields) => fields,
+            Err(message) => {
+                report.errors.push(ParseError {
+                    location: Location::Line(line_number),
+                    message,
+                });
+                continue;
+            }
+        };
+        if fields.len() != columns.len() {
+            report.errors.push(ParseError {
+                location: Location::Line(line_number),
+                message: format!("expected {} fields, found {}", columns.len(), fields.len()),
+            });
+            continue;
+        }
+
+        let text = fields[text_column].trim().to_string();
+        if text.is_empty() {
+            report.errors.push(ParseError {
+                location: Location::Line(line_number),
+                message: "empty text".to_string(),
+            });
+            continue;
+        }
+        let completed = match completed_column.map(|c| fields[c].trim().to_lowercase()) {
+            None => false,
+            Some(value) => match value.as_str() {
+                "true" | "yes" | "1" | "x" => true,
+                "false" | "no" | "0" | "" => false,
+                _ => {
+                    report.errors.push(ParseError {
+                        location: Location::Line(line_number),
+                        message: format!("invalid completed value \"{}\"", value),
+                    });
+                    continue;
+                }
+            },
+        };
+        let tags = tags_column
+            .map(|c| {
+                fields[c]
+                    .split([' ', ','])
+                    .map(|tag| tag.trim().trim_start_matches('#').to_lowercase())
+                    .filter(|tag| !tag.is_empty())
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        let mut todo = ImportedTodo { text, completed, tags };
+        merge_tags(&mut todo);
+        report.todos.push(todo);
+    }
+    report
+}
+
+fn parse_todo_txt(input: &str) -> ParseReport {
+    let mut report = ParseReport::default();
+
+    for (index, line) in input.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+
+        let mut rest = line;
+        let completed = match rest.strip_prefix("x ") {
+            Some(stripped) => {
+                rest = stripped.trim_start();
+                rest = strip_date(rest);
+                true
+            }
+            None => false,
+        };
+        if let Some(stripped) = strip_priority(rest) {
+            rest = stripped;
+        }
+        rest = strip_date(rest);
+
+        let mut text_words = Vec::new();
+        let mut tags = Vec::new();
+        for word in rest.split_whitespace() {
+            if reads_as_tag(word.trim_start_matches('\\')) && word.starts_with('\\') {
+                text_words.push(&word[1..]);
+                continue;
+            }
+            match word.strip_prefix('+').or_else(|| word.strip_prefix('@')) {
+                Some(tag) if !tag.is_empty() => tags.push(tag.to_lowercase()),
+                _ => text_words.push(word),
+            }
+        }
+
+        let text = text_words.join(" ");
+        if text.is_empty() {
+            report.errors.push(ParseError {
+                location: Location::Line(index + 1),
+                message: "task has no description".to_string(),
+            });
+            continue;
+        }
+
+        let mut todo = ImportedTodo { text, completed, tags };
+        merge_tags(&mut todo);
+        report.todos.push(todo);
+    }
+    report
+}
+
+/// Whether a todo.txt word is a `+project` or `@context`.
+fn reads_as_tag(word: &str) -> bool {
+    word.len() > 1 && word.starts_with(['+', '@'])
+}
+
+/// Keeps a word of todo text that would read as a tag literal by putting a
+/// backslash in front, e.g. `\+1`. Words already starting with backslashes
+/// before `+` or `@` get one more, so unescaping is always one backslash.
+fn escape_word(word: &str) -> String {
+    if reads_as_tag(word.trim_start_matches('\\')) {
+        format!("\\{}", word)
+    } else {
+        word.to_string()
+    }
+}
+
+fn merge_tags(todo: &mut ImportedTodo) {
+    todo.tags.extend(extract_tags(&todo.text));
+    todo.tags.sort();
+    todo.tags.dedup();
+}
+
+fn strip_priority(input: &str) -> Option<&str> {
+    let bytes = input.as_bytes();
+    if bytes.len() >= 4
+        && bytes[0] == b'('
+        && bytes[1].is_ascii_uppercase()
+        && bytes[2] == b')'
+        && bytes[3] == b' '
+    {
+        Some(input[4..].trim_start())
+    } else {
+        None
+    }
+}
+
+fn strip_date(input: &str) -> &str {
+    let bytes = input.as_bytes();
+    let is_date = bytes.len() >= 10
+        && bytes[..10].iter().enumerate().all(|(i, b)| match i {
+            4 | 7 => *b == b'-',
+            _ => b.is_ascii_digit(),
+        })
+        && (bytes.len() == 10 || bytes[10] == b' ');
+    if is_date {
+        input[10..].trim_start()
+    } else {
+        input
+    }
+}
+
+fn csv_escape(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+/// `YYYY-MM-DD` in UTC for a JavaScript timestamp in milliseconds.
+fn iso_date(timestamp: f64) -> String {
+    // Days since 1970-01-01 to a civil date, after Howard Hinnant's
+    // `civil_from_days`.
+    let z = (timestamp / 86_400_000.0).floor() as i64 + 719_468;
+    let era = z.div_euclid(146_097);
+    let day_of_era = z.rem_euclid(146_097);
+    let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let shifted_month = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * shifted_month + 2) / 5 + 1;
+    let month = if shifted_month < 10 { shifted_month + 3 } else { shifted_month - 9 };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+    format!("{:04}-{:02}-{:02}", year, month, day)
+}
+
+/// Splits CSV text into records, each with the line it starts on. Quoted
+/// fields may span lines; blank lines are skipped.
+fn csv_records(input: &str) -> Vec<(usize, Result<Vec<String>, String>)> {
+    let mut records = Vec::new();
+    let mut fields = Vec::new();
+    let mut field = String::new();
+    let mut in_quotes = false;
+    let mut line = 1;
+    let mut start = 1;
+    let mut chars = input.chars().peekable();
+
+    let mut finish = |start: usize, fields: Vec<String>| {
+        if !(fields.len() == 1 && fields[0].trim().is_empty()) {
+            records.push((start, Ok(fields)));
+        }
+    };
+
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if in_quotes && chars.peek() == Some(&'"') => {
+                field.push('"');
+                chars.next();
+            }
+            '"' if in_quotes => in_quotes = false,
+            '"' if field.is_empty() => in_quotes = true,
+            ',' if !in_quotes => fields.push(std::mem::take(&mut field)),
+            '\r' if !in_quotes && chars.peek() == Some(&'\n') => {}
+            '\n' if !in_quotes => {
+                fields.push(std::mem::take(&mut field));
+                finish(start, std::mem::take(&mut fields));
+                line += 1;
+                start = line;
+            }
+            _ => {
+                if c == '\n' {
+                    line += 1;
+                }
+                field.push(c);
+            }
+        }
+    }
+
+    if in_quotes {
+        records.push((start, Err("unterminated quoted field".to_string())));
+    } else {
+        fields.push(field);
+        finish(start, fields);
+    }
+    records
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn todo(text: &str, completed: bool, tags: &[&str]) -> Todo {
+        Todo {
+            id: 1,
+            text: text.to_string(),
+            completed,
+            tags: tags.iter().map(|t| t.to_string()).collect(),
+            // 2023-11-14T22:13:20Z
+            updated_at: 1_700_000_000_000.0,
+            version: 0,
+        }
+    }
+
+    fn imported(todos: &[Todo]) -> Vec<ImportedTodo> {
+        todos
+            .iter()
+            .map(|t| ImportedTodo {
+                text: t.text.clone(),
+                completed: t.completed,
+                tags: t.tags.clone(),
+            })
+            .collect()
+    }
+
+    fn round_trip(format: Format, todos: &[Todo]) {
+        let report = parse(format, &export(format, todos));
+        assert_eq!(report.errors, Vec::new(), "{:?}", format);
+        assert_eq!(report.todos, imported(todos), "{:?}", format);
+    }
+
+    #[test]
+    fn json_round_trip() {
+        round_trip(
+            Format::Json,
+            &[
+                todo("Buy #milk, eggs", false, &["milk", "shop"]),
+                todo("Say \"hi\"\nthen leave", true, &[]),
+            ],
+        );
+    }
+
+    #[test]
+    fn csv_round_trip() {
+        round_trip(
+            Format::Csv,
+            &[
+                todo("Buy #milk, eggs", false, &["milk", "shop"]),
+                todo("Say \"hi\"\nthen leave", true, &[]),
+                todo("plain", false, &[]),
+            ],
+        );
+    }
+
+    #[test]
+    fn todo_txt_round_trip() {
+        round_trip(
+            Format::TodoTxt,
+            &[
+                todo("Buy #milk", false, &["milk", "shop"]),
+                todo("Call mum", true, &["family"]),
+                todo("x marks the spot", false, &[]),
+                todo("x marks the spot", true, &[]),
+                todo("(A) is not a priority", false, &[]),
+                todo("2024-01-01 is a heading", true, &[]),
+                todo("Order a #milkshake", false, &["milk", "milkshake"]),
+                todo("+1 call @ noon with @home", false, &[]),
+                todo("keep \\+this and \\\\@that", false, &["x"]),
+            ],
+        );
+    }
+
+    #[test]
+    fn todo_txt_writes_tags_missing_from_the_text() {
+        let exported = export(Format::TodoTxt, &[todo("Order a #milkshake", false, &["milk", "milkshake"])]);
+        assert_eq!(exported, "Order a #milkshake +milk\n");
+        let exported = export(Format::TodoTxt, &[todo("+1 call @home", false, &[])]);
+        assert_eq!(exported, "\\+1 call \\@home\n");
+    }
+
+    #[test]
+    fn todo_txt_guards_text_with_creation_date() {
+        let exported = export(Format::TodoTxt, &[todo("x marks the spot", false, &[])]);
+        assert_eq!(exported, "2023-11-14 x marks the spot\n");
+    }
+
+    #[test]
+    fn iso_dates() {
+        assert_eq!(iso_date(0.0), "1970-01-01");
+        assert_eq!(iso_date(951_782_400_000.0), "2000-02-29");
+        assert_eq!(iso_date(-86_400_000.0), "1969-12-31");
+    }
+
+    #[test]
+    fn csv_errors_report_the_line_a_record_starts_on() {
+        let report = parse(Format::Csv, "text,completed\n\"two\nlines\",true\n,false\n\"open");
+        assert_eq!(report.todos.len(), 1);
+        assert_eq!(report.todos[0].text, "two\nlines");
+        let locations: Vec<_> = report.errors.iter().map(|e| e.location).collect();
+        assert_eq!(locations, vec![Location::Line(4), Location::Line(5)]);
+    }
+
+    #[test]
+    fn json_errors_name_the_item() {
+        let report = parse(Format::Json, r#"[{"text": "ok"}, {"text": " "}, {"done": true}]"#);
+        let locations: Vec<_> = report.errors.iter().map(|e| e.location.to_string()).collect();
+        assert_eq!(locations, vec!["Item 2", "Item 3"]);
+    }
+}
@@ -0,0 +1,788 @@
+use std::cmp::Ordering;
+use std::fmt;
+use std::str::FromStr;
+
+/// Coefficients longer than this are treated as overflow.
+pub const MAX_DIGITS: usize = 2_000;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum RoundingMode {
+    #[default]
+    HalfEven,
+    HalfUp,
+    Down,
+    Up,
+    Ceiling,
+    Floor,
+}
+
+impl RoundingMode {
+    pub const ALL: [RoundingMode; 6] = [
+        RoundingMode::HalfEven,
+        RoundingMode::HalfUp,
+        RoundingMode::Down,
+        RoundingMode::Up,
+        RoundingMode::Ceiling,
+        RoundingMode::Floor,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            RoundingMode::HalfEven => "Half even",
+            RoundingMode::HalfUp => "Half up",
+            RoundingMode::Down => "Toward zero",
+            RoundingMode::Up => "Away from zero",
+            RoundingMode::Ceiling => "Ceiling",
+            RoundingMode::Floor => "Floor",
+        }
+    }
+}
+
+/// An arbitrary-precision decimal: `coefficient × 10^-scale`.
+///
+/// The coefficient is kept as little-endian base-10 digits without
+/// trailing (most significant) zeros; zero has no digits.
+#[derive(Clone, Debug, Default)]
+pub struct Decimal {
+    negative: bool,
+    digits: Vec<u8>,
+    scale: i64,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct FormatOptions {
+    pub thousands_separator: bool,
+    pub max_digits: usize,
+}
+
+impl Default for FormatOptions {
+    fn default() -> Self {
+        Self {
+            thousands_separator: true,
+            max_digits: 16,
+        }
+    }
+}
+
+impl Decimal {
+    pub fn zero() -> Self {
+        Self::default()
+    }
+
+    pub fn from_i64(value: i64) -> Self {
+        let mut digits = Vec::new();
+        let mut magnitude = value.unsigned_abs();
+        while magnitude > 0 {
+            digits.push((magnitude % 10) as u8);
+            magnitude /= 10;
+        }
+        Self {
+            negative: value < 0,
+            digits,
+            scale: 0,
+        }
+        .normalized()
+    }
+
+    /// Converts through the shortest decimal representation that round
+    /// trips, so `0.1_f64` becomes exactly `0.1`.
+    pub fn from_f64(value: f64) -> Option<Self> {
+        if !value.is_finite() {
+            return None;
+        }
+        format!("{:e}", value).parse().ok()
+    }
+
+    pub fn to_f64(&self) -> f64 {
+        self.to_scientific_string().parse().unwrap_or(f64::NAN)
+    }
+
+    pub fn to_i64(&self) -> Option<i64> {
+        // Normalised integers have a non-positive scale, so this counts
+        // every digit including the implied trailing zeros.
+        if !self.is_integer() || self.digits.len() as i64 - self.scale > 19 {
+            return None;
+        }
+        let mut value: i128 = 0;
+        for digit in self.digits.iter().rev() {
+            value = value * 10 + *digit as i128;
+        }
+        for _ in 0..(-self.scale).max(0) {
+            value *= 10;
+        }
+        if self.negative {
+            value = -value;
+        }
+        i64::try_from(value).ok()
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.digits.is_empty()
+    }
+
+    pub fn is_negative(&self) -> bool {
+        self.negative && !self.is_zero()
+    }
+
+    pub fn is_integer(&self) -> bool {
+        // Normalised values have no trailing zeros in the coefficient.
+        self.scale <= 0
+    }
+
+    pub fn abs(&self) -> Self {
+        Self {
+            negative: false,
+            ..self.clone()
+        }
+    }
+
+    /// Power of ten of the most significant digit, e.g. 2 for 123.4.
+    pub fn magnitude(&self) -> i64 {
+        self.digits.len() as i64 - 1 - self.scale
+    }
+
+    fn normalized(mut self) -> Self {
+        while self.digits.last() == Some(&0) {
+            self.digits.pop();
+        }
+        let trailing = self.digits.iter().take_while(|d| **d == 0).count();
+        if trailing > 0 {
+            self.digits.drain(..trailing);
+            self.scale -= trailing as i64;
+        }
+        if self.digits.is_empty() {
+            self.negative = false;
+            self.scale = 0;
+        }
+        self
+    }
+
+    fn with_scale(&self, scale: i64) -> Vec<u8> {
+        let mut digits = vec![0; (scale - self.scale).max(0) as usize];
+        digits.extend_from_slice(&self.digits);
+        digits
+    }
+
+    pub fn mul_pow10(&self, exponent: i64) -> Self {
+        Self {
+            scale: self.scale - exponent,
+            ..self.clone()
+        }
+        .normalized()
+    }
+
+    /// Rounds to `places` digits after the decimal point.
+    pub fn round_to_places(&self, places: i64, mode: RoundingMode) -> Self {
+        if self.scale <= places {
+            return self.clone();
+        }
+        let drop = (self.scale - places) as usize;
+        if drop > self.digits.len() {
+            let increment = match mode {
+                RoundingMode::Up => true,
+                RoundingMode::Ceiling => !self.negative,
+                RoundingMode::Floor => self.negative,
+                _ => false,
+            };
+            return if increment {
+                Self {
+                    negative: self.negative,
+                    digits: vec![1],
+                    scale: places,
+                }
+            } else {
+                Self::zero()
+            };
+        }
+
+        let (dropped, kept) = self.digits.split_at(drop);
+        let first_dropped = dropped.last().copied().unwrap_or(0);
+        let rest_nonzero = dropped.iter().rev().skip(1).any(|d| *d != 0);
+        let any_dropped = first_dropped != 0 || rest_nonzero;
+        let last_kept_odd = kept.first().is_some_and(|d| d % 2 == 1);
+
+        let increment = match mode {
+            RoundingMode::Down => false,
+            RoundingMode::Up => any_dropped,
+            RoundingMode::Ceiling => any_dropped && !self.negative,
+            RoundingMode::Floor => any_dropped && self.negative,
+            RoundingMode::HalfUp => first_dropped >= 5,
+            RoundingMode::HalfEven => {
+                first_dropped > 5 || (first_dropped == 5 && (rest_nonzero || last_kept_odd))
+            }
+        };
+
+        let mut digits = kept.to_vec();
+        if increment {
+            add_one(&mut digits);
+        }
+        Self {
+            negative: self.negative,
+            digits,
+            scale: places,
+        }
+        .normalized()
+    }
+
+    /// Rounds to `precision` significant digits.
+    pub fn round_to_precision(&self, precision: u32, mode: RoundingMode) -> Self {
+        if self.is_zero() {
+            return self.clone();
+        }
+        let places = precision as i64 - 1 - self.magnitude();
+        self.round_to_places(places, mode)
+    }
+
+    /// Divides to `precision` significant digits.
+    pub fn div(&self, other: &Self, precision: u32, mode: RoundingMode) -> Option<Self> {
+        if other.is_zero() {
+            return None;
+        }
+        if self.is_zero() {
+            return Some(Self::zero());
+        }
+
+        // Shift the dividend so the integer quotient carries enough digits
+        // for the requested precision plus a guard digit and sticky bit.
+        let wanted = precision as i64 + 2;
+        let shift = (wanted - (self.digits.len() as i64 - other.digits.len() as i64)).max(0);
+        let mut dividend = vec![0; shift as usize];
+        dividend.extend_from_slice(&self.digits);
+
+        let (mut digits, remainder_nonzero) = div_digits(&dividend, &other.digits);
+        let mut scale = self.scale - other.scale + shift;
+        if remainder_nonzero {
+            // A sticky digit keeps "exactly half" and "just over half" apart.
+            digits.insert(0, 1);
+            scale += 1;
+        }
+
+        let result = Self {
+            negative: self.negative != other.negative,
+            digits,
+            scale,
+        }
+        .normalized();
+        Some(result.round_to_precision(precision, mode))
+    }
+
+    /// Raises to an integer power. Negative exponents divide at the given
+    /// precision.
+    pub fn powi(&self, exponent: i64, precision: u32, mode: RoundingMode) -> Option<Self> {
+        let mut result = Self::from_i64(1);
+        let mut base = self.clone();
+        let mut n = exponent.unsigned_abs();
+        while n > 0 {
+            if n & 1 == 1 {
+                result = &result * &base;
+            }
+            n >>= 1;
+            if n > 0 {
+                base = &base * &base;
+            }
+            if result.digits.len() > MAX_DIGITS || base.digits.len() > MAX_DIGITS {
+                return None;
+            }
+        }
+        if exponent < 0 {
+            Self::from_i64(1).div(&result, precision, mode)
+        } else {
+            Some(result)
+        }
+    }
+
+    pub fn to_plain_string(&self) -> String {
+        if self.is_zero() {
+            return "0".to_string();
+        }
+        let mut out = String::new();
+        if self.negative {
+            out.push('-');
+        }
+        let (integer, fraction) = self.split_parts();
+        out.push_str(&integer);
+        if !fraction.is_empty() {
+            out.push('.');
+            out.push_str(&fraction);
+        }
+        out
+    }
+
+    pub fn to_scientific_string(&self) -> String {
+        if self.is_zero() {
+            return "0".to_string();
+        }
+        let mut out = String::new();
+        if self.negative {
+            out.push('-');
+        }
+        let mut digits = self.digits.iter().rev().map(|d| (b'0' + d) as char);
+        out.push(digits.next().unwrap_or('0'));
+        let rest: String = digits.collect();
+        if !rest.is_empty() {
+            out.push('.');
+            out.push_str(&rest);
+        }
+        out.push_str(&format!("e{}", self.magnitude()));
+        out
+    }
+
+    fn split_parts(&self) -> (String, String) {
+        let text: String = self.digits.iter().rev().map(|d| (b'0' + d) as char).collect();
+        if self.scale <= 0 {
+            let mut integer = text;
+            integer.extend(std::iter::repeat_n('0', (-self.scale) as usize));
+            (integer, String::new())
+        } else if self.scale as usize >= text.len() {
+            let mut fraction = "0".repeat(self.scale as usize - text.len());
+            fraction.push_str(&text);
+            ("0".to_string(), fraction)
+        } else {
+            let split = text.len() - self.scale as usize;
+            (text[..split].to_string(), text[split..].to_string())
+        }
+    }
+
+    /// Formats for the display: grouped thousands for ordinary values and
+    /// scientific notation once a value needs more than `max_digits`.
+    pub fn format(&self, options: FormatOptions) -> String {
+        if self.is_zero() {
+            return "0".to_string();
+        }
+        let magnitude = self.magnitude();
+        let max = options.max_digits as i64;
+        if magnitude >= max || magnitude < -6 {
+            let rounded = self.round_to_precision(options.max_digits as u32, RoundingMode::HalfEven);
+            let text = rounded.to_scientific_string();
+            return match text.split_once('e') {
+                Some((mantissa, exponent)) if exponent.starts_with('-') => {
+                    format!("{}e{}", mantissa, exponent)
+                }
+                Some((mantissa, exponent)) => format!("{}e+{}", mantissa, exponent),
+                None => text,
+            };
+        }
+
+        let places = (max - 1 - magnitude).max(0);
+        let rounded = self.round_to_places(places, RoundingMode::HalfEven);
+        let (integer, fraction) = rounded.split_parts();
+        let integer = if options.thousands_separator {
+            group_thousands(&integer)
+        } else {
+            integer
+        };
+
+        let mut out = String::new();
+        if rounded.is_negative() {
+            out.push('-');
+        }
+        out.push_str(&integer);
+        if !fraction.is_empty() {
+            out.push('.');
+            out.push_str(&fraction);
+        }
+        out
+    }
+}
+
+fn group_thousands(integer: &str) -> String {
+    let mut out = String::new();
+    for (i, c) in integer.chars().enumerate() {
+        if i > 0 && (integer.len() - i).is_multiple_of(3) {
+            out.push(',');
+        }
+        out.push(c);
+    }
+    out
+}
+
+/// Removes thousands separators from the integer part of a number, or
+/// `None` if commas don't separate groups of three, as in `1,2,3`.
+fn ungroup(integer: &str) -> Option<String> {
+    let mut groups = integer.split(',');
+    let first = groups.next().unwrap_or_default();
+    let mut out = first.to_string();
+    if integer.contains(',') && !(1..=3).contains(&first.len()) {
+        return None;
+    }
+    for group in groups {
+        if group.len() != 3 {
+            return None;
+        }
+        out.push_str(group);
+    }
+    Some(out)
+}
+
+fn add_one(digits: &mut Vec<u8>) {
+    for digit in digits.iter_mut() {
+        if *digit == 9 {
+            *digit = 0;
+        } else {
+            *digit += 1;
+            return;
+        }
+    }
+    digits.push(1);
+}
+
+fn cmp_digits(a: &[u8], b: &[u8]) -> Ordering {
+    let trim = |d: &[u8]| d.len() - d.iter().rev().take_while(|x| **x == 0).count();
+    let (la, lb) = (trim(a), trim(b));
+    if la != lb {
+        return la.cmp(&lb);
+    }
+    for i in (0..la).rev() {
+        if a[i] != b[i] {
+            return a[i].cmp(&b[i]);
+        }
+    }
+    Ordering::Equal
+}
+
+fn add_digits(a: &[u8], b: &[u8]) -> Vec<u8> {
+    let mut out = Vec::with_capacity(a.len().max(b.len()) + 1);
+    let mut carry = 0;
+    for i in 0..a.len().max(b.len()) {
+        let sum = a.get(i).copied().unwrap_or(0) + b.get(i).copied().unwrap_or(0) + carry;
+        out.push(sum % 10);
+        carry = sum / 10;
+    }
+    if carry > 0 {
+        out.push(carry);
+    }
+    out
+}
+
+/// `a - b`, requiring `a >= b`.
+fn sub_digits(a: &[u8], b: &[u8]) -> Vec<u8> {
+    let mut out = Vec::with_capacity(a.len());
+    let mut borrow = 0i8;
+    for (i, digit) in a.iter().enumerate() {
+        let mut diff = *digit as i8 - b.get(i).copied().unwrap_or(0) as i8 - borrow;
+        borrow = if diff < 0 {
+            diff += 10;
+            1
+        } else {
+            0
+        };
+        out.push(diff as u8);
+    }
+    while out.last() == Some(&0) {
+        out.pop();
+    }
+    out
+}
+
+fn mul_digits(a: &[u8], b: &[u8]) -> Vec<u8> {
+    if a.is_empty() || b.is_empty() {
+        return Vec::new();
+    }
+    let mut out = vec![0u64; a.len() + b.len()];
+    for (i, x) in a.iter().enumerate() {
+        for (j, y) in b.iter().enumerate() {
+            out[i + j] += *x as u64 * *y as u64;
+        }
+    }
+    let mut carry = 0;
+    for slot in out.iter_mut() {
+        let total = *slot + carry;
+        *slot = total % 10;
+        carry = total / 10;
+    }
+    let mut digits: Vec<u8> = out.into_iter().map(|d| d as u8).collect();
+    while digits.last() == Some(&0) {
+        digits.pop();
+    }
+    digits
+}
+
+/// Long division of digit vectors. Returns the quotient and whether a
+/// remainder was left over.
+fn div_digits(dividend: &[u8], divisor: &[u8]) -> (Vec<u8>, bool) {
+    let mut quotient = vec![0u8; dividend.len()];
+    let mut remainder: Vec<u8> = Vec::new();
+    for i in (0..dividend.len()).rev() {
+        remainder.insert(0, dividend[i]);
+        while remainder.last() == Some(&0) {
+            remainder.pop();
+        }
+        let mut count = 0;
+        while cmp_digits(&remainder, divisor) != Ordering::Less {
+            remainder = sub_digits(&remainder, divisor);
+            count += 1;
+        }
+        quotient[i] = count;
+    }
+    while quotient.last() == Some(&0) {
+        quotient.pop();
+    }
+    (quotient, !remainder.is_empty())
+}
+
+impl FromStr for Decimal {
+    type Err = String;
+
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        let input = input.trim();
+        let (negative, body) = match input.strip_prefix('-') {
+            Some(rest) => (true, rest),
+            None => (false, input.strip_prefix('+').unwrap_or(input)),
+        };
+        let (mantissa, exponent) = match body.find(['e', 'E']) {
+            Some(index) => (&body[..index], body[index + 1..].parse::<i64>().map_err(|e| e.to_string())?),
+            None => (body, 0),
+        };
+        let (integer, fraction) = mantissa.split_once('.').unwrap_or((mantissa, ""));
+        let integer = ungroup(integer).ok_or_else(|| format!("invalid number \"{}\"", input))?;
+        if integer.is_empty() && fraction.is_empty() {
+            return Err(format!("invalid number \"{}\"", input));
+        }
+        if !integer.chars().chain(fraction.chars()).all(|c| c.is_ascii_digit()) {
+            return Err(format!("invalid number \"{}\"", input));
+        }
+
+        // Half the i64 range leaves room for the digit counts that
+        // `normalized` and `magnitude` add to the scale.
+        let scale = (fraction.len() as i64)
+            .checked_sub(exponent)
+            .filter(|scale| scale.checked_abs().is_some_and(|abs| abs <= i64::MAX / 2))
+            .ok_or_else(|| format!("exponent out of range in \"{}\"", input))?;
+
+        let digits = integer
+            .chars()
+            .chain(fraction.chars())
+            .rev()
+            .map(|c| c as u8 - b'0')
+            .collect();
+        Ok(Self {
+            negative,
+            digits,
+            scale,
+        }
+        .normalized())
+    }
+}
+
+impl fmt::Display for Decimal {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let magnitude = self.magnitude();
+        if !self.is_zero() && !(-7..21).contains(&magnitude) {
+            write!(f, "{}", self.to_scientific_string())
+        } else {
+            write!(f, "{}", self.to_plain_string())
+        }
+    }
+}
+
+impl PartialEq for Decimal {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Decimal {}
+
+impl PartialOrd for Decimal {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Decimal {
+    fn cmp(&self, other: &Self) -> Ordering {
+        match (self.is_negative(), other.is_negative()) {
+            (true, false) => return Ordering::Less,
+            (false, true) => return Ordering::Greater,
+            _ => {}
+        }
+        let scale = self.scale.max(other.scale);
+        let magnitude = cmp_digits(&self.with_scale(scale), &other.with_scale(scale));
+        if self.is_negative() {
+            magnitude.reverse()
+        } else {
+            magnitude
+        }
+    }
+}
+
+impl std::ops::Neg for &Decimal {
+    type Output = Decimal;
+
+    fn neg(self) -> Decimal {
+        Decimal {
+            negative: !self.negative,
+            ..self.clone()
+        }
+        .normalized()
+    }
+}
+
+impl std::ops::Add for &Decimal {
+    type Output = Decimal;
+
+    fn add(self, other: &Decimal) -> Decimal {
+        let scale = self.scale.max(other.scale);
+        let a = self.with_scale(scale);
+        let b = other.with_scale(scale);
+        let (negative, digits) = if self.is_negative() == other.is_negative() {
+            (self.is_negative(), add_digits(&a, &b))
+        } else if cmp_digits(&a, &b) != Ordering::Less {
+            (self.is_negative(), sub_digits(&a, &b))
+        } else {
+            (other.is_negative(), sub_digits(&b, &a))
+        };
+        Decimal {
+            negative,
+            digits,
+            scale,
+        }
+        .normalized()
+    }
+}
+
+impl std::ops::Sub for &Decimal {
+    type Output = Decimal;
+
+    fn sub(self, other: &Decimal) -> Decimal {
+        self + &(-other)
+    }
+}
+
+impl std::ops::Mul for &Decimal {
+    type Output = Decimal;
+
+    fn mul(self, other: &Decimal) -> Decimal {
+        Decimal {
+            negative: self.is_negative() != other.is_negative(),
+            digits: mul_digits(&self.digits, &other.digits),
+            scale: self.scale + other.scale,
+        }
+        .normalized()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn d(input: &str) -> Decimal {
+        input.parse().unwrap()
+    }
+
+    #[test]
+    fn rounding_modes() {
+        use RoundingMode::*;
+        // Columns follow `RoundingMode::ALL`: HalfEven, HalfUp, Down, Up,
+        // Ceiling, Floor.
+        let cases: [(&str, i64, [&str; 6]); 9] = [
+            ("2.5", 0, ["2", "3", "2", "3", "3", "2"]),
+            ("3.5", 0, ["4", "4", "3", "4", "4", "3"]),
+            ("-2.5", 0, ["-2", "-3", "-2", "-3", "-2", "-3"]),
+            ("-3.5", 0, ["-4", "-4", "-3", "-4", "-3", "-4"]),
+            ("2.51", 0, ["3", "3", "2", "3", "3", "2"]),
+            ("-2.4", 0, ["-2", "-2", "-2", "-3", "-2", "-3"]),
+            ("9.95", 1, ["10", "10", "9.9", "10", "10", "9.9"]),
+            ("0.05", 1, ["0", "0.1", "0", "0.1", "0.1", "0"]),
+            ("-0.0004", 0, ["0", "0", "0", "-1", "0", "-1"]),
+        ];
+        assert_eq!(RoundingMode::ALL, [HalfEven, HalfUp, Down, Up, Ceiling, Floor]);
+        for (input, places, expected) in cases {
+            for (mode, expected) in RoundingMode::ALL.into_iter().zip(expected) {
+                assert_eq!(
+                    d(input).round_to_places(places, mode).to_string(),
+                    expected,
+                    "{} to {} places, {:?}",
+                    input,
+                    places,
+                    mode
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn round_to_precision_counts_significant_digits() {
+        let round = |input: &str, precision| d(input).round_to_precision(precision, RoundingMode::HalfEven).to_string();
+        assert_eq!(round("123456", 3), "123000");
+        assert_eq!(round("0.00123456", 2), "0.0012");
+        assert_eq!(round("9.99", 2), "10");
+        assert_eq!(round("-0.5", 1), "-0.5");
+        assert_eq!(round("0", 5), "0");
+        assert_eq!(round("1.25", 2), "1.2");
+        assert_eq!(round("1.35", 2), "1.4");
+    }
+
+    #[test]
+    fn division_rounds_to_the_precision() {
+        let div = |a: &str, b: &str, precision, mode| d(a).div(&d(b), precision, mode).map(|q| q.to_string());
+        assert_eq!(div("1", "3", 5, RoundingMode::HalfEven).as_deref(), Some("0.33333"));
+        assert_eq!(div("2", "3", 5, RoundingMode::HalfEven).as_deref(), Some("0.66667"));
+        assert_eq!(div("2", "3", 5, RoundingMode::Down).as_deref(), Some("0.66666"));
+        assert_eq!(div("-1", "3", 3, RoundingMode::Floor).as_deref(), Some("-0.334"));
+        assert_eq!(div("-1", "3", 3, RoundingMode::Ceiling).as_deref(), Some("-0.333"));
+        assert_eq!(div("10", "4", 10, RoundingMode::HalfEven).as_deref(), Some("2.5"));
+        assert_eq!(div("1", "-8", 2, RoundingMode::HalfEven).as_deref(), Some("-0.12"));
+        assert_eq!(div("3", "8", 2, RoundingMode::HalfEven).as_deref(), Some("0.38"));
+        // Just over half must not be mistaken for an exact tie.
+        assert_eq!(div("1", "7.99999", 2, RoundingMode::HalfEven).as_deref(), Some("0.13"));
+        assert_eq!(div("123456789", "0.001", 3, RoundingMode::HalfEven).as_deref(), Some("123000000000"));
+        assert_eq!(div("0", "7", 5, RoundingMode::HalfEven).as_deref(), Some("0"));
+        assert_eq!(div("1", "0", 5, RoundingMode::HalfEven), None);
+    }
+
+    #[test]
+    fn display_switches_to_scientific_outside_the_plain_range() {
+        assert_eq!(d("1e20").to_string(), "100000000000000000000");
+        assert_eq!(d("1e21").to_string(), "1e21");
+        assert_eq!(d("-1.5e21").to_string(), "-1.5e21");
+        assert_eq!(d("1e-7").to_string(), "0.0000001");
+        assert_eq!(d("1.25e-8").to_string(), "1.25e-8");
+        assert_eq!(d("0").to_string(), "0");
+    }
+
+    #[test]
+    fn format_groups_thousands_and_switches_at_max_digits() {
+        let options = FormatOptions::default();
+        assert_eq!(d("1234567.5").format(options), "1,234,567.5");
+        assert_eq!(d("-1234").format(options), "-1,234");
+        assert_eq!(d("1e15").format(options), "1,000,000,000,000,000");
+        assert_eq!(d("1e16").format(options), "1e+16");
+        assert_eq!(d("0.000001").format(options), "0.000001");
+        assert_eq!(d("0.0000001").format(options), "1e-7");
+        assert_eq!(d("2").div(&d("3"), 30, RoundingMode::HalfEven).unwrap().format(options), "0.6666666666666667");
+        let plain = FormatOptions { thousands_separator: false, ..options };
+        assert_eq!(d("1234567").format(plain), "1234567");
+    }
+
+    #[test]
+    fn group_thousands_from_the_right() {
+        for (input, expected) in [
+            ("", ""),
+            ("1", "1"),
+            ("123", "123"),
+            ("1234", "1,234"),
+            ("123456", "123,456"),
+            ("1234567", "1,234,567"),
+        ] {
+            assert_eq!(group_thousands(input), expected);
+        }
+    }
+
+    #[test]
+    fn parsing_accepts_only_grouped_commas() {
+        assert_eq!(d("1,234"), d("1234"));
+        assert_eq!(d("-12,345,678.25"), d("-12345678.25"));
+        for input in ["1,2,3", "1234,567", ",123", "1,23", "1,234,", "1.2,3", "1e1,000"] {
+            assert!(input.parse::<Decimal>().is_err(), "{}", input);
+        }
+    }
+
+    #[test]
+    fn huge_exponents_are_errors() {
+        assert!("1e-9223372036854775808".parse::<Decimal>().is_err());
+        assert!("1e9223372036854775807".parse::<Decimal>().is_err());
+        assert!("1e99999999999999999999".parse::<Decimal>().is_err());
+        assert_eq!(d("1e-999999999").magnitude(), -999_999_999);
+    }
+}
@@ -0,0 +1,1254 @@
+use std::collections::BTreeMap;
+use std::fmt;
+
+use crate::decimal::{Decimal, RoundingMode, MAX_DIGITS};
+
+/// Significant digits kept for results of floating-point functions such
+/// as `sin` and `ln`, which are computed in `f64`.
+const FLOAT_DIGITS: u32 = 15;
+const PI: &str = "3.14159265358979323846264338327950288419716939937510582097494459";
+const E: &str = "2.71828182845904523536028747135266249775724709369995957496696763";
+/// How deeply user functions may call each other before evaluation gives up.
+const MAX_CALL_DEPTH: u32 = 64;
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum CalcError {
+    Syntax(String),
+    DivisionByZero,
+    Domain(String),
+    Overflow,
+    Underflow,
+}
+
+impl fmt::Display for CalcError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CalcError::Syntax(message) => write!(f, "Syntax error: {}", message),
+            CalcError::DivisionByZero => write!(f, "Cannot divide by zero"),
+            CalcError::Domain(message) => write!(f, "Math error: {}", message),
+            CalcError::Overflow => write!(f, "Result too large"),
+            CalcError::Underflow => write!(f, "Result too small"),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Token {
+    Number(Decimal),
+    Plus,
+    Minus,
+    Star,
+    Slash,
+    Caret,
+    Percent,
+    Bang,
+    Comma,
+    Equals,
+    LParen,
+    RParen,
+    Ident(String),
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum AngleMode {
+    #[default]
+    Degrees,
+    Radians,
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct Context {
+    pub angle_mode: AngleMode,
+    /// Significant digits kept after each operation.
+    pub precision: u32,
+    pub rounding: RoundingMode,
+}
+
+impl Default for Context {
+    fn default() -> Self {
+        Self {
+            angle_mode: AngleMode::default(),
+            precision: 32,
+            rounding: RoundingMode::default(),
+        }
+    }
+}
+
+impl Context {
+    fn round(&self, value: Decimal) -> Decimal {
+        value.round_to_precision(self.precision, self.rounding)
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Function {
+    Sin,
+    Cos,
+    Tan,
+    Asin,
+    Acos,
+    Atan,
+    Ln,
+    Log,
+    Exp,
+    Sqrt,
+    Cbrt,
+    Root,
+    Abs,
+}
+
+/// sin and cos of an angle in degrees. The angle is reduced to a turn first
+/// and multiples of 90° are exact, where going through radians would leave
+/// residues like sin(180°) = 1.2e-16.
+fn sin_cos_degrees(angle: f64) -> (f64, f64) {
+    let reduced = angle.rem_euclid(360.0);
+    if reduced % 90.0 != 0.0 {
+        return reduced.to_radians().sin_cos();
+    }
+    match (reduced / 90.0) as u8 {
+        0 => (0.0, 1.0),
+        1 => (1.0, 0.0),
+        2 => (0.0, -1.0),
+        _ => (-1.0, 0.0),
+    }
+}
+
+impl Function {
+    fn lookup(name: &str) -> Option<Self> {
+        match name {
+            "sin" => Some(Function::Sin),
+            "cos" => Some(Function::Cos),
+            "tan" => Some(Function::Tan),
+            "asin" => Some(Function::Asin),
+            "acos" => Some(Function::Acos),
+            "atan" => Some(Function::Atan),
+            "ln" => Some(Function::Ln),
+            "log" => Some(Function::Log),
+            "exp" => Some(Function::Exp),
+            "sqrt" | "√" => Some(Function::Sqrt),
+            "cbrt" | "∛" => Some(Function::Cbrt),
+            "root" => Some(Function::Root),
+            "abs" => Some(Function::Abs),
+            _ => None,
+        }
+    }
+
+    fn arity(self) -> usize {
+        match self {
+            Function::Root => 2,
+            _ => 1,
+        }
+    }
+
+    fn apply(self, args: &[f64], ctx: &Context) -> Result<f64, CalcError> {
+        let x = args[0];
+        let to_radians = |angle: f64| match ctx.angle_mode {
+            AngleMode::Degrees => angle.to_radians(),
+            AngleMode::Radians => angle,
+        };
+        let from_radians = |angle: f64| match ctx.angle_mode {
+            AngleMode::Degrees => angle.to_degrees(),
+            AngleMode::Radians => angle,
+        };
+        let domain = |message: &str| Err(CalcError::Domain(message.to_string()));
+
+        let degrees = ctx.angle_mode == AngleMode::Degrees;
+
+        match self {
+            Function::Sin if degrees => Ok(sin_cos_degrees(x).0),
+            Function::Cos if degrees => Ok(sin_cos_degrees(x).1),
+            Function::Tan if degrees => {
+                let reduced = x.rem_euclid(180.0);
+                if reduced == 90.0 {
+                    return domain("tan is undefined here");
+                }
+                Ok(if reduced == 0.0 { 0.0 } else { reduced.to_radians().tan() })
+            }
+            Function::Sin => Ok(to_radians(x).sin()),
+            Function::Cos => Ok(to_radians(x).cos()),
+            Function::Tan => {
+                let radians = to_radians(x);
+                // tan is undefined at odd multiples of 90°.
+                if ((radians / std::f64::consts::FRAC_PI_2).rem_euclid(2.0) - 1.0).abs() < 1e-12 {
+                    return domain("tan is undefined here");
+                }
+                Ok(radians.tan())
+            }
+            Function::Asin if !(-1.0..=1.0).contains(&x) => domain("asin needs -1 ≤ x ≤ 1"),
+            Function::Asin => Ok(from_radians(x.asin())),
+            Function::Acos if !(-1.0..=1.0).contains(&x) => domain("acos needs -1 ≤ x ≤ 1"),
+            Function::Acos => Ok(from_radians(x.acos())),
+            Function::Atan => Ok(from_radians(x.atan())),
+            Function::Ln | Function::Log if x <= 0.0 => domain("logarithm needs x > 0"),
+            Function::Ln => Ok(x.ln()),
+            Function::Log => Ok(x.log10()),
+            Function::Exp => Ok(x.exp()),
+            Function::Sqrt if x < 0.0 => domain("square root needs x ≥ 0"),
+            Function::Sqrt => Ok(x.sqrt()),
+            Function::Cbrt => Ok(x.cbrt()),
+            Function::Root => {
+                let n = args[1];
+                if n == 0.0 {
+                    return domain("root degree can't be 0");
+                }
+                let odd_integer = n.fract() == 0.0 && n.rem_euclid(2.0) == 1.0;
+                if x < 0.0 && !odd_integer {
+                    return domain("even root of a negative number");
+                }
+                Ok(x.signum() * x.abs().powf(1.0 / n))
+            }
+            Function::Abs => Ok(x.abs()),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Constant {
+    Pi,
+    E,
+}
+
+impl Constant {
+    fn lookup(name: &str) -> Option<Self> {
+        match name {
+            "pi" | "π" => Some(Constant::Pi),
+            "e" => Some(Constant::E),
+            _ => None,
+        }
+    }
+
+    fn value(self) -> Decimal {
+        let digits = match self {
+            Constant::Pi => PI,
+            Constant::E => E,
+        };
+        digits.parse().unwrap_or_default()
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum BinaryOp {
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Pow,
+}
+
+impl BinaryOp {
+    fn precedence(self) -> u8 {
+        match self {
+            BinaryOp::Add | BinaryOp::Sub => 1,
+            BinaryOp::Mul | BinaryOp::Div => 2,
+            BinaryOp::Pow => 4,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Expr {
+    Number(Decimal),
+    Constant(Constant),
+    Variable(String),
+    /// A call to a name that isn't built in, resolved at evaluation time.
+    UserCall(String, Vec<Expr>),
+    Neg(Box<Expr>),
+    Percent(Box<Expr>),
+    Factorial(Box<Expr>),
+    Call(Function, Vec<Expr>),
+    Binary(BinaryOp, Box<Expr>, Box<Expr>),
+}
+
+/// Splits the input into tokens. Accepts the display glyphs `×`, `÷` and
+/// `−` as well as their ASCII forms.
+pub fn tokenize(input: &str) -> Result<Vec<Token>, CalcError> {
+    let chars: Vec<char> = input.chars().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+
+    while i < chars.len() {
+        let c = chars[i];
+        match c {
+            ' ' | '\t' => {}
+            '0'..='9' | '.' => {
+                let start = i;
+                while i < chars.len() && (chars[i].is_ascii_digit() || chars[i] == '.') {
+                    i += 1;
+                }
+                if i < chars.len()
+                    && (chars[i] == 'e' || chars[i] == 'E')
+                    && chars
+                        .get(i + 1)
+                        .is_some_and(|n| n.is_ascii_digit() || *n == '-' || *n == '+')
+                {
+                    i += 2;
+                    while i < chars.len() && chars[i].is_ascii_digit() {
+                        i += 1;
+                    }
+                }
+                let literal: String = chars[start..i].iter().collect();
+                let value = literal
+                    .parse::<Decimal>()
+                    .map_err(|_| CalcError::Syntax(format!("invalid number \"{}\"", literal)))?;
+                tokens.push(Token::Number(value));
+                continue;
+            }
+            '+' => tokens.push(Token::Plus),
+            '-' | '−' => tokens.push(Token::Minus),
+            '*' | '×' => tokens.push(Token::Star),
+            '/' | '÷' => tokens.push(Token::Slash),
+            '^' => tokens.push(Token::Caret),
+            '%' => tokens.push(Token::Percent),
+            '!' => tokens.push(Token::Bang),
+            ',' => tokens.push(Token::Comma),
+            '=' => tokens.push(Token::Equals),
+            'π' | '√' | '∛' => tokens.push(Token::Ident(c.to_string())),
+            'a'..='z' | 'A'..='Z' => {
+                let start = i;
+                while i < chars.len() && (chars[i].is_ascii_alphanumeric() || chars[i] == '_') {
+                    i += 1;
+                }
+                tokens.push(Token::Ident(chars[start..i].iter().collect::<String>().to_lowercase()));
+                continue;
+            }
+            '(' => tokens.push(Token::LParen),
+            ')' => tokens.push(Token::RParen),
+            _ => return Err(CalcError::Syntax(format!("unexpected \"{}\"", c))),
+        }
+        i += 1;
+    }
+
+    Ok(tokens)
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.pos).cloned();
+        self.pos += 1;
+        token
+    }
+
+    /// Returns the operator at the cursor and whether it is written out.
+    /// A name or `(` directly after an operand is an implicit `×`, so
+    /// `2π` and `3(4+1)` work.
+    fn binary_op(&self) -> Option<(BinaryOp, bool)> {
+        match self.peek()? {
+            Token::Plus => Some((BinaryOp::Add, true)),
+            Token::Minus => Some((BinaryOp::Sub, true)),
+            Token::Star => Some((BinaryOp::Mul, true)),
+            Token::Slash => Some((BinaryOp::Div, true)),
+            Token::Ident(_) | Token::LParen => Some((BinaryOp::Mul, false)),
+            _ => None,
+        }
+    }
+
+    /// Precedence climbing over the left-associative binary operators.
+    fn expression(&mut self, min_precedence: u8) -> Result<Expr, CalcError> {
+        let mut lhs = self.unary()?;
+        while let Some((op, explicit)) = self.binary_op() {
+            if op.precedence() < min_precedence {
+                break;
+            }
+            if explicit {
+                self.pos += 1;
+            }
+            let rhs = self.expression(op.precedence() + 1)?;
+            lhs = Expr::Binary(op, Box::new(lhs), Box::new(rhs));
+        }
+        Ok(lhs)
+    }
+
+    /// Unary minus binds looser than `^`, so `-2^2` is `-(2^2)`.
+    fn unary(&mut self) -> Result<Expr, CalcError> {
+        match self.peek() {
+            Some(Token::Minus) => {
+                self.pos += 1;
+                Ok(Expr::Neg(Box::new(self.unary()?)))
+            }
+            Some(Token::Plus) => {
+                self.pos += 1;
+                self.unary()
+            }
+            _ => self.power(),
+        }
+    }
+
+    /// `^` is right-associative and its exponent may carry a sign.
+    fn power(&mut self) -> Result<Expr, CalcError> {
+        let base = self.postfix()?;
+        if self.peek() == Some(&Token::Caret) {
+            self.pos += 1;
+            let exponent = self.unary()?;
+            return Ok(Expr::Binary(BinaryOp::Pow, Box::new(base), Box::new(exponent)));
+        }
+        Ok(base)
+    }
+
+    fn postfix(&mut self) -> Result<Expr, CalcError> {
+        let mut expr = self.primary()?;
+        loop {
+            match self.peek() {
+                Some(Token::Percent) => expr = Expr::Percent(Box::new(expr)),
+                Some(Token::Bang) => expr = Expr::Factorial(Box::new(expr)),
+                _ => return Ok(expr),
+            }
+            self.pos += 1;
+        }
+    }
+
+    fn primary(&mut self) -> Result<Expr, CalcError> {
+        match self.next() {
+            Some(Token::Number(value)) => Ok(Expr::Number(value)),
+            Some(Token::LParen) => {
+                let inner = self.expression(0)?;
+                match self.next() {
+                    Some(Token::RParen) => Ok(inner),
+                    _ => Err(CalcError::Syntax("missing \")\"".to_string())),
+                }
+            }
+            Some(Token::Ident(name)) => {
+                if let Some(constant) = Constant::lookup(&name) {
+                    return Ok(Expr::Constant(constant));
+                }
+                let Some(function) = Function::lookup(&name) else {
+                    if self.peek() == Some(&Token::LParen) {
+                        return Ok(Expr::UserCall(name, self.arguments()?));
+                    }
+                    return Ok(Expr::Variable(name));
+                };
+                // Single-argument functions may omit parentheses: `√9`, `sin 30`.
+                if self.peek() != Some(&Token::LParen) && function.arity() == 1 {
+                    let arg = self.power()?;
+                    return Ok(Expr::Call(function, vec![arg]));
+                }
+                let args = self.arguments()?;
+                if args.len() != function.arity() {
+                    return Err(CalcError::Syntax(format!(
+                        "{} takes {} argument(s), got {}",
+                        name,
+                        function.arity(),
+                        args.len()
+                    )));
+                }
+                Ok(Expr::Call(function, args))
+            }
+            Some(token) => Err(CalcError::Syntax(format!("unexpected {}", describe(&token)))),
+            None => Err(CalcError::Syntax("unexpected end of expression".to_string())),
+        }
+    }
+}
+
+impl Parser {
+    fn arguments(&mut self) -> Result<Vec<Expr>, CalcError> {
+        if self.next() != Some(Token::LParen) {
+            return Err(CalcError::Syntax("expected \"(\"".to_string()));
+        }
+        let mut args = vec![self.expression(0)?];
+        loop {
+            match self.next() {
+                Some(Token::Comma) => args.push(self.expression(0)?),
+                Some(Token::RParen) => return Ok(args),
+                _ => return Err(CalcError::Syntax("missing \")\"".to_string())),
+            }
+        }
+    }
+}
+
+fn describe(token: &Token) -> String {
+    match token {
+        Token::Number(value) => value.to_string(),
+        Token::Plus => "\"+\"".to_string(),
+        Token::Minus => "\"−\"".to_string(),
+        Token::Star => "\"×\"".to_string(),
+        Token::Slash => "\"÷\"".to_string(),
+        Token::Caret => "\"^\"".to_string(),
+        Token::Percent => "\"%\"".to_string(),
+        Token::Bang => "\"!\"".to_string(),
+        Token::Comma => "\",\"".to_string(),
+        Token::Equals => "\"=\"".to_string(),
+        Token::Ident(name) => format!("\"{}\"", name),
+        Token::LParen => "\"(\"".to_string(),
+        Token::RParen => "\")\"".to_string(),
+    }
+}
+
+impl Parser {
+    fn finish(&mut self) -> Result<Expr, CalcError> {
+        let expr = self.expression(0)?;
+        match self.peek() {
+            None => Ok(expr),
+            Some(token) => Err(CalcError::Syntax(format!("unexpected {}", describe(token)))),
+        }
+    }
+
+    fn name(&mut self) -> Result<String, CalcError> {
+        match self.next() {
+            Some(Token::Ident(name)) if is_reserved(&name) => {
+                Err(CalcError::Syntax(format!("\"{}\" is a built-in name", name)))
+            }
+            Some(Token::Ident(name)) => Ok(name),
+            _ => Err(CalcError::Syntax("expected a name".to_string())),
+        }
+    }
+}
+
+pub fn parse(input: &str) -> Result<Expr, CalcError> {
+    Parser {
+        tokens: tokenize(input)?,
+        pos: 0,
+    }
+    .finish()
+}
+
+fn is_reserved(name: &str) -> bool {
+    matches!(name, "let" | "ans") || Function::lookup(name).is_some() || Constant::lookup(name).is_some()
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct UserFunction {
+    pub params: Vec<String>,
+    pub body: Expr,
+    /// The definition as typed, for display and persistence.
+    pub source: String,
+}
+
+/// Names the user has defined, plus the last result as `ans`.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Environment {
+    pub variables: BTreeMap<String, Decimal>,
+    pub functions: BTreeMap<String, UserFunction>,
+    pub ans: Option<Decimal>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Statement {
+    Expr(Expr),
+    /// `let rate = 0.07`
+    Let(String, Expr),
+    /// `f(x) = x^2 + 1`
+    Define(String, UserFunction),
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Outcome {
+    Value(Decimal),
+    Assigned(String, Decimal),
+    Defined(String),
+}
+
+impl Outcome {
+    pub fn value(&self) -> Option<&Decimal> {
+        match self {
+            Outcome::Value(value) | Outcome::Assigned(_, value) => Some(value),
+            Outcome::Defined(_) => None,
+        }
+    }
+}
+
+pub fn parse_statement(input: &str) -> Result<Statement, CalcError> {
+    let tokens = tokenize(input)?;
+    let mut parser = Parser { tokens, pos: 0 };
+
+    if parser.peek() == Some(&Token::Ident("let".to_string())) {
+        parser.pos += 1;
+        let name = parser.name()?;
+        if parser.next() != Some(Token::Equals) {
+            return Err(CalcError::Syntax("expected \"=\" after the name".to_string()));
+        }
+        return Ok(Statement::Let(name, parser.finish()?));
+    }
+
+    if !parser.tokens.contains(&Token::Equals) {
+        return Ok(Statement::Expr(parser.finish()?));
+    }
+
+    let name = parser.name()?;
+    let mut params = Vec::new();
+    if parser.next() != Some(Token::LParen) {
+        return Err(CalcError::Syntax("use \"let\" to assign a variable".to_string()));
+    }
+    loop {
+        let param = parser.name()?;
+        if params.contains(&param) {
+            return Err(CalcError::Syntax(format!("parameter \"{}\" repeated", param)));
+        }
+        params.push(param);
+        match parser.next() {
+            Some(Token::Comma) => {}
+            Some(Token::RParen) => break,
+            _ => return Err(CalcError::Syntax("missing \")\"".to_string())),
+        }
+    }
+    if parser.next() != Some(Token::Equals) {
+        return Err(CalcError::Syntax("expected \"=\"".to_string()));
+    }
+    let body = parser.finish()?;
+    Ok(Statement::Define(
+        name,
+        UserFunction {
+            params,
+            body,
+            source: input.trim().to_string(),
+        },
+    ))
+}
+
+/// Runs one line of input against the environment, updating variables,
+/// functions and `ans` as it goes.
+pub fn run(input: &str, env: &mut Environment, ctx: &Context) -> Result<Outcome, CalcError> {
+    let outcome = match parse_statement(input)? {
+        Statement::Expr(expr) => Outcome::Value(evaluate(&expr, env, ctx)?),
+        Statement::Let(name, expr) => {
+            let value = evaluate(&expr, env, ctx)?;
+            env.variables.insert(name.clone(), value.clone());
+            Outcome::Assigned(name, value)
+        }
+        Statement::Define(name, function) => {
+            env.functions.insert(name.clone(), function);
+            Outcome::Defined(name)
+        }
+    };
+    if let Some(value) = outcome.value() {
+        env.ans = Some(value.clone());
+    }
+    Ok(outcome)
+}
+
+/// Evaluates in decimal arithmetic. `+`, `−` and `×` are exact, `÷` and
+/// negative powers keep `ctx.precision` significant digits, and functions
+/// without an exact decimal form go through `f64`.
+pub fn evaluate(expr: &Expr, env: &Environment, ctx: &Context) -> Result<Decimal, CalcError> {
+    Evaluator {
+        env,
+        ctx,
+        locals: Vec::new(),
+        depth: 0,
+    }
+    .eval(expr)
+}
+
+struct Evaluator<'a> {
+    env: &'a Environment,
+    ctx: &'a Context,
+    /// Parameters of the user function being evaluated.
+    locals: Vec<(String, Decimal)>,
+    depth: u32,
+}
+
+impl Evaluator<'_> {
+    fn lookup(&self, name: &str) -> Result<Decimal, CalcError> {
+        if let Some((_, value)) = self.locals.iter().find(|(local, _)| local == name) {
+            return Ok(value.clone());
+        }
+        if let Some(value) = self.env.variables.get(name) {
+            return Ok(value.clone());
+        }
+        match (name, &self.env.ans) {
+            ("ans", Some(ans)) => Ok(ans.clone()),
+            ("ans", None) => Err(CalcError::Syntax("no previous answer for \"ans\"".to_string())),
+            _ => Err(CalcError::Syntax(format!("unknown name \"{}\"", name))),
+        }
+    }
+
+    fn call(&mut self, name: &str, args: &[Expr]) -> Result<Decimal, CalcError> {
+        let Some(function) = self.env.functions.get(name) else {
+            // `k(2 + 1)` with `k` a variable is an implicit multiplication.
+            return match args {
+                [arg] => Ok(&self.lookup(name)? * &self.eval(arg)?),
+                _ => Err(CalcError::Syntax(format!("unknown function \"{}\"", name))),
+            };
+        };
+        if args.len() != function.params.len() {
+            return Err(CalcError::Syntax(format!(
+                "{} takes {} argument(s), got {}",
+                name,
+                function.params.len(),
+                args.len()
+            )));
+        }
+        if self.depth >= MAX_CALL_DEPTH {
+            return Err(CalcError::Domain("function calls nest too deeply".to_string()));
+        }
+        let mut bound = Vec::with_capacity(args.len());
+        for (param, arg) in function.params.iter().zip(args) {
+            bound.push((param.clone(), self.eval(arg)?));
+        }
+        let caller = std::mem::replace(&mut self.locals, bound);
+        self.depth += 1;
+        let result = self.eval(&function.body);
+        self.depth -= 1;
+        self.locals = caller;
+        result
+    }
+
+    fn eval(&mut self, expr: &Expr) -> Result<Decimal, CalcError> {
+        let ctx = self.ctx;
+        let value = match expr {
+            Expr::Number(value) => value.clone(),
+            Expr::Constant(constant) => constant.value(),
+            Expr::Variable(name) => self.lookup(name)?,
+            Expr::UserCall(name, args) => self.call(name, args)?,
+            Expr::Neg(inner) => -&self.eval(inner)?,
+            Expr::Percent(inner) => self.eval(inner)?.mul_pow10(-2),
+            Expr::Factorial(inner) => factorial(&self.eval(inner)?)?,
+            Expr::Call(Function::Sqrt, args) => {
+                let x = self.eval(&args[0])?;
+                if x.is_negative() {
+                    return Err(CalcError::Domain("square root needs x ≥ 0".to_string()));
+                }
+                sqrt(&x, ctx)
+            }
+            Expr::Call(Function::Abs, args) => self.eval(&args[0])?.abs(),
+            Expr::Call(function, args) => {
+                let values = args
+                    .iter()
+                    .map(|arg| self.eval(arg).map(|v| v.to_f64()))
+                    .collect::<Result<Vec<_>, _>>()?;
+                from_float(function.apply(&values, ctx)?)?
+            }
+            Expr::Binary(op, lhs, rhs) => {
+                let left = self.eval(lhs)?;
+                // `a + b%` and `a - b%` add or subtract b percent of a, as on
+                // a desk calculator.
+                let right = match (op, rhs.as_ref()) {
+                    (BinaryOp::Add | BinaryOp::Sub, Expr::Percent(inner)) => {
+                        (&left * &self.eval(inner)?).mul_pow10(-2)
+                    }
+                    _ => self.eval(rhs)?,
+                };
+                match op {
+                    BinaryOp::Add => &left + &right,
+                    BinaryOp::Sub => &left - &right,
+                    BinaryOp::Mul => &left * &right,
+                    BinaryOp::Div => left
+                        .div(&right, ctx.precision, ctx.rounding)
+                        .ok_or(CalcError::DivisionByZero)?,
+                    BinaryOp::Pow => power(&left, &right, ctx)?,
+                }
+            }
+        };
+
+        if value.magnitude() > MAX_DIGITS as i64 {
+            return Err(CalcError::Overflow);
+        }
+        // Lining up digits with a value this small would take as many
+        // digits as its exponent, so it stops here rather than in `+`.
+        if value.magnitude() < -(MAX_DIGITS as i64) {
+            return Err(CalcError::Underflow);
+        }
+        Ok(ctx.round(value))
+    }
+}
+
+fn from_float(value: f64) -> Result<Decimal, CalcError> {
+    if value.is_infinite() {
+        return Err(CalcError::Overflow);
+    }
+    let decimal = Decimal::from_f64(value)
+        .ok_or_else(|| CalcError::Domain("undefined result".to_string()))?;
+    Ok(decimal.round_to_precision(FLOAT_DIGITS, RoundingMode::HalfEven))
+}
+
+fn power(base: &Decimal, exponent: &Decimal, ctx: &Context) -> Result<Decimal, CalcError> {
+    if let Some(n) = exponent.to_i64() {
+        if base.is_zero() && n < 0 {
+            return Err(CalcError::DivisionByZero);
+        }
+        // `powi` gives up once the digits run past MAX_DIGITS; whether that
+        // is a huge or a tiny result depends on which side of 1 it heads.
+        let shrinks = (base.abs() < Decimal::from_i64(1)) == (n > 0);
+        return base
+            .powi(n, ctx.precision, ctx.rounding)
+            .ok_or(if shrinks { CalcError::Underflow } else { CalcError::Overflow });
+    }
+    if base.is_negative() {
+        return Err(CalcError::Domain("fractional power of a negative number".to_string()));
+    }
+    from_float(base.to_f64().powf(exponent.to_f64()))
+}
+
+/// Newton's method, carried out at the context precision.
+fn sqrt(x: &Decimal, ctx: &Context) -> Decimal {
+    if x.is_zero() {
+        return Decimal::zero();
+    }
+    let two = Decimal::from_i64(2);
+    let guard = ctx.precision + 4;
+    let mut estimate = Decimal::from_f64(x.to_f64().sqrt())
+        .filter(|e| !e.is_zero())
+        .unwrap_or_else(|| x.clone());
+    for _ in 0..100 {
+        let quotient = x.div(&estimate, guard, ctx.rounding).unwrap_or_default();
+        let next = (&estimate + &quotient)
+            .div(&two, guard, ctx.rounding)
+            .unwrap_or_default();
+        if next == estimate {
+            break;
+        }
+        estimate = next;
+    }
+    estimate
+}
+
+fn factorial(n: &Decimal) -> Result<Decimal, CalcError> {
+    if n.is_negative() || !n.is_integer() {
+        return Err(CalcError::Domain("factorial needs a whole number ≥ 0".to_string()));
+    }
+    let Some(n) = n.to_i64() else {
+        return Err(CalcError::Overflow);
+    };
+    let mut result = Decimal::from_i64(1);
+    for k in 2..=n {
+        result = &result * &Decimal::from_i64(k);
+        if result.magnitude() > MAX_DIGITS as i64 {
+            return Err(CalcError::Overflow);
+        }
+    }
+    Ok(result)
+}
+
+/// An expression prepared for plotting in `f64`. Literals, constants and
+/// the environment's variables are converted once here rather than at every
+/// sample.
+pub struct FloatFn {
+    expr: FloatExpr,
+    functions: BTreeMap<String, (Vec<String>, FloatExpr)>,
+    ctx: Context,
+}
+
+/// `Expr` with every number already in `f64`.
+enum FloatExpr {
+    Number(f64),
+    /// A name bound by the call being evaluated, or else the global value.
+    Variable(String, f64),
+    /// A user function call, or `name × arg` when no such function exists.
+    UserCall(String, Vec<FloatExpr>, f64),
+    Neg(Box<FloatExpr>),
+    Percent(Box<FloatExpr>),
+    Factorial(Box<FloatExpr>),
+    Call(Function, Vec<FloatExpr>),
+    Binary(BinaryOp, Box<FloatExpr>, Box<FloatExpr>),
+}
+
+impl FloatExpr {
+    fn new(expr: &Expr, env: &Environment) -> Self {
+        let compile = |inner: &Expr| Self::new(inner, env);
+        let boxed = |inner: &Expr| Box::new(compile(inner));
+        let global = |name: &str| {
+            env.variables
+                .get(name)
+                .or(env.ans.as_ref().filter(|_| name == "ans"))
+                .map_or(f64::NAN, Decimal::to_f64)
+        };
+        match expr {
+            Expr::Number(value) => FloatExpr::Number(value.to_f64()),
+            Expr::Constant(Constant::Pi) => FloatExpr::Number(std::f64::consts::PI),
+            Expr::Constant(Constant::E) => FloatExpr::Number(std::f64::consts::E),
+            Expr::Variable(name) => FloatExpr::Variable(name.clone(), global(name)),
+            Expr::UserCall(name, args) => {
+                FloatExpr::UserCall(name.clone(), args.iter().map(compile).collect(), global(name))
+            }
+            Expr::Neg(inner) => FloatExpr::Neg(boxed(inner)),
+            Expr::Percent(inner) => FloatExpr::Percent(boxed(inner)),
+            Expr::Factorial(inner) => FloatExpr::Factorial(boxed(inner)),
+            Expr::Call(function, args) => FloatExpr::Call(*function, args.iter().map(compile).collect()),
+            Expr::Binary(op, lhs, rhs) => FloatExpr::Binary(*op, boxed(lhs), boxed(rhs)),
+        }
+    }
+}
+
+impl FloatFn {
+    pub fn new(expr: &Expr, env: &Environment, ctx: &Context) -> Self {
+        Self {
+            expr: FloatExpr::new(expr, env),
+            functions: env
+                .functions
+                .iter()
+                .map(|(name, function)| {
+                    (name.clone(), (function.params.clone(), FloatExpr::new(&function.body, env)))
+                })
+                .collect(),
+            ctx: *ctx,
+        }
+    }
+
+    /// Evaluates with `x` bound to the given value. Anything undefined
+    /// comes back as NaN.
+    pub fn eval(&self, x: f64) -> f64 {
+        self.float(&self.expr, &[("x", x)], 0)
+    }
+
+    fn float(&self, expr: &FloatExpr, locals: &[(&str, f64)], depth: u32) -> f64 {
+        let eval = |inner: &FloatExpr| self.float(inner, locals, depth);
+        let lookup = |name: &str, global: f64| {
+            locals
+                .iter()
+                .find(|(local, _)| *local == name)
+                .map_or(global, |(_, value)| *value)
+        };
+        match expr {
+            FloatExpr::Number(value) => *value,
+            FloatExpr::Variable(name, global) => lookup(name, *global),
+            FloatExpr::UserCall(name, args, global) => match self.functions.get(name) {
+                Some((params, body)) if params.len() == args.len() && depth < MAX_CALL_DEPTH => {
+                    let bound: Vec<(&str, f64)> =
+                        params.iter().map(String::as_str).zip(args.iter().map(eval)).collect();
+                    self.float(body, &bound, depth + 1)
+                }
+                None if args.len() == 1 => lookup(name, *global) * eval(&args[0]),
+                _ => f64::NAN,
+            },
+            FloatExpr::Neg(inner) => -eval(inner),
+            FloatExpr::Percent(inner) => eval(inner) / 100.0,
+            FloatExpr::Factorial(inner) => {
+                let n = eval(inner);
+                if n < 0.0 || n.fract() != 0.0 || n > 170.0 {
+                    return f64::NAN;
+                }
+                (2..=n as u32).map(f64::from).product()
+            }
+            FloatExpr::Call(function, args) => {
+                let values: Vec<f64> = args.iter().map(eval).collect();
+                function.apply(&values, &self.ctx).unwrap_or(f64::NAN)
+            }
+            FloatExpr::Binary(op, lhs, rhs) => {
+                let left = eval(lhs);
+                let right = match (op, rhs.as_ref()) {
+                    (BinaryOp::Add | BinaryOp::Sub, FloatExpr::Percent(inner)) => left * eval(inner) / 100.0,
+                    _ => eval(rhs),
+                };
+                match op {
+                    BinaryOp::Add => left + right,
+                    BinaryOp::Sub => left - right,
+                    BinaryOp::Mul => left * right,
+                    BinaryOp::Div => left / right,
+                    BinaryOp::Pow => left.powf(right),
+                }
+            }
+        }
+    }
+}
+
+pub fn eval_str(input: &str, ctx: &Context) -> Result<Decimal, CalcError> {
+    evaluate(&parse(input)?, &Environment::default(), ctx)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A naive evaluator for the operator grammar over whole numbers:
+    /// `+`, `-`, `*`, `^`, unary signs and parentheses. `None` when a step
+    /// leaves the small integers, e.g. a negative exponent.
+    struct Reference<'a> {
+        input: &'a [u8],
+        pos: usize,
+    }
+
+    impl Reference<'_> {
+        const LIMIT: i128 = 1_000_000_000_000_000_000;
+
+        fn eval(input: &str) -> Option<i128> {
+            let mut reference = Reference {
+                input: input.as_bytes(),
+                pos: 0,
+            };
+            let value = reference.sum()?;
+            (reference.pos == input.len()).then_some(value)
+        }
+
+        fn peek(&self) -> Option<u8> {
+            self.input.get(self.pos).copied()
+        }
+
+        fn checked(value: Option<i128>) -> Option<i128> {
+            value.filter(|v| v.abs() <= Self::LIMIT)
+        }
+
+        fn sum(&mut self) -> Option<i128> {
+            let mut value = self.product()?;
+            while let Some(op @ (b'+' | b'-')) = self.peek() {
+                self.pos += 1;
+                let rhs = self.product()?;
+                value = Self::checked(if op == b'+' { value.checked_add(rhs) } else { value.checked_sub(rhs) })?;
+            }
+            Some(value)
+        }
+
+        fn product(&mut self) -> Option<i128> {
+            let mut value = self.signed()?;
+            while self.peek() == Some(b'*') {
+                self.pos += 1;
+                let rhs = self.signed()?;
+                value = Self::checked(value.checked_mul(rhs))?;
+            }
+            Some(value)
+        }
+
+        fn signed(&mut self) -> Option<i128> {
+            match self.peek() {
+                Some(b'-') => {
+                    self.pos += 1;
+                    Some(-self.signed()?)
+                }
+                Some(b'+') => {
+                    self.pos += 1;
+                    self.signed()
+                }
+                _ => self.power(),
+            }
+        }
+
+        fn power(&mut self) -> Option<i128> {
+            let base = self.atom()?;
+            if self.peek() != Some(b'^') {
+                return Some(base);
+            }
+            self.pos += 1;
+            let exponent = u32::try_from(self.signed()?).ok().filter(|e| *e <= 64)?;
+            Self::checked(base.checked_pow(exponent))
+        }
+
+        fn atom(&mut self) -> Option<i128> {
+            if self.peek() == Some(b'(') {
+                self.pos += 1;
+                let value = self.sum()?;
+                (self.peek() == Some(b')')).then_some(())?;
+                self.pos += 1;
+                return Some(value);
+            }
+            let start = self.pos;
+            while self.peek().is_some_and(|c| c.is_ascii_digit()) {
+                self.pos += 1;
+            }
+            std::str::from_utf8(&self.input[start..self.pos]).ok()?.parse().ok()
+        }
+    }
+
+    /// Writes random expressions in the same grammar.
+    struct Generator(u64);
+
+    impl Generator {
+        fn below(&mut self, n: u64) -> u64 {
+            // xorshift64
+            self.0 ^= self.0 << 13;
+            self.0 ^= self.0 >> 7;
+            self.0 ^= self.0 << 17;
+            self.0 % n
+        }
+
+        fn sum(&mut self, out: &mut String, depth: u32) {
+            self.product(out, depth);
+            for _ in 0..self.below(3) {
+                out.push(if self.below(2) == 0 { '+' } else { '-' });
+                self.product(out, depth);
+            }
+        }
+
+        fn product(&mut self, out: &mut String, depth: u32) {
+            self.signed(out, depth);
+            for _ in 0..self.below(3) {
+                out.push('*');
+                self.signed(out, depth);
+            }
+        }
+
+        fn signed(&mut self, out: &mut String, depth: u32) {
+            match self.below(8) {
+                0 | 1 => {
+                    out.push('-');
+                    self.signed(out, depth);
+                }
+                2 => {
+                    out.push('+');
+                    self.signed(out, depth);
+                }
+                _ => self.power(out, depth),
+            }
+        }
+
+        fn power(&mut self, out: &mut String, depth: u32) {
+            self.atom(out, depth);
+            if self.below(4) == 0 {
+                out.push('^');
+                // Mostly small exponents, sometimes a chain like 2^3^2.
+                if self.below(3) == 0 {
+                    self.power(out, depth.saturating_sub(1));
+                } else {
+                    out.push_str(&self.below(4).to_string());
+                }
+            }
+        }
+
+        fn atom(&mut self, out: &mut String, depth: u32) {
+            if depth > 0 && self.below(4) == 0 {
+                out.push('(');
+                self.sum(out, depth - 1);
+                out.push(')');
+            } else {
+                out.push_str(&self.below(20).to_string());
+            }
+        }
+    }
+
+    #[test]
+    fn huge_integers_overflow_instead_of_panicking() {
+        let ctx = Context::default();
+        assert_eq!(eval_str("2^1e40", &ctx), Err(CalcError::Overflow));
+        assert_eq!(eval_str("(1e40)!", &ctx), Err(CalcError::Overflow));
+        assert_eq!(eval_str("1e19 + 1", &ctx).unwrap().to_i64(), None);
+        assert_eq!(eval_str("-9e18", &ctx).unwrap().to_i64(), Some(-9_000_000_000_000_000_000));
+    }
+
+    #[test]
+    fn degree_trig_is_exact_at_right_angles() {
+        let ctx = Context { angle_mode: AngleMode::Degrees, ..Context::default() };
+        let show = |input: &str| eval_str(input, &ctx).map(|value| value.to_string());
+        for (input, expected) in [
+            ("sin(180)", "0"),
+            ("sin(360)", "0"),
+            ("sin(-180)", "0"),
+            ("sin(90)", "1"),
+            ("sin(270)", "-1"),
+            ("sin(450)", "1"),
+            ("sin(-90)", "-1"),
+            ("cos(90)", "0"),
+            ("cos(270)", "0"),
+            ("cos(180)", "-1"),
+            ("cos(720)", "1"),
+            ("cos(-90)", "0"),
+            ("tan(180)", "0"),
+            ("tan(-360)", "0"),
+            ("sin(30)", "0.5"),
+            ("cos(60)", "0.5"),
+            ("sin(390)", "0.5"),
+        ] {
+            assert_eq!(show(input).as_deref(), Ok(expected), "{}", input);
+        }
+        assert!(matches!(eval_str("tan(90)", &ctx), Err(CalcError::Domain(_))));
+        assert!(matches!(eval_str("tan(-270)", &ctx), Err(CalcError::Domain(_))));
+    }
+
+    fn run_all(lines: &[&str]) -> (Environment, Result<Outcome, CalcError>) {
+        let ctx = Context::default();
+        let mut env = Environment::default();
+        let mut last = Err(CalcError::Syntax("nothing run".to_string()));
+        for line in lines {
+            last = run(line, &mut env, &ctx);
+        }
+        (env, last)
+    }
+
+    fn value(lines: &[&str]) -> Result<String, CalcError> {
+        run_all(lines).1.map(|outcome| outcome.value().map(Decimal::to_string).unwrap_or_default())
+    }
+
+    #[test]
+    fn let_binds_variables_and_results_set_ans() {
+        let (env, outcome) = run_all(&["let rate = 0.07", "100 * rate"]);
+        assert_eq!(outcome.unwrap().value().map(Decimal::to_string).as_deref(), Some("7"));
+        assert_eq!(env.variables["rate"].to_string(), "0.07");
+        assert_eq!(env.ans.as_ref().map(Decimal::to_string).as_deref(), Some("7"));
+
+        assert_eq!(value(&["let a = 2", "let a = a + 1", "a"]).as_deref(), Ok("3"));
+        assert_eq!(value(&["6 * 7", "ans + 1", "ans * 2"]).as_deref(), Ok("86"));
+        // An assignment is a result too; a definition isn't.
+        assert_eq!(value(&["let a = 5", "f(x) = x", "ans"]).as_deref(), Ok("5"));
+        assert!(matches!(value(&["ans"]), Err(CalcError::Syntax(_))));
+        assert!(matches!(value(&["unknown + 1"]), Err(CalcError::Syntax(_))));
+    }
+
+    #[test]
+    fn user_functions() {
+        assert_eq!(value(&["f(x) = x^2 + 1", "f(3)"]).as_deref(), Ok("10"));
+        assert_eq!(value(&["area(w, h) = w * h", "area(3, 4) + area(1, 2)"]).as_deref(), Ok("14"));
+        // Parameters shadow variables; other names come from the environment.
+        assert_eq!(value(&["let x = 100", "let k = 2", "f(x) = x * k", "f(5)"]).as_deref(), Ok("10"));
+        assert_eq!(value(&["f(x) = x + 1", "g(x) = f(x) * 2", "g(1)"]).as_deref(), Ok("4"));
+        // A variable before parentheses multiplies.
+        assert_eq!(value(&["let k = 3", "k(2 + 1)"]).as_deref(), Ok("9"));
+        assert!(matches!(value(&["f(x, y) = x", "f(1)"]), Err(CalcError::Syntax(_))));
+        assert!(matches!(value(&["f(x, x) = x"]), Err(CalcError::Syntax(_))));
+        assert!(matches!(value(&["g(1, 2)"]), Err(CalcError::Syntax(_))));
+    }
+
+    #[test]
+    fn recursion_stops_at_the_depth_limit() {
+        assert!(matches!(value(&["f(x) = f(x) + 1", "f(1)"]), Err(CalcError::Domain(_))));
+        assert!(matches!(value(&["f(x) = g(x)", "g(x) = f(x)", "f(1)"]), Err(CalcError::Domain(_))));
+        // Nesting just under the limit is fine.
+        let mut lines = vec!["f0(x) = x + 1".to_string()];
+        for depth in 1..MAX_CALL_DEPTH {
+            lines.push(format!("f{}(x) = f{}(x) + 1", depth, depth - 1));
+        }
+        lines.push(format!("f{}(0)", MAX_CALL_DEPTH - 1));
+        let lines: Vec<&str> = lines.iter().map(String::as_str).collect();
+        assert_eq!(value(&lines), Ok(MAX_CALL_DEPTH.to_string()));
+    }
+
+    #[test]
+    fn built_in_names_cannot_be_redefined() {
+        for line in [
+            "let pi = 3",
+            "let e = 2",
+            "let sin = 1",
+            "let ans = 1",
+            "let let = 1",
+            "sqrt(x) = x",
+            "log(x) = x",
+            "f(pi) = pi",
+            "f(cos) = 1",
+        ] {
+            assert!(matches!(value(&[line]), Err(CalcError::Syntax(_))), "{}", line);
+        }
+        assert!(matches!(value(&["x = 1"]), Err(CalcError::Syntax(_))));
+    }
+
+    #[test]
+    fn tiny_values_underflow() {
+        let ctx = Context::default();
+        assert_eq!(eval_str("1e-999999999 + 1", &ctx), Err(CalcError::Underflow));
+        assert!(matches!(eval_str("1e-9223372036854775808", &ctx), Err(CalcError::Syntax(_))));
+        assert_eq!(eval_str("0.5^100000", &ctx), Err(CalcError::Underflow));
+        assert_eq!(eval_str("2^-100000", &ctx), Err(CalcError::Underflow));
+        assert_eq!(eval_str("0.5^-100000", &ctx), Err(CalcError::Overflow));
+        assert_eq!(eval_str("1e-1999 * 10", &ctx).unwrap().to_string(), "1e-1998");
+    }
+
+    #[test]
+    fn reference_follows_the_documented_grammar() {
+        assert_eq!(Reference::eval("2+3*4"), Some(14));
+        assert_eq!(Reference::eval("10-4-3"), Some(3));
+        assert_eq!(Reference::eval("2^3^2"), Some(512));
+        assert_eq!(Reference::eval("-2^2"), Some(-4));
+        assert_eq!(Reference::eval("2*-3"), Some(-6));
+        assert_eq!(Reference::eval("2^-1"), None);
+    }
+
+    #[test]
+    fn generated_expressions_match_the_reference() {
+        let ctx = Context::default();
+        let mut generator = Generator(0x2545_f491_4f6c_dd1d);
+        let mut checked = 0;
+        for _ in 0..2_000 {
+            let mut input = String::new();
+            generator.sum(&mut input, 3);
+            let Some(expected) = Reference::eval(&input) else {
+                continue;
+            };
+            let expected = Decimal::from_i64(i64::try_from(expected).unwrap());
+            assert_eq!(eval_str(&input, &ctx), Ok(expected), "evaluating {}", input);
+            checked += 1;
+        }
+        assert!(checked > 1_000, "only {} expressions were checked", checked);
+    }
+}
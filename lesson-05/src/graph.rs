@@ -0,0 +1,620 @@
+use leptos::ev;
+use leptos::prelude::*;
+use wasm_bindgen::JsCast;
+
+use crate::engine::{parse, AngleMode, Context, Environment, Expr, FloatFn};
+
+const WIDTH: f64 = 340.0;
+const HEIGHT: f64 = 260.0;
+const INITIAL_SAMPLES: usize = 128;
+const MAX_DEPTH: u32 = 8;
+const MAX_POINTS: usize = 6_000;
+const SCAN_STEPS: usize = 600;
+const COLORS: [&str; 6] = ["#00dbde", "#fc00ff", "#ffb86b", "#50fa7b", "#ff6b6b", "#bd93f9"];
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Viewport {
+    pub x_min: f64,
+    pub x_max: f64,
+    pub y_min: f64,
+    pub y_max: f64,
+}
+
+impl Default for Viewport {
+    fn default() -> Self {
+        let half_height = 10.0 * HEIGHT / WIDTH;
+        Self {
+            x_min: -10.0,
+            x_max: 10.0,
+            y_min: -half_height,
+            y_max: half_height,
+        }
+    }
+}
+
+impl Viewport {
+    fn to_screen(self, x: f64, y: f64) -> (f64, f64) {
+        (
+            (x - self.x_min) / (self.x_max - self.x_min) * WIDTH,
+            (self.y_max - y) / (self.y_max - self.y_min) * HEIGHT,
+        )
+    }
+
+    fn to_world(self, sx: f64, sy: f64) -> (f64, f64) {
+        (
+            self.x_min + sx / WIDTH * (self.x_max - self.x_min),
+            self.y_max - sy / HEIGHT * (self.y_max - self.y_min),
+        )
+    }
+
+    /// Moves the view by a drag of `dx`, `dy` in screen units.
+    pub fn pan(self, dx: f64, dy: f64) -> Self {
+        let shift_x = dx / WIDTH * (self.x_max - self.x_min);
+        let shift_y = dy / HEIGHT * (self.y_max - self.y_min);
+        Self {
+            x_min: self.x_min - shift_x,
+            x_max: self.x_max - shift_x,
+            y_min: self.y_min + shift_y,
+            y_max: self.y_max + shift_y,
+        }
+    }
+
+    /// Scales the view by `factor` while keeping the point under the
+    /// screen position `(sx, sy)` fixed.
+    pub fn zoom(self, factor: f64, sx: f64, sy: f64) -> Self {
+        let (cx, cy) = self.to_world(sx, sy);
+        let span_x = (self.x_max - self.x_min) * factor;
+        let span_y = (self.y_max - self.y_min) * factor;
+        if !(1e-9..=1e9).contains(&span_x) {
+            return self;
+        }
+        let fx = sx / WIDTH;
+        let fy = sy / HEIGHT;
+        Self {
+            x_min: cx - fx * span_x,
+            x_max: cx + (1.0 - fx) * span_x,
+            y_min: cy - (1.0 - fy) * span_y,
+            y_max: cy + fy * span_y,
+        }
+    }
+
+    fn pixel_height(self) -> f64 {
+        (self.y_max - self.y_min) / HEIGHT
+    }
+}
+
+struct Sampler<'a> {
+    f: &'a dyn Fn(f64) -> f64,
+    view: Viewport,
+    segments: Vec<Vec<(f64, f64)>>,
+    points: usize,
+}
+
+impl Sampler<'_> {
+    fn push(&mut self, x: f64, y: f64) {
+        if !y.is_finite() {
+            self.break_segment();
+            return;
+        }
+        match self.segments.last_mut() {
+            Some(segment) => segment.push((x, y)),
+            None => self.segments.push(vec![(x, y)]),
+        }
+        self.points += 1;
+    }
+
+    fn break_segment(&mut self) {
+        if self.segments.last().is_none_or(|segment| !segment.is_empty()) {
+            self.segments.push(Vec::new());
+        }
+    }
+
+    /// Splits `[a, b]` until the curve is straight to within a pixel.
+    /// A jump taller than the view that survives the deepest split is taken
+    /// as an asymptote and the line is broken there.
+    fn refine(&mut self, (a, fa): (f64, f64), (b, fb): (f64, f64), depth: u32) {
+        let m = (a + b) / 2.0;
+        let fm = (self.f)(m);
+        let pixel = self.view.pixel_height();
+        let needs_split = if fa.is_finite() && fb.is_finite() && fm.is_finite() {
+            ((fa + fb) / 2.0 - fm).abs() > pixel / 2.0
+        } else {
+            fa.is_finite() || fb.is_finite() || fm.is_finite()
+        };
+
+        if needs_split && depth < MAX_DEPTH && self.points < MAX_POINTS {
+            self.refine((a, fa), (m, fm), depth + 1);
+            self.refine((m, fm), (b, fb), depth + 1);
+            return;
+        }
+
+        let span = self.view.y_max - self.view.y_min;
+        if fa.is_finite() && fb.is_finite() && (fb - fa).abs() > span && (fm - fa) * (fb - fm) < 0.0 {
+            self.break_segment();
+        }
+        self.push(b, fb);
+    }
+}
+
+/// Samples `f` across the view, more densely where it curves, as a list
+/// of unbroken polyline segments in world coordinates.
+pub fn sample(f: &dyn Fn(f64) -> f64, view: Viewport) -> Vec<Vec<(f64, f64)>> {
+    let mut sampler = Sampler {
+        f,
+        view,
+        segments: Vec::new(),
+        points: 0,
+    };
+    let step = (view.x_max - view.x_min) / INITIAL_SAMPLES as f64;
+    let mut previous = (view.x_min, f(view.x_min));
+    sampler.push(previous.0, previous.1);
+    for i in 1..=INITIAL_SAMPLES {
+        let x = view.x_min + step * i as f64;
+        let next = (x, f(x));
+        sampler.refine(previous, next, 0);
+        previous = next;
+    }
+    sampler.segments.retain(|segment| segment.len() > 1);
+    sampler.segments
+}
+
+/// Finds where `f` crosses zero in `[x_min, x_max]` by scanning for sign
+/// changes and bisecting. Crossings where `f` doesn't approach zero, such
+/// as the pole of `1/x`, are discarded.
+pub fn find_zeros(f: &dyn Fn(f64) -> f64, x_min: f64, x_max: f64) -> Vec<f64> {
+    let step = (x_max - x_min) / SCAN_STEPS as f64;
+    let tolerance = step * 1e-3;
+    let mut zeros: Vec<f64> = Vec::new();
+    let mut push = |x: f64| {
+        if zeros.last().is_none_or(|last| (x - last).abs() > tolerance) {
+            zeros.push(x);
+        }
+    };
+
+    let mut a = x_min;
+    let mut fa = f(a);
+    for i in 1..=SCAN_STEPS {
+        let b = x_min + step * i as f64;
+        let fb = f(b);
+        if fa == 0.0 {
+            push(a);
+        } else if fa.is_finite() && fb.is_finite() && fa.signum() != fb.signum() && fb != 0.0 {
+            let (mut lo, mut hi, mut flo) = (a, b, fa);
+            for _ in 0..60 {
+                let mid = (lo + hi) / 2.0;
+                let fmid = f(mid);
+                if !fmid.is_finite() {
+                    break;
+                }
+                if fmid.signum() == flo.signum() {
+                    lo = mid;
+                    flo = fmid;
+                } else {
+                    hi = mid;
+                }
+            }
+            let root = (lo + hi) / 2.0;
+            let scale = fa.abs().min(fb.abs()).max(1.0);
+            if f(root).abs() < 1e-6 * scale {
+                push(root);
+            }
+        }
+        a = b;
+        fa = fb;
+    }
+    zeros
+}
+
+fn path_data(segments: &[Vec<(f64, f64)>], view: Viewport) -> String {
+    let mut data = String::new();
+    for segment in segments {
+        for (i, &(x, y)) in segment.iter().enumerate() {
+            let (sx, sy) = view.to_screen(x, y);
+            // Keep far off-screen points from producing enormous coordinates.
+            let sy = sy.clamp(-10.0 * HEIGHT, 11.0 * HEIGHT);
+            data.push_str(&format!("{}{},{} ", if i == 0 { "M" } else { "L" }, coord(sx), coord(sy)));
+        }
+    }
+    data
+}
+
+/// A spacing of 1, 2 or 5 times a power of ten giving about eight lines.
+fn grid_step(span: f64) -> f64 {
+    let raw = span / 8.0;
+    let magnitude = 10_f64.powf(raw.log10().floor());
+    [1.0, 2.0, 5.0, 10.0]
+        .into_iter()
+        .map(|m| m * magnitude)
+        .find(|step| *step >= raw)
+        .unwrap_or(magnitude * 10.0)
+}
+
+fn grid_lines(min: f64, max: f64) -> Vec<f64> {
+    let step = grid_step(max - min);
+    let first = (min / step).ceil() as i64;
+    let last = (max / step).floor() as i64;
+    (first..=last).map(|i| i as f64 * step).collect()
+}
+
+fn coord(value: f64) -> String {
+    format!("{:.2}", value)
+}
+
+fn format_coordinate(value: f64) -> String {
+    let rounded = (value * 1e6).round() / 1e6;
+    if rounded == 0.0 {
+        "0".to_string()
+    } else {
+        format!("{}", rounded)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+struct Plot {
+    id: usize,
+    source: String,
+    color: &'static str,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+struct Marker {
+    x: f64,
+    y: f64,
+    color: &'static str,
+    label: String,
+}
+
+/// Plots functions of `x`, which may use the calculator's variables and
+/// user functions. Angles are in radians here regardless of the
+/// calculator's DEG/RAD setting.
+#[component]
+pub fn Grapher(#[prop(into)] environment: Signal<Environment>) -> impl IntoView {
+    let ctx = Context {
+        angle_mode: AngleMode::Radians,
+        ..Context::default()
+    };
+    let (plots, set_plots) = signal(vec![
+        Plot {
+            id: 0,
+            source: "sin(x)".to_string(),
+            color: COLORS[0],
+        },
+        Plot {
+            id: 1,
+            source: "x^2/4 - 1".to_string(),
+            color: COLORS[1],
+        },
+    ]);
+    let (view, set_view) = signal(Viewport::default());
+    let (drag, set_drag) = signal(None::<(f64, f64, Viewport)>);
+    let (show_markers, set_show_markers) = signal(true);
+
+    let parsed = Memo::new(move |_| {
+        plots
+            .get()
+            .into_iter()
+            .map(|plot| {
+                let expr = parse(&plot.source).map_err(|err| err.to_string());
+                (plot, expr)
+            })
+            .collect::<Vec<_>>()
+    });
+
+    let valid = move || {
+        parsed
+            .get()
+            .into_iter()
+            .filter_map(|(plot, expr)| expr.ok().map(|expr| (plot, expr)))
+            .collect::<Vec<(Plot, Expr)>>()
+    };
+
+    let curves = Memo::new(move |_| {
+        let view = view.get();
+        let env = environment.get();
+        valid()
+            .into_iter()
+            .map(|(plot, expr)| {
+                let function = FloatFn::new(&expr, &env, &ctx);
+                let f = |x: f64| function.eval(x);
+                (plot.id, plot.color, path_data(&sample(&f, view), view))
+            })
+            .collect::<Vec<_>>()
+    });
+
+    let markers = Memo::new(move |_| {
+        let view = view.get();
+        let plots = valid();
+        let env = environment.get();
+        let functions: Vec<FloatFn> = plots.iter().map(|(_, expr)| FloatFn::new(expr, &env, &ctx)).collect();
+        let mut markers = Vec::new();
+        for ((plot, _), function) in plots.iter().zip(&functions) {
+            let f = |x: f64| function.eval(x);
+            for x in find_zeros(&f, view.x_min, view.x_max) {
+                markers.push(Marker {
+                    x,
+                    y: 0.0,
+                    color: plot.color,
+                    label: format!("Root of {}: x = {}", plot.source, format_coordinate(x)),
+                });
+            }
+        }
+        for (i, ((first, _), first_fn)) in plots.iter().zip(&functions).enumerate() {
+            for ((second, _), second_fn) in plots.iter().zip(&functions).skip(i + 1) {
+                let difference = |x: f64| first_fn.eval(x) - second_fn.eval(x);
+                for x in find_zeros(&difference, view.x_min, view.x_max) {
+                    let y = first_fn.eval(x);
+                    markers.push(Marker {
+                        x,
+                        y,
+                        color: "white",
+                        label: format!(
+                            "{} = {} at ({}, {})",
+                            first.source,
+                            second.source,
+                            format_coordinate(x),
+                            format_coordinate(y)
+                        ),
+                    });
+                }
+            }
+        }
+        markers
+    });
+
+    let add_plot = move |_| {
+        set_plots.update(|plots| {
+            let id = plots.iter().map(|p| p.id + 1).max().unwrap_or(0);
+            plots.push(Plot {
+                id,
+                source: String::new(),
+                color: COLORS[id % COLORS.len()],
+            });
+        });
+    };
+
+    // Converts a pointer position to viewBox units, since the SVG is
+    // scaled to fit its container.
+    let local_point = move |ev: &web_sys::MouseEvent| {
+        let target = event_target::<web_sys::Element>(ev);
+        let svg = target.closest("svg").ok().flatten().unwrap_or(target);
+        let rect = svg.get_bounding_client_rect();
+        (
+            (ev.client_x() as f64 - rect.left()) / rect.width() * WIDTH,
+            (ev.client_y() as f64 - rect.top()) / rect.height() * HEIGHT,
+        )
+    };
+
+    let on_pointer_down = move |ev: ev::PointerEvent| {
+        let (x, y) = local_point(&ev);
+        set_drag.set(Some((x, y, view.get_untracked())));
+        if let Some(element) = ev.current_target().and_then(|target| target.dyn_into::<web_sys::Element>().ok()) {
+            let _ = element.set_pointer_capture(ev.pointer_id());
+        }
+    };
+
+    let on_pointer_move = move |ev: ev::PointerEvent| {
+        if let Some((start_x, start_y, start_view)) = drag.get_untracked() {
+            let (x, y) = local_point(&ev);
+            set_view.set(start_view.pan(x - start_x, y - start_y));
+        }
+    };
+
+    let on_wheel = move |ev: ev::WheelEvent| {
+        ev.prevent_default();
+        let (x, y) = local_point(&ev);
+        let factor = if ev.delta_y() > 0.0 { 1.15 } else { 1.0 / 1.15 };
+        set_view.update(|view| *view = view.zoom(factor, x, y));
+    };
+
+    view! {
+        <div class="grapher">
+            <svg
+                class="graph"
+                viewBox=format!("0 0 {} {}", WIDTH, HEIGHT)
+                on:pointerdown=on_pointer_down
+                on:pointermove=on_pointer_move
+                on:pointerup=move |_| set_drag.set(None)
+                on:pointercancel=move |_| set_drag.set(None)
+                on:wheel=on_wheel
+            >
+                {move || {
+                    let view = view.get();
+                    let vertical = grid_lines(view.x_min, view.x_max).into_iter().map(|x| {
+                        let (sx, _) = view.to_screen(x, 0.0);
+                        let axis = x == 0.0;
+                        view! {
+                            <line class="grid-line" class:axis=axis x1=coord(sx) y1="0" x2=coord(sx) y2=coord(HEIGHT) />
+                            <text class="grid-label" x=coord(sx + 2.0) y=coord(HEIGHT - 3.0)>{format_coordinate(x)}</text>
+                        }
+                    }).collect_view();
+                    let horizontal = grid_lines(view.y_min, view.y_max).into_iter().map(|y| {
+                        let (_, sy) = view.to_screen(0.0, y);
+                        let axis = y == 0.0;
+                        view! {
+                            <line class="grid-line" class:axis=axis x1="0" y1=coord(sy) x2=coord(WIDTH) y2=coord(sy) />
+                            <text class="grid-label" x="2" y=coord(sy - 2.0)>{format_coordinate(y)}</text>
+                        }
+                    }).collect_view();
+                    (vertical, horizontal)
+                }}
+                {move || curves.get().into_iter().map(|(_, color, path)| view! {
+                    <path class="curve" d=path stroke=color />
+                }).collect_view()}
+                <Show when=move || show_markers.get()>
+                    {move || {
+                        let view = view.get();
+                        markers.get().into_iter().map(|marker| {
+                            let (sx, sy) = view.to_screen(marker.x, marker.y);
+                            view! {
+                                <circle class="marker" cx=coord(sx) cy=coord(sy) r="4" stroke=marker.color>
+                                    <title>{marker.label}</title>
+                                </circle>
+                            }
+                        }).collect_view()
+                    }}
+                </Show>
+            </svg>
+
+            <div class="graph-controls">
+                <button class="history-action" on:click=move |_| set_view.update(|v| *v = v.zoom(1.0 / 1.5, WIDTH / 2.0, HEIGHT / 2.0))>"+"</button>
+                <button class="history-action" on:click=move |_| set_view.update(|v| *v = v.zoom(1.5, WIDTH / 2.0, HEIGHT / 2.0))>"−"</button>
+                <button class="history-action" on:click=move |_| set_view.set(Viewport::default())>"Reset"</button>
+                <label class="settings-check">
+                    <input
+                        type="checkbox"
+                        prop:checked=show_markers
+                        on:change=move |ev| set_show_markers.set(event_target_checked(&ev))
+                    />
+                    "Roots & intersections"
+                </label>
+            </div>
+
+            <ul class="plot-list">
+                <For
+                    each=move || plots.get()
+                    key=|plot| plot.id
+                    children=move |plot| {
+                        let id = plot.id;
+                        let error = move || {
+                            parsed
+                                .get()
+                                .into_iter()
+                                .find(|(p, _)| p.id == id)
+                                .and_then(|(p, expr)| expr.err().filter(|_| !p.source.trim().is_empty()))
+                        };
+                        view! {
+                            <li class="plot-entry">
+                                <span class="plot-swatch" style=format!("background: {}", plot.color)></span>
+                                <span class="plot-label">"y ="</span>
+                                <input
+                                    type="text"
+                                    placeholder="e.g. 1/x"
+                                    prop:value=plot.source.clone()
+                                    on:change=move |ev| {
+                                        let source = event_target_value(&ev);
+                                        set_plots.update(|plots| {
+                                            if let Some(p) = plots.iter_mut().find(|p| p.id == id) {
+                                                p.source = source;
+                                            }
+                                        });
+                                    }
+                                />
+                                <button
+                                    class="plot-remove"
+                                    title="Remove"
+                                    on:click=move |_| set_plots.update(|plots| plots.retain(|p| p.id != id))
+                                >
+                                    "×"
+                                </button>
+                                <span class="plot-error">{error}</span>
+                            </li>
+                        }
+                    }
+                />
+            </ul>
+            <button class="history-action" on:click=add_plot>"Add function"</button>
+
+            <Show when=move || show_markers.get() && !markers.get().is_empty()>
+                <ul class="marker-list">
+                    {move || markers.get().into_iter().map(|marker| view! {
+                        <li style=format!("color: {}", marker.color)>{marker.label}</li>
+                    }).collect_view()}
+                </ul>
+            </Show>
+        </div>
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn plot(source: &str, angle_mode: AngleMode) -> FloatFn {
+        let ctx = Context { angle_mode, ..Context::default() };
+        FloatFn::new(&parse(source).unwrap(), &Environment::default(), &ctx)
+    }
+
+    fn assert_near(actual: &[f64], expected: &[f64]) {
+        assert_eq!(actual.len(), expected.len(), "{:?} vs {:?}", actual, expected);
+        for (a, e) in actual.iter().zip(expected) {
+            assert!((a - e).abs() < 1e-6, "{:?} vs {:?}", actual, expected);
+        }
+    }
+
+    #[test]
+    fn zeros_are_found_by_bisection() {
+        let f = plot("x^2 - 4", AngleMode::Radians);
+        assert_near(&find_zeros(&|x| f.eval(x), -10.0, 10.0), &[-2.0, 2.0]);
+        let f = plot("sin(x)", AngleMode::Radians);
+        let pi = std::f64::consts::PI;
+        assert_near(&find_zeros(&|x| f.eval(x), -4.0, 4.0), &[-pi, 0.0, pi]);
+    }
+
+    #[test]
+    fn poles_are_not_zeros() {
+        let f = plot("1/x", AngleMode::Radians);
+        assert!(find_zeros(&|x| f.eval(x), -5.0, 5.0).is_empty());
+        let f = plot("1/(x - 0.3)", AngleMode::Radians);
+        assert!(find_zeros(&|x| f.eval(x), -5.0, 5.0).is_empty());
+    }
+
+    #[test]
+    fn tan_meets_a_line_only_between_its_poles() {
+        let tan = plot("tan(x)", AngleMode::Degrees);
+        let one = plot("1", AngleMode::Degrees);
+        let difference = |x: f64| tan.eval(x) - one.eval(x);
+        assert_near(&find_zeros(&difference, 0.0, 360.0), &[45.0, 225.0]);
+        assert_near(&find_zeros(&|x| tan.eval(x), 1.0, 359.0), &[180.0]);
+    }
+
+    #[test]
+    fn sampling_breaks_at_poles_and_asymptotes() {
+        let view = Viewport::default();
+
+        let f = plot("x^2", AngleMode::Radians);
+        assert_eq!(sample(&|x| f.eval(x), view).len(), 1);
+
+        let f = plot("1/x", AngleMode::Radians);
+        let segments = sample(&|x| f.eval(x), view);
+        assert_eq!(segments.len(), 2);
+        for segment in segments.iter() {
+            let negative = segment[0].0 < 0.0;
+            assert!(segment.iter().all(|&(x, _)| (x < 0.0) == negative));
+        }
+
+        let f = plot("sqrt(x)", AngleMode::Radians);
+        let segments = sample(&|x| f.eval(x), view);
+        assert_eq!(segments.len(), 1);
+        assert!(segments[0].iter().all(|&(x, y)| x >= 0.0 && y >= 0.0));
+
+        let f = plot("tan(x)", AngleMode::Degrees);
+        let wide = Viewport { x_min: -180.0, x_max: 180.0, ..view };
+        let segments = sample(&|x| f.eval(x), wide);
+        assert_eq!(segments.len(), 3);
+        for segment in segments.iter() {
+            let (first, last) = (segment[0].0, segment[segment.len() - 1].0);
+            assert!(![-90.0, 90.0].iter().any(|pole| first < *pole && *pole < last), "{} to {}", first, last);
+        }
+    }
+
+    #[test]
+    fn grid_steps_are_one_two_or_five() {
+        for (span, step) in [(8.0, 1.0), (10.0, 2.0), (35.0, 5.0), (80.0, 10.0), (100.0, 20.0), (0.8, 0.1)] {
+            assert!((grid_step(span) - step).abs() < 1e-12, "{} -> {}", span, grid_step(span));
+        }
+        assert_eq!(grid_lines(-2.0, 2.0), vec![-2.0, -1.5, -1.0, -0.5, 0.0, 0.5, 1.0, 1.5, 2.0]);
+    }
+
+    #[test]
+    fn plots_use_the_environment() {
+        let mut env = Environment::default();
+        let ctx = Context::default();
+        for source in ["let k = 3", "f(t) = t * k"] {
+            crate::engine::run(source, &mut env, &ctx).unwrap();
+        }
+        let f = FloatFn::new(&parse("f(x) + k + 2%").unwrap(), &env, &ctx);
+        assert!((f.eval(2.0) - (6.0 + 3.0) * 1.02).abs() < 1e-12);
+        let unknown = FloatFn::new(&parse("y + 1").unwrap(), &env, &ctx);
+        assert!(unknown.eval(1.0).is_nan());
+    }
+}
@@ -0,0 +1,80 @@
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+use wasm_bindgen::{JsCast, JsValue};
+
+const STORAGE_KEY: &str = "lesson-05.history";
+const MAX_ENTRIES: usize = 200;
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct HistoryEntry {
+    pub id: u64,
+    pub expression: String,
+    /// The exact result, without separators, so it can be parsed back.
+    pub result: String,
+    pub timestamp: DateTime<Local>,
+}
+
+fn local_storage() -> Option<web_sys::Storage> {
+    leptos::prelude::window().local_storage().ok().flatten()
+}
+
+pub fn load() -> Vec<HistoryEntry> {
+    local_storage()
+        .and_then(|storage| storage.get_item(STORAGE_KEY).ok().flatten())
+        .and_then(|json| serde_json::from_str(&json).ok())
+        .unwrap_or_default()
+}
+
+pub fn save(entries: &[HistoryEntry]) {
+    if let (Some(storage), Ok(json)) = (local_storage(), serde_json::to_string(entries)) {
+        let _ = storage.set_item(STORAGE_KEY, &json);
+    }
+}
+
+/// Adds a calculation to the front of the tape, dropping the oldest entries
+/// once the tape is full.
+pub fn record(entries: &mut Vec<HistoryEntry>, expression: String, result: String) {
+    let id = entries.iter().map(|e| e.id).max().map_or(0, |id| id + 1);
+    entries.insert(
+        0,
+        HistoryEntry {
+            id,
+            expression,
+            result,
+            timestamp: Local::now(),
+        },
+    );
+    entries.truncate(MAX_ENTRIES);
+}
+
+/// Renders the tape oldest first, one calculation per line.
+pub fn to_text(entries: &[HistoryEntry]) -> String {
+    entries
+        .iter()
+        .rev()
+        .map(|entry| {
+            format!(
+                "{}  {} = {}\n",
+                entry.timestamp.format("%Y-%m-%d %H:%M:%S"),
+                entry.expression,
+                entry.result
+            )
+        })
+        .collect()
+}
+
+pub fn download(contents: &str) -> Result<(), JsValue> {
+    let parts = js_sys::Array::of1(&JsValue::from_str(contents));
+    let options = web_sys::BlobPropertyBag::new();
+    options.set_type("text/plain");
+    let blob = web_sys::Blob::new_with_str_sequence_and_options(&parts, &options)?;
+    let url = web_sys::Url::create_object_url_with_blob(&blob)?;
+
+    let anchor: web_sys::HtmlAnchorElement = leptos::prelude::document()
+        .create_element("a")?
+        .dyn_into()?;
+    anchor.set_href(&url);
+    anchor.set_download("calculator-history.txt");
+    anchor.click();
+    web_sys::Url::revoke_object_url(&url)
+}
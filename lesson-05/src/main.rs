@@ -1,100 +1,277 @@
+use std::time::Duration;
+
+use leptos::ev;
 use leptos::prelude::*;
 use leptos_meta::*;
+use wasm_bindgen::JsCast;
+
+mod decimal;
+mod engine;
+mod graph;
+mod history;
+mod programmer;
+mod state;
+mod units;
+mod variables;
+
+use decimal::{Decimal, FormatOptions, RoundingMode};
+use engine::{AngleMode, Context};
+use graph::Grapher;
+use history::HistoryEntry;
+use programmer::Programmer;
+use state::{CalcState, Input, Phase};
+use units::Converter;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Mode {
+    Standard,
+    Programmer,
+    Converter,
+    Graph,
+}
+
+const KEYPAD: [&str; 10] = ["0", "1", "2", "3", "4", "5", "6", "7", "8", "9"];
+const KEY_FLASH_MS: u64 = 120;
+
+/// Keys typed into a form field belong to that field, not the keypad.
+fn is_typing_target(ev: &web_sys::KeyboardEvent) -> bool {
+    ev.target()
+        .and_then(|target| target.dyn_into::<web_sys::HtmlElement>().ok())
+        .is_some_and(|element| {
+            matches!(element.tag_name().as_str(), "INPUT" | "SELECT" | "TEXTAREA") || element.is_content_editable()
+        })
+}
 
 #[component]
 fn Calculator() -> impl IntoView {
-    let (display, set_display) = create_signal("0".to_string());
-    let (first_number, set_first_number) = create_signal(None::<f64>);
-    let (operator, set_operator) = create_signal(None::<String>);
-    let (waiting_for_second, set_waiting_for_second) = create_signal(false);
-
-    let input_digit = move |digit: &str| {
-        if waiting_for_second.get() {
-            set_display.set(digit.to_string());
-            set_waiting_for_second.set(false);
-        } else {
-            if display.get() == "0" {
-                set_display.set(digit.to_string());
-            } else {
-                set_display.update(|d| d.push_str(digit));
-            }
+    let (calc, set_calc) = signal(CalcState::default());
+    let (scientific, set_scientific) = signal(false);
+    let (mode, set_mode) = signal(Mode::Standard);
+    let (angle_mode, set_angle_mode) = signal(AngleMode::Degrees);
+    let (memory, set_memory) = signal(Decimal::zero());
+    let (precision, set_precision) = signal(32_u32);
+    let (rounding, set_rounding) = signal(RoundingMode::HalfEven);
+    let (thousands_separator, set_thousands_separator) = signal(true);
+    let (show_settings, set_show_settings) = signal(false);
+    let (history, set_history) = signal(history::load());
+    let (show_history, set_show_history) = signal(false);
+    let (environment, set_environment) = signal(variables::load());
+    let (show_variables, set_show_variables) = signal(false);
+    let (command, set_command) = signal(String::new());
+    let (pressed, set_pressed) = signal(None::<&'static str>);
+
+    Effect::new(move |_| history::save(&history.get()));
+    Effect::new(move |_| variables::save(&environment.get()));
+
+    let context = move || Context {
+        angle_mode: angle_mode.get(),
+        precision: precision.get(),
+        rounding: rounding.get(),
+    };
+
+    let format_value = move |value: &Decimal| {
+        value.format(FormatOptions {
+            thousands_separator: thousands_separator.get(),
+            ..FormatOptions::default()
+        })
+    };
+
+    let preview = move || {
+        let state = calc.get();
+        if state.expression.is_empty() || !matches!(state.phase, Phase::Editing { .. }) {
+            return None;
         }
+        state.current_value(&environment.get(), &context()).map(|value| format_value(&value))
     };
 
-    let input_decimal = move |_| {
-        if waiting_for_second.get() {
-            set_display.set("0.".to_string());
-            set_waiting_for_second.set(false);
-        } else if !display.get().contains('.') {
-            set_display.update(|d| d.push('.'));
+    let display = move || {
+        let state = calc.get();
+        match &state.phase {
+            Phase::Evaluated(result) => format_value(result),
+            Phase::Defined(name) => format!("{}() defined", name),
+            Phase::Failed(err) => err.to_string(),
+            Phase::Editing { .. } => match state.current_number() {
+                "" => "0".to_string(),
+                number => number.to_string(),
+            },
         }
     };
 
-    let clear_display = move |_| {
-        set_display.set("0".to_string());
-        set_first_number.set(None);
-        set_operator.set(None);
-        set_waiting_for_second.set(false);
+    let expression_line = move || {
+        let state = calc.get();
+        match state.phase {
+            Phase::Editing { .. } | Phase::Defined(_) => state.expression,
+            _ => format!("{} =", state.expression),
+        }
     };
 
-    let perform_operation = move |next_operator: &str| {
-        let input_value = display.get().parse::<f64>().unwrap_or(0.0);
-        
-        if let Some(first) = first_number.get() {
-            if let Some(op) = operator.get() {
-                let result = match op.as_str() {
-                    "+" => first + input_value,
-                    "-" => first - input_value,
-                    "×" => first * input_value,
-                    "÷" => {
-                        if input_value == 0.0 {
-                            return;
-                        }
-                        first / input_value
-                    }
-                    _ => input_value,
-                };
-                
-                set_display.set(result.to_string());
-                set_first_number.set(Some(result));
-            }
-        } else {
-            set_first_number.set(Some(input_value));
+    let apply = move |input: Input| {
+        let ctx = context();
+        let mut env = environment.get_untracked();
+        let mut evaluation = None;
+        set_calc.update(|state| evaluation = state.apply(input, &ctx, &mut env));
+        if env != environment.get_untracked() {
+            set_environment.set(env);
+        }
+        if let Some(evaluation) = evaluation {
+            set_history.update(|entries| {
+                history::record(entries, evaluation.expression, evaluation.result.to_string())
+            });
         }
-        
-        set_waiting_for_second.set(true);
-        set_operator.set(Some(next_operator.to_string()));
     };
 
-    let calculate_result = move |_| {
-        let input_value = display.get().parse::<f64>().unwrap_or(0.0);
-        
-        if let Some(first) = first_number.get() {
-            if let Some(op) = operator.get() {
-                let result = match op.as_str() {
-                    "+" => first + input_value,
-                    "-" => first - input_value,
-                    "×" => first * input_value,
-                    "÷" => {
-                        if input_value == 0.0 {
-                            set_display.set("Error".to_string());
-                            set_first_number.set(None);
-                            set_operator.set(None);
-                            return;
-                        }
-                        first / input_value
-                    }
-                    _ => input_value,
-                };
-                
-                set_display.set(result.to_string());
-                set_first_number.set(None);
-                set_operator.set(None);
-                set_waiting_for_second.set(false);
-            }
+    let input_digit = move |digit: char| apply(Input::Digit(digit));
+    let input_decimal = move || apply(Input::Point);
+    let input_operator = move |op: char| apply(Input::Operator(op));
+    let input_paren = move |paren: char| apply(Input::Paren(paren));
+    let input_token = move |token: &str| apply(Input::Token(token.to_string()));
+    let input_percent = move || apply(Input::Percent);
+    let clear_display = move || apply(Input::Clear);
+    let clear_entry = move || apply(Input::ClearEntry);
+    let backspace = move || apply(Input::Backspace);
+    let calculate_result = move || apply(Input::Evaluate);
+
+    let memory_add = move |subtract: bool| {
+        if let Some(value) = calc.get().current_value(&environment.get(), &context()) {
+            set_memory.update(|m| {
+                *m = if subtract { &*m - &value } else { &*m + &value };
+            });
+        }
+    };
+
+    let memory_recall = move || apply(Input::Value(memory.get()));
+
+    let recall_result = move |entry: &HistoryEntry| {
+        if let Ok(value) = entry.result.parse::<Decimal>() {
+            apply(Input::Value(value));
+        }
+    };
+
+    let recall_expression = move |entry: &HistoryEntry| apply(Input::Load(entry.expression.clone()));
+
+    // Runs a typed line such as `let rate = 0.07` or `f(x) = x^2 + 1`,
+    // keeping the text when it has a problem so it can be fixed.
+    let run_command = move || {
+        let line = command.get();
+        if line.trim().is_empty() {
+            return;
         }
+        apply(Input::Clear);
+        apply(Input::Load(line));
+        apply(Input::Evaluate);
+        if matches!(calc.get().phase, Phase::Evaluated(_) | Phase::Defined(_)) {
+            set_command.set(String::new());
+        }
+    };
+
+    let remove_variable = move |name: String| {
+        set_environment.update(|env| {
+            env.variables.remove(&name);
+        });
     };
 
+    let remove_function = move |name: String| {
+        set_environment.update(|env| {
+            env.functions.remove(&name);
+        });
+    };
+
+    let export_history = move |_| {
+        let _ = history::download(&history::to_text(&history.get()));
+    };
+
+    let toggle_mode = move |next: Mode| {
+        set_mode.update(|mode| *mode = if *mode == next { Mode::Standard } else { next });
+    };
+
+    let flash_key = move |key: &'static str| {
+        set_pressed.set(Some(key));
+        set_timeout(
+            move || set_pressed.update(|p| {
+                if *p == Some(key) {
+                    *p = None;
+                }
+            }),
+            Duration::from_millis(KEY_FLASH_MS),
+        );
+    };
+
+    let handle = window_event_listener(ev::keydown, move |ev| {
+        if mode.get() != Mode::Standard || ev.ctrl_key() || ev.meta_key() || ev.alt_key() || is_typing_target(&ev) {
+            return;
+        }
+        let key = ev.key();
+        let pressed_key = match key.as_str() {
+            digit @ ("0" | "1" | "2" | "3" | "4" | "5" | "6" | "7" | "8" | "9") => {
+                let digit = KEYPAD.iter().copied().find(|k| *k == digit).unwrap_or("0");
+                input_digit(digit.chars().next().unwrap_or('0'));
+                digit
+            }
+            "." | "," => {
+                input_decimal();
+                "."
+            }
+            "+" => {
+                input_operator('+');
+                "+"
+            }
+            "-" => {
+                input_operator('−');
+                "−"
+            }
+            "*" | "x" | "X" => {
+                input_operator('×');
+                "×"
+            }
+            "/" => {
+                input_operator('÷');
+                "÷"
+            }
+            "^" => {
+                input_operator('^');
+                "^"
+            }
+            "%" => {
+                input_percent();
+                "%"
+            }
+            "(" => {
+                input_paren('(');
+                "("
+            }
+            ")" => {
+                input_paren(')');
+                ")"
+            }
+            "!" => {
+                input_token("!");
+                "!"
+            }
+            "Enter" | "=" => {
+                calculate_result();
+                "="
+            }
+            "Backspace" => {
+                backspace();
+                "⌫"
+            }
+            "Escape" => {
+                clear_display();
+                "C"
+            }
+            "Delete" => {
+                clear_entry();
+                "CE"
+            }
+            _ => return,
+        };
+        // Keeps Enter from also clicking a focused button and `/` from
+        // opening the browser's quick find.
+        ev.prevent_default();
+        flash_key(pressed_key);
+    });
+    on_cleanup(move || handle.remove());
+
     view! {
         <div class="calculator-container">
             <div class="header">
@@ -102,80 +279,451 @@ fn Calculator() -> impl IntoView {
                 <p>"A simple working calculator"</p>
             </div>
 
-            <div class="display">
-                <div class="result">{display}</div>
-            </div>
+            <Show when=move || mode.get() == Mode::Standard>
+                <div class="display">
+                    <div class="display-status">
+                        <span>{move || match angle_mode.get() {
+                            AngleMode::Degrees => "DEG",
+                            AngleMode::Radians => "RAD",
+                        }}</span>
+                        <span>{move || (!memory.get().is_zero()).then_some("M")}</span>
+                    </div>
+                    <div class="expression">{expression_line}</div>
+                    <div class="result" class:error=move || calc.get().is_blocked()>{display}</div>
+                    <div class="preview" class:notice=move || calc.get().notice().is_some()>
+                        {move || match calc.get().notice() {
+                            Some(notice) => Some(notice.to_string()),
+                            None => preview().map(|value| format!("= {}", value)),
+                        }}
+                    </div>
+                </div>
+            </Show>
 
-            <div class="calculator-body">
-                <button class="btn function" on:click=clear_display>
-                    "C"
-                </button>
-                <button class="btn function" on:click=move |_| set_display.set("0".to_string())>
-                    "CE"
-                </button>
-                <button class="btn function" on:click=move |_| {
-                    let current = display.get();
-                    if current.len() > 1 {
-                        set_display.set(current[..current.len()-1].to_string());
-                    } else {
-                        set_display.set("0".to_string());
-                    }
-                }>
-                    "⌫"
-                </button>
-                <button class="btn operator" on:click=move |_| perform_operation("÷")>
-                    "÷"
-                </button>
-                
-                <button class="btn number" on:click=move |_| input_digit("7")>
-                    "7"
-                </button>
-                <button class="btn number" on:click=move |_| input_digit("8")>
-                    "8"
-                </button>
-                <button class="btn number" on:click=move |_| input_digit("9")>
-                    "9"
-                </button>
-                <button class="btn operator" on:click=move |_| perform_operation("×")>
-                    "×"
+            <div class="mode-toggle">
+                <button
+                    class="mode-btn"
+                    class:active=scientific
+                    on:click=move |_| set_scientific.update(|s| *s = !*s)
+                >
+                    "Scientific"
                 </button>
-                
-                <button class="btn number" on:click=move |_| input_digit("4")>
-                    "4"
+                <button
+                    class="mode-btn"
+                    class:active=move || mode.get() == Mode::Programmer
+                    on:click=move |_| toggle_mode(Mode::Programmer)
+                >
+                    "Programmer"
                 </button>
-                <button class="btn number" on:click=move |_| input_digit("5")>
-                    "5"
+                <button
+                    class="mode-btn"
+                    class:active=move || mode.get() == Mode::Converter
+                    on:click=move |_| toggle_mode(Mode::Converter)
+                >
+                    "Convert"
                 </button>
-                <button class="btn number" on:click=move |_| input_digit("6")>
-                    "6"
+                <button
+                    class="mode-btn"
+                    class:active=move || mode.get() == Mode::Graph
+                    on:click=move |_| toggle_mode(Mode::Graph)
+                >
+                    "Graph"
                 </button>
-                <button class="btn operator" on:click=move |_| perform_operation("-")>
-                    "-"
+                <button
+                    class="mode-btn"
+                    class:active=show_settings
+                    on:click=move |_| set_show_settings.update(|s| *s = !*s)
+                >
+                    "Settings"
                 </button>
-                
-                <button class="btn number" on:click=move |_| input_digit("1")>
-                    "1"
+                <button
+                    class="mode-btn"
+                    class:active=show_history
+                    on:click=move |_| set_show_history.update(|s| *s = !*s)
+                >
+                    "History"
                 </button>
-                <button class="btn number" on:click=move |_| input_digit("2")>
-                    "2"
-                </button>
-                <button class="btn number" on:click=move |_| input_digit("3")>
-                    "3"
-                </button>
-                <button class="btn operator" on:click=move |_| perform_operation("+")>
-                    "+"
-                </button>
-                
-                <button class="btn number zero" on:click=move |_| input_digit("0")>
-                    "0"
-                </button>
-                <button class="btn number" on:click=input_decimal>
-                    "."
-                </button>
-                <button class="btn equals" on:click=calculate_result>
-                    "="
+                <button
+                    class="mode-btn"
+                    class:active=show_variables
+                    on:click=move |_| set_show_variables.update(|s| *s = !*s)
+                >
+                    "Variables"
                 </button>
             </div>
+
+            <Show when=move || show_variables.get()>
+                <div class="variables-panel">
+                    <input
+                        class="command-input"
+                        type="text"
+                        placeholder="let rate = 0.07   or   f(x) = x^2 + 1"
+                        prop:value=command
+                        on:input=move |ev| set_command.set(event_target_value(&ev))
+                        on:keydown=move |ev: leptos::ev::KeyboardEvent| {
+                            if ev.key() == "Enter" {
+                                run_command();
+                            }
+                        }
+                    />
+                    <ul class="variable-list">
+                        {move || environment.get().ans.map(|ans| view! {
+                            <li class="variable-entry">
+                                <button class="variable-name" on:click=move |_| input_token("ans")>"ans"</button>
+                                <span class="variable-value">{format_value(&ans)}</span>
+                            </li>
+                        })}
+                        {move || environment.get().variables.into_iter().map(|(name, value)| {
+                            let token = name.clone();
+                            let removed = name.clone();
+                            view! {
+                                <li class="variable-entry">
+                                    <button class="variable-name" on:click=move |_| input_token(&token)>{name}</button>
+                                    <span class="variable-value">{format_value(&value)}</span>
+                                    <button class="plot-remove" title="Delete" on:click=move |_| remove_variable(removed.clone())>"×"</button>
+                                </li>
+                            }
+                        }).collect_view()}
+                        {move || environment.get().functions.into_iter().map(|(name, function)| {
+                            let token = format!("{}(", name);
+                            view! {
+                                <li class="variable-entry">
+                                    <button class="variable-name" on:click=move |_| input_token(&token)>{function.source}</button>
+                                    <button class="plot-remove" title="Delete" on:click=move |_| remove_function(name.clone())>"×"</button>
+                                </li>
+                            }
+                        }).collect_view()}
+                    </ul>
+                </div>
+            </Show>
+
+            <Show when=move || show_history.get()>
+                <div class="history-panel">
+                    <div class="history-actions">
+                        <button
+                            class="history-action"
+                            disabled=move || history.get().is_empty()
+                            on:click=export_history
+                        >
+                            "Export"
+                        </button>
+                        <button
+                            class="history-action"
+                            disabled=move || history.get().is_empty()
+                            on:click=move |_| set_history.set(Vec::new())
+                        >
+                            "Clear"
+                        </button>
+                    </div>
+                    <Show
+                        when=move || !history.get().is_empty()
+                        fallback=|| view! { <p class="history-empty">"No calculations yet"</p> }
+                    >
+                        <ul class="history-list">
+                            <For
+                                each=move || history.get()
+                                key=|entry| entry.id
+                                children=move |entry| {
+                                    let expression_entry = entry.clone();
+                                    let result_entry = entry.clone();
+                                    let result = entry
+                                        .result
+                                        .parse::<Decimal>()
+                                        .map(|value| format_value(&value))
+                                        .unwrap_or_else(|_| entry.result.clone());
+                                    view! {
+                                        <li class="history-entry">
+                                            <button
+                                                class="history-expression"
+                                                title="Load expression"
+                                                on:click=move |_| recall_expression(&expression_entry)
+                                            >
+                                                {entry.expression.clone()}
+                                            </button>
+                                            <button
+                                                class="history-result"
+                                                title="Insert result"
+                                                on:click=move |_| recall_result(&result_entry)
+                                            >
+                                                {format!("= {}", result)}
+                                            </button>
+                                        </li>
+                                    }
+                                }
+                            />
+                        </ul>
+                    </Show>
+                </div>
+            </Show>
+
+            <Show when=move || show_settings.get()>
+                <div class="settings-panel">
+                    <label>
+                        "Precision"
+                        <select on:change=move |ev| {
+                            if let Ok(value) = event_target_value(&ev).parse::<u32>() {
+                                set_precision.set(value);
+                            }
+                        }>
+                            {[16_u32, 24, 32, 50, 100].into_iter().map(|digits| view! {
+                                <option value=digits.to_string() selected=move || precision.get() == digits>
+                                    {format!("{} digits", digits)}
+                                </option>
+                            }).collect_view()}
+                        </select>
+                    </label>
+                    <label>
+                        "Rounding"
+                        <select on:change=move |ev| {
+                            let label = event_target_value(&ev);
+                            if let Some(mode) = RoundingMode::ALL.into_iter().find(|m| m.label() == label) {
+                                set_rounding.set(mode);
+                            }
+                        }>
+                            {RoundingMode::ALL.into_iter().map(|mode| view! {
+                                <option value=mode.label() selected=move || rounding.get() == mode>
+                                    {mode.label()}
+                                </option>
+                            }).collect_view()}
+                        </select>
+                    </label>
+                    <label class="settings-check">
+                        <input
+                            type="checkbox"
+                            prop:checked=thousands_separator
+                            on:change=move |ev| set_thousands_separator.set(event_target_checked(&ev))
+                        />
+                        "Thousands separators"
+                    </label>
+                </div>
+            </Show>
+
+            <Show
+                when=move || mode.get() == Mode::Standard
+                fallback=move || move || match mode.get() {
+                    Mode::Converter => view! {
+                        <Converter context=Signal::derive(context) thousands_separator=thousands_separator/>
+                    }
+                    .into_any(),
+                    Mode::Graph => view! { <Grapher environment=environment/> }.into_any(),
+                    _ => view! { <Programmer/> }.into_any(),
+                }
+            >
+                <Show when=move || scientific.get()>
+                    <div class="calculator-body scientific-body" class:blocked=move || calc.get().is_blocked()>
+                        <button class="btn science" on:click=move |_| input_token("sin(")>"sin"</button>
+                        <button class="btn science" on:click=move |_| input_token("cos(")>"cos"</button>
+                        <button class="btn science" on:click=move |_| input_token("tan(")>"tan"</button>
+                        <button
+                            class="btn function"
+                            on:click=move |_| set_angle_mode.update(|mode| {
+                                *mode = match mode {
+                                    AngleMode::Degrees => AngleMode::Radians,
+                                    AngleMode::Radians => AngleMode::Degrees,
+                                }
+                            })
+                        >
+                            {move || match angle_mode.get() {
+                                AngleMode::Degrees => "DEG",
+                                AngleMode::Radians => "RAD",
+                            }}
+                        </button>
+
+                        <button class="btn science" on:click=move |_| input_token("asin(")>"sin⁻¹"</button>
+                        <button class="btn science" on:click=move |_| input_token("acos(")>"cos⁻¹"</button>
+                        <button class="btn science" on:click=move |_| input_token("atan(")>"tan⁻¹"</button>
+                        <button class="btn science" on:click=move |_| input_token("π")>"π"</button>
+
+                        <button class="btn science" on:click=move |_| input_token("ln(")>"ln"</button>
+                        <button class="btn science" on:click=move |_| input_token("log(")>"log"</button>
+                        <button class="btn science" on:click=move |_| input_token("exp(")>"eˣ"</button>
+                        <button class="btn science" on:click=move |_| input_token("e")>"e"</button>
+
+                        <button class="btn science" on:click=move |_| input_token("^2")>"x²"</button>
+                        <button class="btn science" on:click=move |_| input_token("√(")>"√"</button>
+                        <button class="btn science" on:click=move |_| input_token("∛(")>"∛"</button>
+                        <button class="btn science" on:click=move |_| input_token("root(")>"ʸ√x"</button>
+
+                        <button class="btn science" on:click=move |_| input_token("!")>"x!"</button>
+                        <button class="btn science" on:click=move |_| input_token(",")>","</button>
+                        <button class="btn science" on:click=move |_| input_token("abs(")>"|x|"</button>
+                        <button class="btn science" on:click=move |_| input_token("10^")>"10ˣ"</button>
+
+                        <button class="btn memory" on:click=move |_| set_memory.set(Decimal::zero())>"MC"</button>
+                        <button class="btn memory" on:click=move |_| memory_recall()>"MR"</button>
+                        <button class="btn memory" on:click=move |_| memory_add(false)>"M+"</button>
+                        <button class="btn memory" on:click=move |_| memory_add(true)>"M−"</button>
+                    </div>
+                </Show>
+
+                <div class="calculator-body" class:blocked=move || calc.get().is_blocked()>
+                    <button
+                        class="btn function clear-key"
+                        class:pressed=move || pressed.get() == Some("C")
+                        on:click=move |_| clear_display()
+                    >
+                        "C"
+                    </button>
+                    <button
+                        class="btn function clear-key"
+                        class:pressed=move || pressed.get() == Some("CE")
+                        on:click=move |_| clear_entry()
+                    >
+                        "CE"
+                    </button>
+                    <button
+                        class="btn function"
+                        class:pressed=move || pressed.get() == Some("⌫")
+                        on:click=move |_| backspace()
+                    >
+                        "⌫"
+                    </button>
+                    <button
+                        class="btn operator"
+                        class:pressed=move || pressed.get() == Some("÷")
+                        on:click=move |_| input_operator('÷')
+                    >
+                        "÷"
+                    </button>
+
+                    <button
+                        class="btn function"
+                        class:pressed=move || pressed.get() == Some("(")
+                        on:click=move |_| input_paren('(')
+                    >
+                        "("
+                    </button>
+                    <button
+                        class="btn function"
+                        class:pressed=move || pressed.get() == Some(")")
+                        on:click=move |_| input_paren(')')
+                    >
+                        ")"
+                    </button>
+                    <button
+                        class="btn function"
+                        class:pressed=move || pressed.get() == Some("%")
+                        on:click=move |_| input_percent()
+                    >
+                        "%"
+                    </button>
+                    <button
+                        class="btn operator"
+                        class:pressed=move || pressed.get() == Some("^")
+                        on:click=move |_| input_operator('^')
+                    >
+                        "xʸ"
+                    </button>
+
+                    <button
+                        class="btn number"
+                        class:pressed=move || pressed.get() == Some("7")
+                        on:click=move |_| input_digit('7')
+                    >
+                        "7"
+                    </button>
+                    <button
+                        class="btn number"
+                        class:pressed=move || pressed.get() == Some("8")
+                        on:click=move |_| input_digit('8')
+                    >
+                        "8"
+                    </button>
+                    <button
+                        class="btn number"
+                        class:pressed=move || pressed.get() == Some("9")
+                        on:click=move |_| input_digit('9')
+                    >
+                        "9"
+                    </button>
+                    <button
+                        class="btn operator"
+                        class:pressed=move || pressed.get() == Some("×")
+                        on:click=move |_| input_operator('×')
+                    >
+                        "×"
+                    </button>
+
+                    <button
+                        class="btn number"
+                        class:pressed=move || pressed.get() == Some("4")
+                        on:click=move |_| input_digit('4')
+                    >
+                        "4"
+                    </button>
+                    <button
+                        class="btn number"
+                        class:pressed=move || pressed.get() == Some("5")
+                        on:click=move |_| input_digit('5')
+                    >
+                        "5"
+                    </button>
+                    <button
+                        class="btn number"
+                        class:pressed=move || pressed.get() == Some("6")
+                        on:click=move |_| input_digit('6')
+                    >
+                        "6"
+                    </button>
+                    <button
+                        class="btn operator"
+                        class:pressed=move || pressed.get() == Some("−")
+                        on:click=move |_| input_operator('−')
+                    >
+                        "−"
+                    </button>
+
+                    <button
+                        class="btn number"
+                        class:pressed=move || pressed.get() == Some("1")
+                        on:click=move |_| input_digit('1')
+                    >
+                        "1"
+                    </button>
+                    <button
+                        class="btn number"
+                        class:pressed=move || pressed.get() == Some("2")
+                        on:click=move |_| input_digit('2')
+                    >
+                        "2"
+                    </button>
+                    <button
+                        class="btn number"
+                        class:pressed=move || pressed.get() == Some("3")
+                        on:click=move |_| input_digit('3')
+                    >
+                        "3"
+                    </button>
+                    <button
+                        class="btn operator"
+                        class:pressed=move || pressed.get() == Some("+")
+                        on:click=move |_| input_operator('+')
+                    >
+                        "+"
+                    </button>
+
+                    <button
+                        class="btn number zero"
+                        class:pressed=move || pressed.get() == Some("0")
+                        on:click=move |_| input_digit('0')
+                    >
+                        "0"
+                    </button>
+                    <button
+                        class="btn number"
+                        class:pressed=move || pressed.get() == Some(".")
+                        on:click=move |_| input_decimal()
+                    >
+                        "."
+                    </button>
+                    <button
+                        class="btn equals"
+                        class:pressed=move || pressed.get() == Some("=")
+                        on:click=move |_| calculate_result()
+                    >
+                        "="
+                    </button>
+                </div>
+            </Show>
         </div>
     }
 }
@@ -0,0 +1,740 @@
+use std::fmt;
+
+use leptos::prelude::*;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Radix {
+    Hex,
+    Dec,
+    Oct,
+    Bin,
+}
+
+impl Radix {
+    pub const ALL: [Radix; 4] = [Radix::Hex, Radix::Dec, Radix::Oct, Radix::Bin];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Radix::Hex => "HEX",
+            Radix::Dec => "DEC",
+            Radix::Oct => "OCT",
+            Radix::Bin => "BIN",
+        }
+    }
+
+    pub fn base(self) -> u32 {
+        match self {
+            Radix::Hex => 16,
+            Radix::Dec => 10,
+            Radix::Oct => 8,
+            Radix::Bin => 2,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum WordSize {
+    Byte,
+    Word,
+    DWord,
+    QWord,
+}
+
+impl WordSize {
+    pub const ALL: [WordSize; 4] = [WordSize::Byte, WordSize::Word, WordSize::DWord, WordSize::QWord];
+
+    pub fn bits(self) -> u32 {
+        match self {
+            WordSize::Byte => 8,
+            WordSize::Word => 16,
+            WordSize::DWord => 32,
+            WordSize::QWord => 64,
+        }
+    }
+}
+
+/// The integer type every value is held in. Results that don't fit wrap
+/// around in two's complement and are flagged as overflowed.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Word {
+    pub size: WordSize,
+    pub signed: bool,
+}
+
+impl Word {
+    pub fn label(self) -> String {
+        format!("{}{}", if self.signed { "i" } else { "u" }, self.size.bits())
+    }
+
+    pub fn mask(self) -> u64 {
+        u64::MAX >> (64 - self.size.bits())
+    }
+
+    pub fn min(self) -> i128 {
+        if self.signed {
+            -(1_i128 << (self.size.bits() - 1))
+        } else {
+            0
+        }
+    }
+
+    pub fn max(self) -> i128 {
+        if self.signed {
+            (1_i128 << (self.size.bits() - 1)) - 1
+        } else {
+            self.mask() as i128
+        }
+    }
+
+    /// Reads a bit pattern as a number of this type.
+    pub fn interpret(self, bits: u64) -> i128 {
+        let bits = bits & self.mask();
+        let sign_bit = 1_u64 << (self.size.bits() - 1);
+        if self.signed && bits & sign_bit != 0 {
+            bits as i128 - (1_i128 << self.size.bits())
+        } else {
+            bits as i128
+        }
+    }
+
+    pub fn to_bits(self, value: i128) -> u64 {
+        (value as u128 as u64) & self.mask()
+    }
+
+    fn fits(self, value: i128) -> bool {
+        (self.min()..=self.max()).contains(&value)
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum BinaryOp {
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Mod,
+    And,
+    Or,
+    Xor,
+    Shl,
+    Shr,
+}
+
+impl BinaryOp {
+    pub fn symbol(self) -> &'static str {
+        match self {
+            BinaryOp::Add => "+",
+            BinaryOp::Sub => "−",
+            BinaryOp::Mul => "×",
+            BinaryOp::Div => "÷",
+            BinaryOp::Mod => "mod",
+            BinaryOp::And => "AND",
+            BinaryOp::Or => "OR",
+            BinaryOp::Xor => "XOR",
+            BinaryOp::Shl => "<<",
+            BinaryOp::Shr => ">>",
+        }
+    }
+
+    /// C precedence: multiplicative, additive, shifts, AND, XOR, OR.
+    fn precedence(self) -> u8 {
+        match self {
+            BinaryOp::Mul | BinaryOp::Div | BinaryOp::Mod => 6,
+            BinaryOp::Add | BinaryOp::Sub => 5,
+            BinaryOp::Shl | BinaryOp::Shr => 4,
+            BinaryOp::And => 3,
+            BinaryOp::Xor => 2,
+            BinaryOp::Or => 1,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Token {
+    /// A literal, stored as its bit pattern so changing radix only changes
+    /// how it is shown.
+    Number(u64),
+    Op(BinaryOp),
+    Not,
+    LParen,
+    RParen,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ProgError {
+    DivisionByZero,
+    Syntax,
+}
+
+impl fmt::Display for ProgError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ProgError::DivisionByZero => write!(f, "Cannot divide by zero"),
+            ProgError::Syntax => write!(f, "Incomplete expression"),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Outcome {
+    pub bits: u64,
+    pub overflowed: bool,
+}
+
+struct Evaluator<'a> {
+    tokens: &'a [Token],
+    pos: usize,
+    word: Word,
+    overflowed: bool,
+}
+
+impl Evaluator<'_> {
+    fn peek(&self) -> Option<Token> {
+        self.tokens.get(self.pos).copied()
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.peek();
+        self.pos += 1;
+        token
+    }
+
+    /// Brings an intermediate result back into the word, recording whether
+    /// it had to wrap. `None` means the operation overflowed even `i128`.
+    fn fit(&mut self, value: Option<i128>, wrapped: i128) -> i128 {
+        match value {
+            Some(value) if self.word.fits(value) => value,
+            _ => {
+                self.overflowed = true;
+                self.word.interpret(self.word.to_bits(wrapped))
+            }
+        }
+    }
+
+    fn expression(&mut self, min_precedence: u8) -> Result<i128, ProgError> {
+        let mut lhs = self.unary()?;
+        while let Some(Token::Op(op)) = self.peek() {
+            if op.precedence() < min_precedence {
+                break;
+            }
+            self.pos += 1;
+            let rhs = self.expression(op.precedence() + 1)?;
+            lhs = self.apply(op, lhs, rhs)?;
+        }
+        Ok(lhs)
+    }
+
+    fn unary(&mut self) -> Result<i128, ProgError> {
+        match self.next() {
+            Some(Token::Number(bits)) => Ok(self.word.interpret(bits)),
+            Some(Token::Not) => {
+                let value = self.unary()?;
+                Ok(self.word.interpret(!self.word.to_bits(value)))
+            }
+            Some(Token::Op(BinaryOp::Sub)) => {
+                let value = self.unary()?;
+                Ok(self.fit(value.checked_neg(), value.wrapping_neg()))
+            }
+            Some(Token::LParen) => {
+                let value = self.expression(0)?;
+                // A missing closing parenthesis at the very end is implied.
+                match self.next() {
+                    Some(Token::RParen) | None => Ok(value),
+                    Some(_) => Err(ProgError::Syntax),
+                }
+            }
+            _ => Err(ProgError::Syntax),
+        }
+    }
+
+    fn apply(&mut self, op: BinaryOp, a: i128, b: i128) -> Result<i128, ProgError> {
+        let word = self.word;
+        let bits = word.size.bits();
+        Ok(match op {
+            BinaryOp::Add => self.fit(a.checked_add(b), a.wrapping_add(b)),
+            BinaryOp::Sub => self.fit(a.checked_sub(b), a.wrapping_sub(b)),
+            BinaryOp::Mul => self.fit(a.checked_mul(b), a.wrapping_mul(b)),
+            BinaryOp::Div => {
+                if b == 0 {
+                    return Err(ProgError::DivisionByZero);
+                }
+                self.fit(Some(a / b), a / b)
+            }
+            BinaryOp::Mod => {
+                if b == 0 {
+                    return Err(ProgError::DivisionByZero);
+                }
+                a % b
+            }
+            BinaryOp::And => word.interpret(word.to_bits(a) & word.to_bits(b)),
+            BinaryOp::Or => word.interpret(word.to_bits(a) | word.to_bits(b)),
+            BinaryOp::Xor => word.interpret(word.to_bits(a) ^ word.to_bits(b)),
+            // Bits shifted past the word are discarded and shifting by the
+            // word size or more clears the value. Like multiplying by 2^n,
+            // it overflows when the result no longer equals a × 2^n.
+            BinaryOp::Shl => match u32::try_from(b) {
+                Ok(shift) if shift < bits => self.fit(
+                    a.checked_mul(1_i128 << shift),
+                    (word.to_bits(a) << shift) as i128,
+                ),
+                _ => self.fit((a == 0).then_some(0), 0),
+            },
+            // Signed values shift arithmetically, unsigned ones logically.
+            BinaryOp::Shr => match u32::try_from(b) {
+                Ok(shift) if shift < bits => {
+                    if word.signed {
+                        a >> shift
+                    } else {
+                        word.interpret(word.to_bits(a) >> shift)
+                    }
+                }
+                _ if word.signed && a < 0 => -1,
+                _ => 0,
+            },
+        })
+    }
+}
+
+pub fn evaluate(tokens: &[Token], word: Word) -> Result<Outcome, ProgError> {
+    let mut evaluator = Evaluator {
+        tokens,
+        pos: 0,
+        word,
+        overflowed: false,
+    };
+    let value = evaluator.expression(0)?;
+    if evaluator.pos < tokens.len() {
+        return Err(ProgError::Syntax);
+    }
+    Ok(Outcome {
+        bits: word.to_bits(value),
+        overflowed: evaluator.overflowed,
+    })
+}
+
+/// Shows a bit pattern in `radix`. Decimal honours the sign of the word;
+/// the other radices show the raw bits, grouped when `grouped` is set.
+pub fn format_bits(bits: u64, radix: Radix, word: Word, grouped: bool) -> String {
+    let digits = match radix {
+        Radix::Dec => return word.interpret(bits).to_string(),
+        Radix::Hex => format!("{:X}", bits & word.mask()),
+        Radix::Oct => format!("{:o}", bits & word.mask()),
+        Radix::Bin => format!("{:b}", bits & word.mask()),
+    };
+    if !grouped || radix == Radix::Oct {
+        return digits;
+    }
+    let group = 4;
+    let padded = format!("{:0>width$}", digits, width = digits.len().div_ceil(group) * group);
+    padded
+        .as_bytes()
+        .chunks(group)
+        .map(|chunk| std::str::from_utf8(chunk).unwrap_or_default())
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Appends a digit to a literal being typed, refusing digits that would
+/// take it past what the word can hold.
+pub fn append_digit(bits: u64, digit: u32, radix: Radix, word: Word) -> Option<u64> {
+    if digit >= radix.base() {
+        return None;
+    }
+    let limit = match radix {
+        Radix::Dec => word.max(),
+        _ => word.mask() as i128,
+    };
+    let current = match radix {
+        Radix::Dec => word.interpret(bits),
+        _ => (bits & word.mask()) as i128,
+    };
+    if current < 0 {
+        return None;
+    }
+    let next = current * radix.base() as i128 + digit as i128;
+    (next <= limit).then(|| word.to_bits(next))
+}
+
+pub fn remove_digit(bits: u64, radix: Radix, word: Word) -> u64 {
+    match radix {
+        Radix::Dec => word.to_bits(word.interpret(bits) / 10),
+        _ => (bits & word.mask()) / radix.base() as u64,
+    }
+}
+
+pub fn format_tokens(tokens: &[Token], radix: Radix, word: Word) -> String {
+    tokens
+        .iter()
+        .map(|token| match token {
+            Token::Number(bits) => format_bits(*bits, radix, word, false),
+            Token::Op(op) => op.symbol().to_string(),
+            Token::Not => "NOT".to_string(),
+            Token::LParen => "(".to_string(),
+            Token::RParen => ")".to_string(),
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+const DIGITS: [&str; 16] = [
+    "0", "1", "2", "3", "4", "5", "6", "7", "8", "9", "A", "B", "C", "D", "E", "F",
+];
+
+#[component]
+pub fn Programmer() -> impl IntoView {
+    let (tokens, set_tokens) = signal(Vec::<Token>::new());
+    let (radix, set_radix) = signal(Radix::Dec);
+    let (word, set_word) = signal(Word {
+        size: WordSize::QWord,
+        signed: true,
+    });
+    let (result, set_result) = signal(None::<Result<Outcome, ProgError>>);
+    let (just_evaluated, set_just_evaluated) = signal(false);
+
+    let result_bits = move || match result.get() {
+        Some(Ok(outcome)) => outcome.bits,
+        _ => 0,
+    };
+
+    // The value shown in the radix rows and bit grid: the literal being
+    // typed, otherwise the last result.
+    let current_bits = move || match tokens.get().last() {
+        Some(Token::Number(bits)) if !just_evaluated.get() => *bits,
+        _ if just_evaluated.get() => result_bits(),
+        _ => 0,
+    };
+
+    let start_fresh = move |carry_result: bool| {
+        if just_evaluated.get() {
+            let carried = match result.get() {
+                Some(Ok(outcome)) if carry_result => vec![Token::Number(outcome.bits)],
+                _ => Vec::new(),
+            };
+            set_tokens.set(carried);
+            set_result.set(None);
+            set_just_evaluated.set(false);
+        }
+    };
+
+    let input_digit = move |digit: u32| {
+        start_fresh(false);
+        let (radix, word) = (radix.get(), word.get());
+        set_tokens.update(|tokens| match tokens.last_mut() {
+            Some(Token::Number(bits)) => {
+                if let Some(next) = append_digit(*bits, digit, radix, word) {
+                    *bits = next;
+                }
+            }
+            Some(Token::RParen) => {}
+            _ => {
+                if let Some(bits) = append_digit(0, digit, radix, word) {
+                    tokens.push(Token::Number(bits));
+                }
+            }
+        });
+    };
+
+    let input_operator = move |op: BinaryOp| {
+        start_fresh(true);
+        set_tokens.update(|tokens| {
+            match tokens.last() {
+                None if op != BinaryOp::Sub => tokens.push(Token::Number(0)),
+                // A second operator replaces the first, except that `−`
+                // after another operator negates the next operand.
+                Some(Token::Op(_)) if op != BinaryOp::Sub => {
+                    tokens.pop();
+                }
+                _ => {}
+            }
+            tokens.push(Token::Op(op));
+        });
+    };
+
+    let input_token = move |token: Token| {
+        start_fresh(token == Token::Not);
+        set_tokens.update(|tokens| {
+            if token == Token::Not && matches!(tokens.last(), Some(Token::Number(_))) {
+                tokens.insert(tokens.len() - 1, Token::Not);
+            } else {
+                tokens.push(token);
+            }
+        });
+    };
+
+    let toggle_bit = move |bit: u32| {
+        let flipped = current_bits() ^ (1 << bit);
+        if just_evaluated.get() {
+            set_tokens.set(vec![Token::Number(flipped)]);
+            set_result.set(None);
+            set_just_evaluated.set(false);
+            return;
+        }
+        set_tokens.update(|tokens| match tokens.last_mut() {
+            Some(Token::Number(bits)) => *bits = flipped,
+            Some(Token::RParen) => {}
+            _ => tokens.push(Token::Number(flipped)),
+        });
+    };
+
+    let backspace = move || {
+        if just_evaluated.get() {
+            return;
+        }
+        let (radix, word) = (radix.get(), word.get());
+        set_tokens.update(|tokens| match tokens.last_mut() {
+            Some(Token::Number(bits)) if *bits != 0 => *bits = remove_digit(*bits, radix, word),
+            _ => {
+                tokens.pop();
+            }
+        });
+    };
+
+    let clear = move || {
+        set_tokens.set(Vec::new());
+        set_result.set(None);
+        set_just_evaluated.set(false);
+    };
+
+    let calculate = move || {
+        if tokens.get().is_empty() || just_evaluated.get() {
+            return;
+        }
+        set_result.set(Some(evaluate(&tokens.get(), word.get())));
+        set_just_evaluated.set(true);
+    };
+
+    // Changing the word size truncates every value to the new width.
+    let change_word = move |next: Word| {
+        let mask = next.mask();
+        set_tokens.update(|tokens| {
+            for token in tokens.iter_mut() {
+                if let Token::Number(bits) = token {
+                    *bits &= mask;
+                }
+            }
+        });
+        set_result.update(|result| {
+            if let Some(Ok(outcome)) = result {
+                outcome.bits &= mask;
+            }
+        });
+        set_word.set(next);
+    };
+
+    let main_display = move || match result.get() {
+        Some(Err(err)) if just_evaluated.get() => err.to_string(),
+        _ => format_bits(current_bits(), radix.get(), word.get(), true),
+    };
+
+    let overflowed = move || just_evaluated.get() && matches!(result.get(), Some(Ok(outcome)) if outcome.overflowed);
+
+    view! {
+        <div class="display programmer-display">
+            <div class="display-status">
+                <span>{move || word.get().label()}</span>
+                <span class="overflow-flag">{move || overflowed().then_some("OVERFLOW")}</span>
+            </div>
+            <div class="expression">
+                {move || {
+                    let text = format_tokens(&tokens.get(), radix.get(), word.get());
+                    if just_evaluated.get() { format!("{} =", text) } else { text }
+                }}
+            </div>
+            <div class="result programmer-result">{main_display}</div>
+        </div>
+
+        <div class="radix-list">
+            {Radix::ALL.into_iter().map(|r| view! {
+                <button
+                    class="radix-row"
+                    class:active=move || radix.get() == r
+                    on:click=move |_| set_radix.set(r)
+                >
+                    <span class="radix-label">{r.label()}</span>
+                    <span class="radix-value">{move || format_bits(current_bits(), r, word.get(), true)}</span>
+                </button>
+            }).collect_view()}
+        </div>
+
+        <div class="word-options">
+            {WordSize::ALL.into_iter().map(|size| view! {
+                <button
+                    class="mode-btn"
+                    class:active=move || word.get().size == size
+                    on:click=move |_| change_word(Word { size, ..word.get() })
+                >
+                    {format!("{}-bit", size.bits())}
+                </button>
+            }).collect_view()}
+            <button
+                class="mode-btn"
+                on:click=move |_| change_word(Word { signed: !word.get().signed, ..word.get() })
+            >
+                {move || if word.get().signed { "Signed" } else { "Unsigned" }}
+            </button>
+        </div>
+
+        <div class="bit-grid">
+            {move || {
+                let bits = current_bits();
+                (0..word.get().size.bits()).rev().map(|bit| {
+                    let set = bits & (1 << bit) != 0;
+                    view! {
+                        <button
+                            class="bit"
+                            class:set=set
+                            title=format!("Bit {}", bit)
+                            on:click=move |_| toggle_bit(bit)
+                        >
+                            {if set { "1" } else { "0" }}
+                        </button>
+                    }
+                }).collect_view()
+            }}
+        </div>
+
+        <div class="calculator-body programmer-body">
+            <button class="btn operator" on:click=move |_| input_operator(BinaryOp::And)>"AND"</button>
+            <button class="btn operator" on:click=move |_| input_operator(BinaryOp::Or)>"OR"</button>
+            <button class="btn operator" on:click=move |_| input_operator(BinaryOp::Xor)>"XOR"</button>
+            <button class="btn operator" on:click=move |_| input_token(Token::Not)>"NOT"</button>
+            <button class="btn operator" on:click=move |_| input_operator(BinaryOp::Shl)>"<<"</button>
+
+            <button class="btn operator" on:click=move |_| input_operator(BinaryOp::Shr)>">>"</button>
+            <button class="btn function" on:click=move |_| input_token(Token::LParen)>"("</button>
+            <button class="btn function" on:click=move |_| input_token(Token::RParen)>")"</button>
+            <button class="btn function" on:click=move |_| clear()>"C"</button>
+            <button class="btn function" on:click=move |_| backspace()>"⌫"</button>
+
+            {(0..16_u32).map(|digit| {
+                let enabled = move || digit < radix.get().base();
+                view! {
+                    <button
+                        class="btn number"
+                        disabled=move || !enabled()
+                        on:click=move |_| input_digit(digit)
+                    >
+                        {DIGITS[digit as usize]}
+                    </button>
+                }
+            }).collect_view()}
+
+            <button class="btn operator" on:click=move |_| input_operator(BinaryOp::Add)>"+"</button>
+            <button class="btn operator" on:click=move |_| input_operator(BinaryOp::Sub)>"−"</button>
+            <button class="btn operator" on:click=move |_| input_operator(BinaryOp::Mul)>"×"</button>
+            <button class="btn operator" on:click=move |_| input_operator(BinaryOp::Div)>"÷"</button>
+            <button class="btn operator" on:click=move |_| input_operator(BinaryOp::Mod)>"mod"</button>
+
+            <button class="btn equals" on:click=move |_| calculate()>"="</button>
+        </div>
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const I8: Word = Word { size: WordSize::Byte, signed: true };
+    const U8: Word = Word { size: WordSize::Byte, signed: false };
+    const I64: Word = Word { size: WordSize::QWord, signed: true };
+    const U64: Word = Word { size: WordSize::QWord, signed: false };
+
+    fn words() -> impl Iterator<Item = Word> {
+        WordSize::ALL
+            .into_iter()
+            .flat_map(|size| [true, false].map(|signed| Word { size, signed }))
+    }
+
+    /// Evaluates `a op b`, returning the value read back in `word` and the
+    /// overflow flag.
+    fn binary(a: i128, op: BinaryOp, b: i128, word: Word) -> Result<(i128, bool), ProgError> {
+        let tokens = [Token::Number(word.to_bits(a)), Token::Op(op), Token::Number(word.to_bits(b))];
+        evaluate(&tokens, word).map(|outcome| (word.interpret(outcome.bits), outcome.overflowed))
+    }
+
+    #[test]
+    fn arithmetic_wraps_and_flags_at_every_word_size() {
+        for word in words() {
+            let (min, max) = (word.min(), word.max());
+            let cases = [
+                (1, BinaryOp::Add, 2, (3, false)),
+                (max, BinaryOp::Add, 1, (min, true)),
+                (max, BinaryOp::Add, max, (if word.signed { -2 } else { max - 1 }, true)),
+                (min, BinaryOp::Sub, 1, (max, true)),
+                (5, BinaryOp::Sub, 3, (2, false)),
+                (max, BinaryOp::Mul, 1, (max, false)),
+                (max, BinaryOp::Mul, 2, (if word.signed { -2 } else { max - 1 }, true)),
+                (max / 2 + 1, BinaryOp::Mul, 2, (if word.signed { min } else { 0 }, true)),
+            ];
+            for (a, op, b, expected) in cases {
+                assert_eq!(binary(a, op, b, word), Ok(expected), "{} {:?} {} in {}", a, op, b, word.label());
+            }
+        }
+    }
+
+    #[test]
+    fn signed_division_edge_cases() {
+        assert_eq!(binary(i64::MIN as i128, BinaryOp::Div, -1, I64), Ok((i64::MIN as i128, true)));
+        assert_eq!(binary(i64::MIN as i128, BinaryOp::Mod, -1, I64), Ok((0, false)));
+        assert_eq!(binary(-128, BinaryOp::Div, -1, I8), Ok((-128, true)));
+        assert_eq!(binary(-7, BinaryOp::Div, 2, I8), Ok((-3, false)));
+        assert_eq!(binary(-7, BinaryOp::Mod, 2, I8), Ok((-1, false)));
+        assert_eq!(binary(7, BinaryOp::Div, 0, U8), Err(ProgError::DivisionByZero));
+        assert_eq!(binary(7, BinaryOp::Mod, 0, U8), Err(ProgError::DivisionByZero));
+    }
+
+    #[test]
+    fn negation_wraps_unsigned_and_signed_minimum() {
+        let negate = |value: i128, word: Word| {
+            evaluate(&[Token::Op(BinaryOp::Sub), Token::Number(word.to_bits(value))], word)
+                .map(|outcome| (word.interpret(outcome.bits), outcome.overflowed))
+        };
+        assert_eq!(negate(5, U8), Ok((251, true)));
+        assert_eq!(negate(0, U8), Ok((0, false)));
+        assert_eq!(negate(1, U64), Ok((u64::MAX as i128, true)));
+        assert_eq!(negate(5, I8), Ok((-5, false)));
+        assert_eq!(negate(-128, I8), Ok((-128, true)));
+    }
+
+    #[test]
+    fn shifts() {
+        for (a, op, b, word, expected) in [
+            (1, BinaryOp::Shl, 7, U8, (128, false)),
+            (0b1100_0000, BinaryOp::Shl, 1, U8, (0b1000_0000, true)),
+            (1, BinaryOp::Shl, 7, I8, (-128, true)),
+            (-1, BinaryOp::Shl, 7, I8, (-128, false)),
+            (-64, BinaryOp::Shl, 1, I8, (-128, false)),
+            (1, BinaryOp::Shl, 8, U8, (0, true)),
+            (0, BinaryOp::Shl, 200, U8, (0, false)),
+            (1, BinaryOp::Shl, 63, U64, (1 << 63, false)),
+            (1, BinaryOp::Shl, 64, U64, (0, true)),
+            // Shr is arithmetic for signed words, logical for unsigned.
+            (-128, BinaryOp::Shr, 1, I8, (-64, false)),
+            (0x80, BinaryOp::Shr, 1, U8, (0x40, false)),
+            (-1, BinaryOp::Shr, 7, I8, (-1, false)),
+            (-1, BinaryOp::Shr, 8, I8, (-1, false)),
+            (100, BinaryOp::Shr, 8, I8, (0, false)),
+            (0xFF, BinaryOp::Shr, 8, U8, (0, false)),
+            (i64::MIN as i128, BinaryOp::Shr, 63, I64, (-1, false)),
+            (u64::MAX as i128, BinaryOp::Shr, 63, U64, (1, false)),
+        ] {
+            assert_eq!(binary(a, op, b, word), Ok(expected), "{} {:?} {} in {}", a, op, b, word.label());
+        }
+    }
+
+    #[test]
+    fn negative_values_in_each_radix() {
+        let bits = I8.to_bits(-2);
+        assert_eq!(format_bits(bits, Radix::Dec, I8, false), "-2");
+        assert_eq!(format_bits(bits, Radix::Hex, I8, false), "FE");
+        assert_eq!(format_bits(bits, Radix::Oct, I8, false), "376");
+        assert_eq!(format_bits(bits, Radix::Bin, I8, true), "1111 1110");
+        // The same bits read as unsigned.
+        assert_eq!(format_bits(bits, Radix::Dec, U8, false), "254");
+
+        let word = Word { size: WordSize::Word, signed: true };
+        assert_eq!(format_bits(word.to_bits(-1), Radix::Hex, word, true), "FFFF");
+        assert_eq!(format_bits(I64.to_bits(i64::MIN as i128), Radix::Dec, I64, false), i64::MIN.to_string());
+        assert_eq!(format_bits(I64.to_bits(-1), Radix::Hex, I64, false), "FFFFFFFFFFFFFFFF");
+    }
+}
@@ -0,0 +1,361 @@
+use crate::decimal::Decimal;
+use crate::engine::{run, CalcError, Context, Environment, Outcome};
+
+const OPERATORS: [char; 5] = ['+', '−', '×', '÷', '^'];
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Phase {
+    /// Building up the expression. `notice` holds the syntax problem found
+    /// by the last `=`, until the next key.
+    Editing { notice: Option<CalcError> },
+    Evaluated(Decimal),
+    /// A user function was defined; there is no value to show.
+    Defined(String),
+    /// A math error. Only C and CE are accepted until it is cleared.
+    Failed(CalcError),
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Input {
+    Digit(char),
+    Point,
+    Operator(char),
+    Paren(char),
+    /// Function names, constants and postfix operators.
+    Token(String),
+    /// A number from memory or the history tape.
+    Value(Decimal),
+    /// Replaces the expression, e.g. with one recalled from history.
+    Load(String),
+    Percent,
+    Backspace,
+    ClearEntry,
+    Clear,
+    Evaluate,
+}
+
+/// A successful `=`, handed back so the caller can record it.
+pub struct Evaluation {
+    pub expression: String,
+    pub result: Decimal,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct CalcState {
+    pub expression: String,
+    pub phase: Phase,
+}
+
+impl Default for CalcState {
+    fn default() -> Self {
+        Self {
+            expression: String::new(),
+            phase: Phase::Editing { notice: None },
+        }
+    }
+}
+
+impl CalcState {
+    pub fn is_blocked(&self) -> bool {
+        matches!(self.phase, Phase::Failed(_))
+    }
+
+    pub fn notice(&self) -> Option<&CalcError> {
+        match &self.phase {
+            Phase::Editing { notice } => notice.as_ref(),
+            _ => None,
+        }
+    }
+
+    /// The number being typed at the end of the expression.
+    pub fn current_number(&self) -> &str {
+        let start = self
+            .expression
+            .rfind(|c: char| !(c.is_ascii_digit() || c == '.'))
+            .map(|i| i + self.expression[i..].chars().next().map_or(1, char::len_utf8))
+            .unwrap_or(0);
+        &self.expression[start..]
+    }
+
+    /// The value memory keys act on: the result after `=`, otherwise the
+    /// expression so far if it evaluates. Nothing is assigned or defined.
+    pub fn current_value(&self, env: &Environment, ctx: &Context) -> Option<Decimal> {
+        match &self.phase {
+            Phase::Evaluated(result) => Some(result.clone()),
+            Phase::Editing { .. } => run(&self.expression, &mut env.clone(), ctx)
+                .ok()
+                .and_then(|outcome| outcome.value().cloned()),
+            Phase::Defined(_) | Phase::Failed(_) => None,
+        }
+    }
+
+    pub fn apply(&mut self, input: Input, ctx: &Context, env: &mut Environment) -> Option<Evaluation> {
+        match (&self.phase, input) {
+            (_, Input::Clear) => *self = Self::default(),
+            // CE after an error hands the failing expression back for fixing.
+            (Phase::Failed(_), Input::ClearEntry) => self.phase = Phase::Editing { notice: None },
+            (Phase::Failed(_), _) => {}
+            (Phase::Evaluated(_) | Phase::Defined(_), Input::ClearEntry) => *self = Self::default(),
+            (Phase::Evaluated(_) | Phase::Defined(_), Input::Backspace | Input::Percent | Input::Evaluate) => {}
+            (Phase::Evaluated(_) | Phase::Defined(_), input) => {
+                let continues = match &input {
+                    Input::Operator(_) => true,
+                    Input::Token(token) => token.starts_with(['^', '!']),
+                    _ => false,
+                };
+                self.start_editing(continues);
+                return self.apply(input, ctx, env);
+            }
+            (Phase::Editing { .. }, input) => {
+                self.phase = Phase::Editing { notice: None };
+                return self.edit(input, ctx, env);
+            }
+        }
+        None
+    }
+
+    /// Leaves a result behind, keeping it as the start of the new expression
+    /// when the key continues from it.
+    fn start_editing(&mut self, continue_from_result: bool) {
+        self.expression = match &self.phase {
+            Phase::Evaluated(result) if continue_from_result => literal(result),
+            _ => String::new(),
+        };
+        self.phase = Phase::Editing { notice: None };
+    }
+
+    fn edit(&mut self, input: Input, ctx: &Context, env: &mut Environment) -> Option<Evaluation> {
+        let e = &mut self.expression;
+        match input {
+            Input::Digit(digit) => e.push(digit),
+            Input::Point => {
+                if !self.current_number().contains('.') {
+                    let e = &mut self.expression;
+                    if !e.ends_with(|c: char| c.is_ascii_digit()) {
+                        e.push('0');
+                    }
+                    e.push('.');
+                }
+            }
+            Input::Operator(op) => {
+                if e.is_empty() && op != '−' {
+                    e.push('0');
+                }
+                // A second operator replaces the first, except that `−`
+                // after another operator starts a negative operand.
+                if op != '−' && e.ends_with(OPERATORS) {
+                    e.pop();
+                }
+                e.push(op);
+            }
+            Input::Paren(paren) => e.push(paren),
+            Input::Token(token) => e.push_str(&token),
+            Input::Value(value) => e.push_str(&literal(&value)),
+            Input::Load(expression) => *e = expression,
+            Input::Percent => {
+                if e.ends_with(|c: char| c.is_ascii_digit() || c == ')') {
+                    e.push('%');
+                }
+            }
+            Input::Backspace => {
+                e.pop();
+            }
+            Input::ClearEntry => {
+                let keep = e.len() - self.current_number().len();
+                self.expression.truncate(keep);
+            }
+            Input::Clear => *self = Self::default(),
+            Input::Evaluate => return self.evaluate(ctx, env),
+        }
+        None
+    }
+
+    fn evaluate(&mut self, ctx: &Context, env: &mut Environment) -> Option<Evaluation> {
+        if self.expression.is_empty() {
+            return None;
+        }
+        match run(&self.expression, env, ctx) {
+            Ok(Outcome::Defined(name)) => {
+                self.phase = Phase::Defined(name);
+                None
+            }
+            Ok(Outcome::Value(result) | Outcome::Assigned(_, result)) => {
+                self.phase = Phase::Evaluated(result.clone());
+                Some(Evaluation {
+                    expression: self.expression.clone(),
+                    result,
+                })
+            }
+            Err(err @ CalcError::Syntax(_)) => {
+                self.phase = Phase::Editing { notice: Some(err) };
+                None
+            }
+            Err(err) => {
+                self.phase = Phase::Failed(err);
+                None
+            }
+        }
+    }
+}
+
+/// Writes a value so it can be spliced into an expression.
+fn literal(value: &Decimal) -> String {
+    if value.is_negative() {
+        format!("(−{})", value.abs())
+    } else {
+        value.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn state(expression: &str, phase: Phase) -> CalcState {
+        CalcState {
+            expression: expression.to_string(),
+            phase,
+        }
+    }
+
+    fn editing() -> Phase {
+        Phase::Editing { notice: None }
+    }
+
+    fn number(n: i64) -> Decimal {
+        Decimal::from_i64(n)
+    }
+
+    fn check(cases: Vec<(CalcState, Input, CalcState)>) {
+        let ctx = Context::default();
+        for (start, input, expected) in cases {
+            let mut state = start.clone();
+            state.apply(input.clone(), &ctx, &mut Environment::default());
+            assert_eq!(state, expected, "{:?} after {:?}", input, start);
+        }
+    }
+
+    #[test]
+    fn editing_builds_the_expression() {
+        let start = || state("12+3", editing());
+        check(vec![
+            (start(), Input::Digit('4'), state("12+34", editing())),
+            (start(), Input::Point, state("12+3.", editing())),
+            (start(), Input::Operator('×'), state("12+3×", editing())),
+            (start(), Input::Paren('('), state("12+3(", editing())),
+            (start(), Input::Token("!".to_string()), state("12+3!", editing())),
+            (start(), Input::Value(number(-2)), state("12+3(−2)", editing())),
+            (start(), Input::Load("7×6".to_string()), state("7×6", editing())),
+            (start(), Input::Percent, state("12+3%", editing())),
+            (start(), Input::Backspace, state("12+", editing())),
+            (start(), Input::ClearEntry, state("12+", editing())),
+            (start(), Input::Clear, CalcState::default()),
+            (start(), Input::Evaluate, state("12+3", Phase::Evaluated(number(15)))),
+            (state("", editing()), Input::Point, state("0.", editing())),
+            (state("1.5", editing()), Input::Point, state("1.5", editing())),
+            (state("", editing()), Input::Operator('×'), state("0×", editing())),
+            (state("", editing()), Input::Operator('−'), state("−", editing())),
+            (state("2×", editing()), Input::Operator('÷'), state("2÷", editing())),
+            (state("2×", editing()), Input::Operator('−'), state("2×−", editing())),
+            (state("2×", editing()), Input::Percent, state("2×", editing())),
+            (state("", editing()), Input::Evaluate, state("", editing())),
+            (state("1÷0", editing()), Input::Evaluate, state("1÷0", Phase::Failed(CalcError::DivisionByZero))),
+        ]);
+    }
+
+    #[test]
+    fn a_syntax_notice_lasts_until_the_next_key() {
+        let notice = || Phase::Editing {
+            notice: Some(CalcError::Syntax("unexpected end of expression".to_string())),
+        };
+        check(vec![
+            (state("12+", editing()), Input::Evaluate, state("12+", notice())),
+            (state("12+", notice()), Input::Digit('4'), state("12+4", editing())),
+            (state("12+", notice()), Input::Operator('×'), state("12×", editing())),
+            (state("12+", notice()), Input::Percent, state("12+", editing())),
+            (state("12+", notice()), Input::Backspace, state("12", editing())),
+            (state("12+", notice()), Input::Clear, CalcState::default()),
+            (state("12+", notice()), Input::Evaluate, state("12+", notice())),
+        ]);
+    }
+
+    #[test]
+    fn keys_after_a_result_start_over_or_continue_from_it() {
+        let start = || state("2+3", Phase::Evaluated(number(5)));
+        check(vec![
+            (start(), Input::Digit('4'), state("4", editing())),
+            (start(), Input::Point, state("0.", editing())),
+            (start(), Input::Operator('×'), state("5×", editing())),
+            (start(), Input::Paren('('), state("(", editing())),
+            (start(), Input::Token("^".to_string()), state("5^", editing())),
+            (start(), Input::Token("!".to_string()), state("5!", editing())),
+            (start(), Input::Token("sin".to_string()), state("sin", editing())),
+            (start(), Input::Value(number(7)), state("7", editing())),
+            (start(), Input::Load("1+1".to_string()), state("1+1", editing())),
+            (start(), Input::Percent, start()),
+            (start(), Input::Backspace, start()),
+            (start(), Input::ClearEntry, CalcState::default()),
+            (start(), Input::Clear, CalcState::default()),
+            (start(), Input::Evaluate, start()),
+            (
+                state("2−7", Phase::Evaluated(number(-5))),
+                Input::Operator('×'),
+                state("(−5)×", editing()),
+            ),
+        ]);
+    }
+
+    #[test]
+    fn keys_after_a_definition_start_a_new_expression() {
+        let start = || state("f(x)=x^2", Phase::Defined("f".to_string()));
+        check(vec![
+            (start(), Input::Digit('4'), state("4", editing())),
+            (start(), Input::Point, state("0.", editing())),
+            (start(), Input::Operator('+'), state("0+", editing())),
+            (start(), Input::Paren('('), state("(", editing())),
+            (start(), Input::Token("!".to_string()), state("!", editing())),
+            (start(), Input::Value(number(7)), state("7", editing())),
+            (start(), Input::Load("f(3)".to_string()), state("f(3)", editing())),
+            (start(), Input::Percent, start()),
+            (start(), Input::Backspace, start()),
+            (start(), Input::ClearEntry, CalcState::default()),
+            (start(), Input::Clear, CalcState::default()),
+            (start(), Input::Evaluate, start()),
+        ]);
+    }
+
+    #[test]
+    fn an_error_blocks_everything_but_clearing() {
+        let start = || state("1÷0", Phase::Failed(CalcError::DivisionByZero));
+        check(vec![
+            (start(), Input::Digit('4'), start()),
+            (start(), Input::Point, start()),
+            (start(), Input::Operator('+'), start()),
+            (start(), Input::Paren('('), start()),
+            (start(), Input::Token("!".to_string()), start()),
+            (start(), Input::Value(number(7)), start()),
+            (start(), Input::Load("1+1".to_string()), start()),
+            (start(), Input::Percent, start()),
+            (start(), Input::Backspace, start()),
+            (start(), Input::Evaluate, start()),
+            (start(), Input::ClearEntry, state("1÷0", editing())),
+            (start(), Input::Clear, CalcState::default()),
+        ]);
+    }
+
+    #[test]
+    fn evaluating_hands_back_what_to_record() {
+        let ctx = Context::default();
+        let mut env = Environment::default();
+        let mut calc = state("let r = 2×3", editing());
+        let evaluation = calc.apply(Input::Evaluate, &ctx, &mut env).unwrap();
+        assert_eq!(evaluation.expression, "let r = 2×3");
+        assert_eq!(evaluation.result, number(6));
+        assert_eq!(env.variables.get("r"), Some(&number(6)));
+
+        let mut calc = state("f(x)=x^2", editing());
+        assert!(calc.apply(Input::Evaluate, &ctx, &mut env).is_none());
+        assert_eq!(calc.phase, Phase::Defined("f".to_string()));
+        assert!(calc.apply(Input::Evaluate, &ctx, &mut env).is_none());
+    }
+}
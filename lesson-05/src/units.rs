@@ -0,0 +1,482 @@
+use std::collections::BTreeMap;
+use std::fmt;
+
+use leptos::prelude::*;
+use serde::Deserialize;
+use wasm_bindgen_futures::JsFuture;
+
+use crate::decimal::{Decimal, FormatOptions};
+use crate::engine::{eval_str, Context};
+
+/// Rates bundled with the app; a newer file can be loaded at runtime.
+const DEFAULT_RATES: &str = include_str!("../rates.json");
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Dimension {
+    Length,
+    Mass,
+    Temperature,
+    DataSize,
+    Time,
+    Currency,
+}
+
+impl Dimension {
+    pub const ALL: [Dimension; 6] = [
+        Dimension::Length,
+        Dimension::Mass,
+        Dimension::Temperature,
+        Dimension::DataSize,
+        Dimension::Time,
+        Dimension::Currency,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Dimension::Length => "Length",
+            Dimension::Mass => "Mass",
+            Dimension::Temperature => "Temperature",
+            Dimension::DataSize => "Data",
+            Dimension::Time => "Time",
+            Dimension::Currency => "Currency",
+        }
+    }
+}
+
+/// A unit relative to its dimension's base unit:
+/// `base = (value + offset) × factor ÷ divisor`.
+#[derive(Clone, Copy, Debug)]
+pub struct Unit {
+    pub symbol: &'static str,
+    pub name: &'static str,
+    pub dimension: Dimension,
+    factor: &'static str,
+    divisor: &'static str,
+    offset: &'static str,
+}
+
+const fn unit(symbol: &'static str, name: &'static str, dimension: Dimension, factor: &'static str) -> Unit {
+    Unit {
+        symbol,
+        name,
+        dimension,
+        factor,
+        divisor: "1",
+        offset: "0",
+    }
+}
+
+pub const UNITS: &[Unit] = &[
+    unit("mm", "Millimetre", Dimension::Length, "0.001"),
+    unit("cm", "Centimetre", Dimension::Length, "0.01"),
+    unit("m", "Metre", Dimension::Length, "1"),
+    unit("km", "Kilometre", Dimension::Length, "1000"),
+    unit("in", "Inch", Dimension::Length, "0.0254"),
+    unit("ft", "Foot", Dimension::Length, "0.3048"),
+    unit("yd", "Yard", Dimension::Length, "0.9144"),
+    unit("mi", "Mile", Dimension::Length, "1609.344"),
+    unit("nmi", "Nautical mile", Dimension::Length, "1852"),
+    unit("mg", "Milligram", Dimension::Mass, "0.000001"),
+    unit("g", "Gram", Dimension::Mass, "0.001"),
+    unit("kg", "Kilogram", Dimension::Mass, "1"),
+    unit("t", "Tonne", Dimension::Mass, "1000"),
+    unit("oz", "Ounce", Dimension::Mass, "0.028349523125"),
+    unit("lb", "Pound", Dimension::Mass, "0.45359237"),
+    unit("st", "Stone", Dimension::Mass, "6.35029318"),
+    Unit {
+        offset: "273.15",
+        ..unit("°C", "Celsius", Dimension::Temperature, "1")
+    },
+    Unit {
+        divisor: "9",
+        offset: "459.67",
+        ..unit("°F", "Fahrenheit", Dimension::Temperature, "5")
+    },
+    unit("K", "Kelvin", Dimension::Temperature, "1"),
+    Unit {
+        divisor: "8",
+        ..unit("bit", "Bit", Dimension::DataSize, "1")
+    },
+    unit("B", "Byte", Dimension::DataSize, "1"),
+    unit("kB", "Kilobyte", Dimension::DataSize, "1000"),
+    unit("MB", "Megabyte", Dimension::DataSize, "1000000"),
+    unit("GB", "Gigabyte", Dimension::DataSize, "1000000000"),
+    unit("TB", "Terabyte", Dimension::DataSize, "1000000000000"),
+    unit("KiB", "Kibibyte", Dimension::DataSize, "1024"),
+    unit("MiB", "Mebibyte", Dimension::DataSize, "1048576"),
+    unit("GiB", "Gibibyte", Dimension::DataSize, "1073741824"),
+    unit("TiB", "Tebibyte", Dimension::DataSize, "1099511627776"),
+    unit("ms", "Millisecond", Dimension::Time, "0.001"),
+    unit("s", "Second", Dimension::Time, "1"),
+    unit("min", "Minute", Dimension::Time, "60"),
+    unit("h", "Hour", Dimension::Time, "3600"),
+    unit("d", "Day", Dimension::Time, "86400"),
+    unit("wk", "Week", Dimension::Time, "604800"),
+    unit("yr", "Year (365.25 d)", Dimension::Time, "31557600"),
+];
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum ConversionError {
+    UnknownUnit(String),
+    Incompatible { from: Dimension, to: Dimension },
+    /// A unit's factor, divisor or offset isn't a number, or would divide
+    /// by zero.
+    InvalidDefinition(String),
+}
+
+impl fmt::Display for ConversionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConversionError::UnknownUnit(symbol) => write!(f, "Unknown unit: {}", symbol),
+            ConversionError::InvalidDefinition(symbol) => write!(f, "Invalid definition for {}", symbol),
+            ConversionError::Incompatible { from, to } => write!(
+                f,
+                "Cannot convert {} to {}",
+                from.label().to_lowercase(),
+                to.label().to_lowercase()
+            ),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct RatesFile {
+    base: String,
+    #[serde(default)]
+    date: String,
+    rates: BTreeMap<String, f64>,
+}
+
+/// Exchange rates as units of each currency per one unit of `base`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct CurrencyRates {
+    pub base: String,
+    pub date: String,
+    rates: BTreeMap<String, Decimal>,
+}
+
+impl CurrencyRates {
+    pub fn from_json(json: &str) -> Result<Self, String> {
+        let file: RatesFile = serde_json::from_str(json).map_err(|e| format!("Invalid rates file: {}", e))?;
+        let base = file.base.to_uppercase();
+        let mut rates = BTreeMap::new();
+        for (code, rate) in file.rates {
+            let rate = Decimal::from_f64(rate)
+                .filter(|rate| !rate.is_zero() && !rate.is_negative())
+                .ok_or_else(|| format!("Invalid rate for {}", code))?;
+            rates.insert(code.to_uppercase(), rate);
+        }
+        rates.insert(base.clone(), Decimal::from_i64(1));
+        Ok(Self {
+            base,
+            date: file.date,
+            rates,
+        })
+    }
+
+    pub fn bundled() -> Self {
+        Self::from_json(DEFAULT_RATES).unwrap_or_else(|_| Self {
+            base: "USD".to_string(),
+            date: String::new(),
+            rates: BTreeMap::from([("USD".to_string(), Decimal::from_i64(1))]),
+        })
+    }
+
+    pub fn codes(&self) -> Vec<String> {
+        self.rates.keys().cloned().collect()
+    }
+}
+
+struct Resolved {
+    dimension: Dimension,
+    factor: Decimal,
+    divisor: Decimal,
+    offset: Decimal,
+}
+
+impl Unit {
+    fn resolve(&self) -> Result<Resolved, ConversionError> {
+        let invalid = || ConversionError::InvalidDefinition(self.symbol.to_string());
+        let parse = |literal: &str| literal.parse::<Decimal>().map_err(|_| invalid());
+        let nonzero = |literal: &str| parse(literal).and_then(|value| if value.is_zero() { Err(invalid()) } else { Ok(value) });
+        Ok(Resolved {
+            dimension: self.dimension,
+            factor: nonzero(self.factor)?,
+            divisor: nonzero(self.divisor)?,
+            offset: parse(self.offset)?,
+        })
+    }
+}
+
+fn resolve(symbol: &str, rates: &CurrencyRates) -> Result<Resolved, ConversionError> {
+    if let Some(unit) = UNITS.iter().find(|unit| unit.symbol == symbol) {
+        return unit.resolve();
+    }
+    match rates.rates.get(symbol) {
+        Some(rate) => Ok(Resolved {
+            dimension: Dimension::Currency,
+            factor: Decimal::from_i64(1),
+            divisor: rate.clone(),
+            offset: Decimal::zero(),
+        }),
+        None => Err(ConversionError::UnknownUnit(symbol.to_string())),
+    }
+}
+
+/// Converts through the base unit of the shared dimension, dividing at the
+/// context's precision.
+pub fn convert(
+    value: &Decimal,
+    from: &str,
+    to: &str,
+    rates: &CurrencyRates,
+    ctx: &Context,
+) -> Result<Decimal, ConversionError> {
+    let (from_symbol, to_symbol) = (from, to);
+    let from = resolve(from, rates)?;
+    let to = resolve(to, rates)?;
+    if from.dimension != to.dimension {
+        return Err(ConversionError::Incompatible {
+            from: from.dimension,
+            to: to.dimension,
+        });
+    }
+    let divide = |a: &Decimal, b: &Decimal, symbol: &str| {
+        a.div(b, ctx.precision, ctx.rounding)
+            .ok_or_else(|| ConversionError::InvalidDefinition(symbol.to_string()))
+    };
+
+    let base = divide(&(&(value + &from.offset) * &from.factor), &from.divisor, from_symbol)?;
+    let result = &divide(&(&base * &to.divisor), &to.factor, to_symbol)? - &to.offset;
+    Ok(result.round_to_precision(ctx.precision, ctx.rounding))
+}
+
+pub fn units_of(dimension: Dimension, rates: &CurrencyRates) -> Vec<(String, String)> {
+    if dimension == Dimension::Currency {
+        return rates.codes().into_iter().map(|code| (code.clone(), code)).collect();
+    }
+    UNITS
+        .iter()
+        .filter(|unit| unit.dimension == dimension)
+        .map(|unit| (unit.symbol.to_string(), format!("{} ({})", unit.name, unit.symbol)))
+        .collect()
+}
+
+async fn read_file(file: web_sys::File) -> Result<String, String> {
+    JsFuture::from(file.text())
+        .await
+        .ok()
+        .and_then(|value| value.as_string())
+        .ok_or_else(|| format!("Could not read {}", file.name()))
+}
+
+#[component]
+pub fn Converter(
+    #[prop(into)] context: Signal<Context>,
+    #[prop(into)] thousands_separator: Signal<bool>,
+) -> impl IntoView {
+    let (rates, set_rates) = signal(CurrencyRates::bundled());
+    let (rates_message, set_rates_message) = signal(None::<String>);
+    let (dimension, set_dimension) = signal(Dimension::Length);
+    let (from, set_from) = signal("m".to_string());
+    let (to, set_to) = signal("ft".to_string());
+    let (input, set_input) = signal("1".to_string());
+
+    let select_dimension = move |next: Dimension| {
+        let units = units_of(next, &rates.get());
+        set_from.set(units.first().map(|(symbol, _)| symbol.clone()).unwrap_or_default());
+        set_to.set(units.get(1).or(units.first()).map(|(symbol, _)| symbol.clone()).unwrap_or_default());
+        set_dimension.set(next);
+    };
+
+    let swap = move |_| {
+        let previous = from.get();
+        set_from.set(to.get());
+        set_to.set(previous);
+    };
+
+    let result = move || {
+        let ctx = context.get();
+        let value = eval_str(&input.get(), &ctx).map_err(|err| err.to_string())?;
+        let converted = convert(&value, &from.get(), &to.get(), &rates.get(), &ctx).map_err(|err| err.to_string())?;
+        Ok::<_, String>(converted.format(FormatOptions {
+            thousands_separator: thousands_separator.get(),
+            ..FormatOptions::default()
+        }))
+    };
+
+    let load_rates = move |ev: leptos::ev::Event| {
+        let input: web_sys::HtmlInputElement = event_target(&ev);
+        let Some(file) = input.files().and_then(|files| files.get(0)) else {
+            return;
+        };
+        input.set_value("");
+        leptos::task::spawn_local(async move {
+            match read_file(file).await.and_then(|json| CurrencyRates::from_json(&json)) {
+                Ok(loaded) => {
+                    set_rates_message.set(Some(format!("Loaded {} rates", loaded.codes().len())));
+                    set_rates.set(loaded);
+                    if dimension.get_untracked() == Dimension::Currency {
+                        select_dimension(Dimension::Currency);
+                    }
+                }
+                Err(err) => set_rates_message.set(Some(err)),
+            }
+        });
+    };
+
+    let unit_options = move |selected: ReadSignal<String>| {
+        units_of(dimension.get(), &rates.get())
+            .into_iter()
+            .map(|(symbol, label)| {
+                let value = symbol.clone();
+                view! { <option value=value selected=move || selected.get() == symbol>{label}</option> }
+            })
+            .collect_view()
+    };
+
+    view! {
+        <div class="converter">
+            <div class="dimension-list">
+                {Dimension::ALL.into_iter().map(|d| view! {
+                    <button
+                        class="mode-btn"
+                        class:active=move || dimension.get() == d
+                        on:click=move |_| select_dimension(d)
+                    >
+                        {d.label()}
+                    </button>
+                }).collect_view()}
+            </div>
+
+            <label class="converter-field">
+                "Value"
+                <input
+                    type="text"
+                    prop:value=input
+                    on:input=move |ev| set_input.set(event_target_value(&ev))
+                />
+            </label>
+
+            <div class="converter-units">
+                <select on:change=move |ev| set_from.set(event_target_value(&ev))>
+                    {move || unit_options(from)}
+                </select>
+                <button class="swap-btn" title="Swap units" on:click=swap>"⇄"</button>
+                <select on:change=move |ev| set_to.set(event_target_value(&ev))>
+                    {move || unit_options(to)}
+                </select>
+            </div>
+
+            <div class="display converter-result" class:error=move || result().is_err()>
+                {move || match result() {
+                    Ok(value) => format!("{} {}", value, to.get()),
+                    Err(err) => err,
+                }}
+            </div>
+
+            <Show when=move || dimension.get() == Dimension::Currency>
+                <div class="rates-info">
+                    <span>
+                        {move || {
+                            let rates = rates.get();
+                            if rates.date.is_empty() {
+                                format!("Rates per 1 {}", rates.base)
+                            } else {
+                                format!("Rates per 1 {} as of {}", rates.base, rates.date)
+                            }
+                        }}
+                    </span>
+                    <label class="history-action">
+                        "Load rates..."
+                        <input
+                            type="file"
+                            accept=".json"
+                            style="display: none;"
+                            on:change=load_rates
+                        />
+                    </label>
+                    <span class="rates-message">{rates_message}</span>
+                </div>
+            </Show>
+        </div>
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rates() -> CurrencyRates {
+        CurrencyRates::from_json(r#"{"base": "usd", "rates": {"EUR": 0.5, "jpy": 150}}"#).unwrap()
+    }
+
+    fn show(value: &str, from: &str, to: &str) -> Result<String, ConversionError> {
+        let value = value.parse::<Decimal>().unwrap();
+        convert(&value, from, to, &rates(), &Context::default()).map(|result| result.to_string())
+    }
+
+    #[test]
+    fn temperatures_apply_offsets() {
+        for (value, from, to, expected) in [
+            ("212", "°F", "°C", "100"),
+            ("32", "°F", "°C", "0"),
+            ("98.6", "°F", "°C", "37"),
+            ("-40", "°C", "°F", "-40"),
+            ("100", "°C", "°F", "212"),
+            ("0", "°C", "K", "273.15"),
+            ("0", "K", "°C", "-273.15"),
+            ("0", "K", "°F", "-459.67"),
+            ("451", "°F", "K", "505.92777777777777777777777777778"),
+        ] {
+            assert_eq!(show(value, from, to).as_deref(), Ok(expected), "{} {} in {}", value, from, to);
+        }
+    }
+
+    #[test]
+    fn data_sizes() {
+        for (value, from, to, expected) in [
+            ("1", "B", "bit", "8"),
+            ("12", "bit", "B", "1.5"),
+            ("1", "KiB", "B", "1024"),
+            ("1", "MB", "bit", "8000000"),
+            ("1", "GiB", "GB", "1.073741824"),
+            ("1", "TB", "TiB", "0.9094947017729282379150390625"),
+        ] {
+            assert_eq!(show(value, from, to).as_deref(), Ok(expected), "{} {} in {}", value, from, to);
+        }
+    }
+
+    #[test]
+    fn currencies_go_through_the_base() {
+        assert_eq!(show("10", "USD", "EUR").as_deref(), Ok("5"));
+        assert_eq!(show("1", "EUR", "JPY").as_deref(), Ok("300"));
+        assert!(CurrencyRates::from_json(r#"{"base": "USD", "rates": {"EUR": 0}}"#).is_err());
+        assert!(CurrencyRates::from_json(r#"{"base": "USD", "rates": {"EUR": -1}}"#).is_err());
+    }
+
+    #[test]
+    fn mismatched_or_unknown_units_are_errors() {
+        assert_eq!(
+            show("1", "m", "kg"),
+            Err(ConversionError::Incompatible { from: Dimension::Length, to: Dimension::Mass })
+        );
+        assert_eq!(
+            show("1", "°C", "EUR"),
+            Err(ConversionError::Incompatible { from: Dimension::Temperature, to: Dimension::Currency })
+        );
+        assert_eq!(show("1", "m", "furlong"), Err(ConversionError::UnknownUnit("furlong".to_string())));
+    }
+
+    #[test]
+    fn broken_unit_definitions_are_errors() {
+        let invalid = Err(ConversionError::InvalidDefinition("x".to_string()));
+        assert_eq!(unit("x", "Zero", Dimension::Length, "0").resolve().map(|_| ()), invalid);
+        assert_eq!(unit("x", "Garbage", Dimension::Length, "1.2.3").resolve().map(|_| ()), invalid);
+        let no_divisor = Unit { divisor: "0", ..unit("x", "No divisor", Dimension::Length, "1") };
+        assert_eq!(no_divisor.resolve().map(|_| ()), invalid);
+        for unit in UNITS {
+            assert!(unit.resolve().is_ok(), "{}", unit.symbol);
+        }
+    }
+}
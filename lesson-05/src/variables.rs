@@ -0,0 +1,92 @@
+use serde::{Deserialize, Serialize};
+
+use crate::engine::{parse_statement, Environment, Statement};
+
+const STORAGE_KEY: &str = "lesson-05.environment";
+
+/// Values are stored as exact decimal strings and functions as the text
+/// that defined them, which is parsed again on load.
+#[derive(Default, Serialize, Deserialize)]
+struct SavedEnvironment {
+    variables: Vec<(String, String)>,
+    functions: Vec<String>,
+    #[serde(default)]
+    ans: Option<String>,
+}
+
+fn local_storage() -> Option<web_sys::Storage> {
+    leptos::prelude::window().local_storage().ok().flatten()
+}
+
+pub fn load() -> Environment {
+    local_storage()
+        .and_then(|storage| storage.get_item(STORAGE_KEY).ok().flatten())
+        .map(|json| from_json(&json))
+        .unwrap_or_default()
+}
+
+pub fn save(env: &Environment) {
+    if let (Some(storage), Some(json)) = (local_storage(), to_json(env)) {
+        let _ = storage.set_item(STORAGE_KEY, &json);
+    }
+}
+
+/// Reads a saved environment, skipping entries that no longer parse.
+fn from_json(json: &str) -> Environment {
+    let saved: SavedEnvironment = serde_json::from_str(json).unwrap_or_default();
+    let mut env = Environment::default();
+    for (name, value) in saved.variables {
+        if let Ok(value) = value.parse() {
+            env.variables.insert(name, value);
+        }
+    }
+    for source in saved.functions {
+        if let Ok(Statement::Define(name, function)) = parse_statement(&source) {
+            env.functions.insert(name, function);
+        }
+    }
+    env.ans = saved.ans.and_then(|ans| ans.parse().ok());
+    env
+}
+
+fn to_json(env: &Environment) -> Option<String> {
+    let saved = SavedEnvironment {
+        variables: env
+            .variables
+            .iter()
+            .map(|(name, value)| (name.clone(), value.to_string()))
+            .collect(),
+        functions: env.functions.values().map(|f| f.source.clone()).collect(),
+        ans: env.ans.as_ref().map(|ans| ans.to_string()),
+    };
+    serde_json::to_string(&saved).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::{run, Context};
+
+    #[test]
+    fn environment_survives_a_round_trip() {
+        let ctx = Context::default();
+        let mut env = Environment::default();
+        for line in ["let rate = 1/3", "let big = 1e40", "area(w, h) = w * h * rate", "area(3, 4)"] {
+            run(line, &mut env, &ctx).unwrap();
+        }
+        let mut restored = from_json(&to_json(&env).unwrap());
+        assert_eq!(restored, env);
+        let outcome = run("area(6, 1) + ans", &mut restored, &ctx).unwrap();
+        assert_eq!(outcome.value().map(|value| value.to_string()).as_deref(), Some("6"));
+    }
+
+    #[test]
+    fn broken_entries_are_skipped() {
+        let json = r#"{"variables": [["a", "2"], ["b", "two"]], "functions": ["f(x) = x +", "g(x) = x * a"], "ans": "nope"}"#;
+        let env = from_json(json);
+        assert_eq!(env.variables.keys().collect::<Vec<_>>(), ["a"]);
+        assert_eq!(env.functions.keys().collect::<Vec<_>>(), ["g"]);
+        assert_eq!(env.ans, None);
+        assert_eq!(from_json("not json"), Environment::default());
+    }
+}
@@ -0,0 +1,415 @@
+use std::collections::BTreeMap;
+
+use chrono::NaiveDate;
+use leptos::prelude::*;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::money::{Currency, Locale, Money, MoneyError};
+use crate::period::{add_months, month_start, this_month};
+use crate::rates::{self, ExchangeRate};
+use crate::splits;
+use crate::{Transaction, TransactionType};
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Budget {
+    pub id: String,
+    pub category: String,
+    /// Spending allowed each month, in the budget's own currency.
+    pub limit: Money,
+    /// Limits replaced by later edits, as the month the edit took effect and
+    /// the limit before it, oldest first. Carry-over into earlier months uses
+    /// these, so changing the limit does not rewrite past months.
+    #[serde(default)]
+    pub earlier_limits: Vec<(NaiveDate, Money)>,
+    /// Whether money left at the end of a month is added to the next.
+    pub carry_over: bool,
+    /// Percentage of the month's allowance at which the category is flagged.
+    pub alert_percent: u32,
+    /// First month the budget applies to; carry-over counts from here.
+    pub start: NaiveDate,
+}
+
+impl Budget {
+    /// The limit in effect in `month`.
+    pub fn limit_in(&self, month: NaiveDate) -> Money {
+        self.earlier_limits
+            .iter()
+            .find(|(until, _)| month < *until)
+            .map_or(self.limit, |(_, limit)| *limit)
+    }
+
+    /// Changes the limit from `month` onwards; earlier months keep theirs.
+    /// Limits in another currency cannot be compared, so a change of
+    /// currency applies to every month.
+    pub fn set_limit(&mut self, limit: Money, month: NaiveDate) {
+        let month = month_start(month);
+        if limit.currency != self.limit.currency {
+            self.earlier_limits.clear();
+            self.limit = limit;
+            return;
+        }
+        let before = self.limit_in(add_months(month, -1));
+        self.earlier_limits.retain(|(until, _)| *until <= month);
+        let covered = self.earlier_limits.iter().any(|(until, _)| *until == month);
+        if month > month_start(self.start) && before != limit && !covered {
+            self.earlier_limits.push((month, before));
+        }
+        self.limit = limit;
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Level {
+    Ok,
+    Warning,
+    Over,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct BudgetStatus {
+    pub budget: Budget,
+    pub spent: Money,
+    /// The monthly limit plus anything carried in.
+    pub available: Money,
+    pub carried: Money,
+}
+
+impl BudgetStatus {
+    pub fn percent(&self) -> f64 {
+        if self.available.minor <= 0 {
+            return if self.spent.minor > 0 { 100.0 } else { 0.0 };
+        }
+        self.spent.minor as f64 / self.available.minor as f64 * 100.0
+    }
+
+    pub fn level(&self) -> Level {
+        if self.spent.minor > self.available.minor {
+            Level::Over
+        } else if self.percent() >= self.budget.alert_percent as f64 {
+            Level::Warning
+        } else {
+            Level::Ok
+        }
+    }
+}
+
+/// Expenses in `category` per month, converted into `currency`, counting
+/// only the part of a split transaction assigned to it. Transactions without
+/// a rate into it are left out.
+fn monthly_spending(
+    category: &str,
+    currency: Currency,
+    transactions: &[Transaction],
+    rates: &[ExchangeRate],
+) -> Result<BTreeMap<NaiveDate, Money>, MoneyError> {
+    let mut months = BTreeMap::new();
+    for t in transactions
+        .iter()
+        .filter(|t| t.transaction_type == TransactionType::Expense)
+        .filter(|t| splits::categories(t).iter().any(|c| c.eq_ignore_ascii_case(category)))
+    {
+        let date = t.date.date_naive();
+        let amount = match rates::convert(rates, t.amount, currency, date) {
+            Ok((amount, _)) => amount,
+            Err(MoneyError::MissingRate(..)) => continue,
+            Err(err) => return Err(err),
+        };
+        let entry = months.entry(month_start(date)).or_insert(Money::zero(currency));
+        for (_, part) in splits::allocate(t, amount)?.into_iter().filter(|(c, _)| c.eq_ignore_ascii_case(category)) {
+            *entry = entry.checked_add(part)?;
+        }
+    }
+    Ok(months)
+}
+
+/// Where the budget stands in `month`. With carry-over, each earlier month
+/// from `start` passes on whatever it left unspent under the limit it had
+/// then; overspending is not carried.
+pub fn status(
+    budget: &Budget,
+    month: NaiveDate,
+    transactions: &[Transaction],
+    rates: &[ExchangeRate],
+) -> Result<BudgetStatus, MoneyError> {
+    let currency = budget.limit.currency;
+    let spending = monthly_spending(&budget.category, currency, transactions, rates)?;
+    let spent_in = |m: NaiveDate| spending.get(&m).copied().unwrap_or(Money::zero(currency));
+
+    let mut carried = Money::zero(currency);
+    if budget.carry_over {
+        let mut m = month_start(budget.start);
+        while m < month {
+            let left = budget.limit_in(m).checked_add(carried)?.checked_sub(spent_in(m))?;
+            carried = if left.is_negative() { Money::zero(currency) } else { left };
+            m = add_months(m, 1);
+        }
+    }
+    Ok(BudgetStatus {
+        budget: budget.clone(),
+        spent: spent_in(month),
+        available: budget.limit_in(month).checked_add(carried)?,
+        carried,
+    })
+}
+
+#[component]
+pub fn Budgets(
+    budgets: ReadSignal<Vec<Budget>>,
+    set_budgets: WriteSignal<Vec<Budget>>,
+    transactions: ReadSignal<Vec<Transaction>>,
+    rates: ReadSignal<Vec<ExchangeRate>>,
+    #[prop(into)] default_currency: Signal<Currency>,
+    locale: Locale,
+) -> impl IntoView {
+    let (month, set_month) = signal(this_month());
+    let (category, set_category) = signal(String::new());
+    let (limit_input, set_limit_input) = signal(String::new());
+    let (carry_over, set_carry_over) = signal(false);
+    let (alert_percent, set_alert_percent) = signal(80u32);
+    let (error, set_error) = signal::<Option<String>>(None);
+
+    let statuses = move || {
+        let month = month.get();
+        let mut sorted = budgets.get();
+        sorted.sort_by_key(|budget| budget.category.to_lowercase());
+        transactions.with(|transactions| {
+            rates.with(|rates| {
+                sorted
+                    .iter()
+                    .filter(|budget| month_start(budget.start) <= month)
+                    .map(|budget| (budget.clone(), status(budget, month, transactions, rates)))
+                    .collect::<Vec<_>>()
+            })
+        })
+    };
+
+    // Setting a budget for a category that already has one changes its
+    // limit from the month shown onwards and replaces its options, but
+    // keeps its start month.
+    let save = move |_| {
+        let name = category.get().trim().to_string();
+        if name.is_empty() {
+            set_error.set(Some("Enter a category".to_string()));
+            return;
+        }
+        let limit = match Money::parse(&limit_input.get(), default_currency.get(), locale) {
+            Ok(limit) if limit.is_positive() => limit,
+            Ok(_) => {
+                set_error.set(Some("The budget must be greater than zero".to_string()));
+                return;
+            }
+            Err(err) => {
+                set_error.set(Some(err.to_string()));
+                return;
+            }
+        };
+        set_budgets.update(|budgets| {
+            match budgets.iter_mut().find(|b| b.category.eq_ignore_ascii_case(&name)) {
+                Some(budget) => {
+                    budget.set_limit(limit, month.get());
+                    budget.carry_over = carry_over.get();
+                    budget.alert_percent = alert_percent.get();
+                }
+                None => budgets.push(Budget {
+                    id: Uuid::new_v4().to_string(),
+                    category: name,
+                    limit,
+                    earlier_limits: Vec::new(),
+                    carry_over: carry_over.get(),
+                    alert_percent: alert_percent.get(),
+                    start: month.get(),
+                }),
+            }
+        });
+        set_category.set(String::new());
+        set_limit_input.set(String::new());
+        set_error.set(None);
+    };
+
+    let edit = move |budget: Budget| {
+        set_limit_input.set(budget.limit_in(month.get_untracked()).format_number(locale));
+        set_category.set(budget.category);
+        set_carry_over.set(budget.carry_over);
+        set_alert_percent.set(budget.alert_percent);
+    };
+
+    view! {
+        <div class="budgets-container">
+            <div class="budgets-header">
+                <button class="edit-btn" on:click=move |_| set_month.update(|m| *m = add_months(*m, -1))>"‹"</button>
+                <h3>{move || format!("Budgets for {}", month.get().format("%B %Y"))}</h3>
+                <button class="edit-btn" on:click=move |_| set_month.update(|m| *m = add_months(*m, 1))>"›"</button>
+            </div>
+
+            <div class="budget-list">
+                {move || statuses().into_iter().map(|(budget, status)| {
+                    let budget_for_edit = budget.clone();
+                    let id = budget.id.clone();
+                    let body = match status {
+                        Ok(status) => {
+                            let level = status.level();
+                            let carried = (status.carried.is_positive())
+                                .then(|| format!(" (incl. {} carried over)", status.carried.format(locale)));
+                            view! {
+                                <div
+                                    class="budget-bar"
+                                    class:warning=level == Level::Warning
+                                    class:over=level == Level::Over
+                                    role="progressbar"
+                                    aria-valuemin="0"
+                                    aria-valuemax="100"
+                                    aria-valuenow=format!("{:.0}", status.percent())
+                                >
+                                    <div class="budget-fill" style=format!("width: {:.1}%", status.percent().min(100.0))></div>
+                                </div>
+                                <div class="budget-figures">
+                                    {format!(
+                                        "{} of {}{} · {:.0}%",
+                                        status.spent.format(locale),
+                                        status.available.format(locale),
+                                        carried.unwrap_or_default(),
+                                        status.percent(),
+                                    )}
+                                </div>
+                            }.into_any()
+                        }
+                        Err(err) => view! { <div class="budget-figures">{err.to_string()}</div> }.into_any(),
+                    };
+                    view! {
+                        <div class="budget-item">
+                            <div class="budget-title">
+                                <strong>{budget.category.clone()}</strong>
+                                <span class="budget-actions">
+                                    <button class="edit-btn" on:click=move |_| edit(budget_for_edit.clone())>"Edit"</button>
+                                    <button
+                                        class="delete-btn"
+                                        on:click=move |_| set_budgets.update(|budgets| budgets.retain(|b| b.id != id))
+                                    >
+                                        "Delete"
+                                    </button>
+                                </span>
+                            </div>
+                            {body}
+                        </div>
+                    }
+                }).collect_view()}
+            </div>
+
+            <div class="budget-form">
+                <input
+                    type="text"
+                    placeholder="Category"
+                    list="categories"
+                    prop:value=category
+                    on:input=move |ev| set_category.set(event_target_value(&ev))
+                />
+                <input
+                    type="text"
+                    inputmode="decimal"
+                    placeholder=move || format!("Monthly {}", default_currency.get().code)
+                    prop:value=limit_input
+                    on:input=move |ev| set_limit_input.set(event_target_value(&ev))
+                />
+                <label title="Flag the category once this share of the budget is spent">
+                    "Alert at"
+                    <input
+                        type="number"
+                        min="1"
+                        max="100"
+                        prop:value=move || alert_percent.get().to_string()
+                        on:input=move |ev| {
+                            if let Ok(percent) = event_target_value(&ev).parse::<u32>() {
+                                set_alert_percent.set(percent.clamp(1, 100));
+                            }
+                        }
+                    />
+                    "%"
+                </label>
+                <label>
+                    <input
+                        type="checkbox"
+                        prop:checked=carry_over
+                        on:change=move |ev| set_carry_over.set(event_target_checked(&ev))
+                    />
+                    "Carry over unspent"
+                </label>
+                <button class="submit-btn" on:click=save>"Set Budget"</button>
+            </div>
+            {move || error.get().map(|message| view! { <p class="form-error">{message}</p> })}
+        </div>
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{Local, TimeZone};
+
+    use super::*;
+    use crate::money::USD;
+
+    fn month(m: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(2024, m, 1).unwrap()
+    }
+
+    fn usd(dollars: i64) -> Money {
+        Money::from_minor(dollars * 100, USD)
+    }
+
+    fn budget(limit: i64) -> Budget {
+        Budget {
+            id: "b".to_string(),
+            category: "Food".to_string(),
+            limit: usd(limit),
+            earlier_limits: Vec::new(),
+            carry_over: true,
+            alert_percent: 80,
+            start: month(1),
+        }
+    }
+
+    fn expense(m: u32, dollars: i64) -> Transaction {
+        Transaction {
+            amount: usd(dollars),
+            category: "Food".to_string(),
+            date: Local.with_ymd_and_hms(2024, m, 10, 12, 0, 0).unwrap(),
+            transaction_type: TransactionType::Expense,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn carries_unspent_money_forward() {
+        let transactions = [expense(1, 60), expense(2, 150), expense(3, 10)];
+        let february = status(&budget(100), month(2), &transactions, &[]).unwrap();
+        assert_eq!(february.carried, usd(40));
+        assert_eq!(february.available, usd(140));
+        // February overspends, which is not carried into March.
+        let march = status(&budget(100), month(3), &transactions, &[]).unwrap();
+        assert_eq!(march.carried, usd(0));
+        assert_eq!(march.spent, usd(10));
+    }
+
+    #[test]
+    fn changing_the_limit_leaves_earlier_months_alone() {
+        let transactions = [expense(1, 60), expense(2, 60), expense(3, 60)];
+        let mut b = budget(100);
+        b.set_limit(usd(200), month(3));
+        assert_eq!(b.limit_in(month(1)), usd(100));
+        assert_eq!(b.limit_in(month(2)), usd(100));
+        assert_eq!(b.limit_in(month(3)), usd(200));
+        let march = status(&b, month(3), &transactions, &[]).unwrap();
+        assert_eq!(march.carried, usd(80));
+        assert_eq!(march.available, usd(280));
+
+        // A second change from an earlier month replaces the later one.
+        b.set_limit(usd(50), month(2));
+        assert_eq!(b.earlier_limits, vec![(month(2), usd(100))]);
+        assert_eq!(b.limit_in(month(3)), usd(50));
+
+        // Changing it back from the start month leaves no history.
+        b.set_limit(usd(100), month(1));
+        assert!(b.earlier_limits.is_empty());
+        assert_eq!(b.limit_in(month(2)), usd(100));
+    }
+}
@@ -0,0 +1,593 @@
+use std::f64::consts::{FRAC_PI_2, TAU};
+
+use leptos::prelude::*;
+
+use crate::money::{Currency, Locale, Money, MoneyError};
+use crate::period::{self, DateRange, MonthSummary};
+use crate::splits;
+use crate::{Transaction, TransactionType};
+
+const WIDTH: f64 = 400.0;
+const HEIGHT: f64 = 240.0;
+const TOP: f64 = 20.0;
+/// Room under the plot for the axis labels.
+const BOTTOM: f64 = 30.0;
+const SIDE: f64 = 10.0;
+const PLOT_HEIGHT: f64 = HEIGHT - TOP - BOTTOM;
+const PLOT_WIDTH: f64 = WIDTH - 2.0 * SIDE;
+
+/// Bars shown before the rest are left to the data table.
+const MAX_BARS: usize = 8;
+
+const PIE_RADIUS: f64 = 90.0;
+const PIE_CENTER: f64 = 100.0;
+
+/// Slice colours; categories beyond the last one are grouped as "Other".
+const PALETTE: [&str; 8] = [
+    "#4f46e5", "#ef4444", "#f59e0b", "#10b981", "#0ea5e9", "#ec4899", "#8b5cf6", "#94a3b8",
+];
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct CategoryTotal {
+    pub category: String,
+    pub income: Money,
+    pub expenses: Money,
+}
+
+/// Income and expenses per category within `range`, kept apart and sorted
+/// by the larger of the two. `amounts` are already converted into
+/// `currency`; a split transaction counts towards each of its categories.
+pub fn category_totals(
+    amounts: &[(Transaction, Money)],
+    range: DateRange,
+    currency: Currency,
+) -> Result<Vec<CategoryTotal>, MoneyError> {
+    let mut totals: Vec<CategoryTotal> = Vec::new();
+    for (t, amount) in amounts.iter().filter(|(t, _)| range.contains(t.date.date_naive())) {
+        for (category, amount) in splits::allocate(t, *amount)? {
+            let index = match totals.iter().position(|c| c.category == category) {
+                Some(index) => index,
+                None => {
+                    totals.push(CategoryTotal {
+                        category,
+                        income: Money::zero(currency),
+                        expenses: Money::zero(currency),
+                    });
+                    totals.len() - 1
+                }
+            };
+            let total = &mut totals[index];
+            match t.transaction_type {
+                TransactionType::Income => total.income = total.income.checked_add(amount)?,
+                TransactionType::Expense => total.expenses = total.expenses.checked_add(amount)?,
+            }
+        }
+    }
+    totals.sort_by(|a, b| {
+        let size = |c: &CategoryTotal| c.income.minor.max(c.expenses.minor);
+        size(b).cmp(&size(a)).then_with(|| a.category.cmp(&b.category))
+    });
+    Ok(totals)
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Slice {
+    pub category: String,
+    pub amount: Money,
+    /// Fraction of all spending, from 0 to 1.
+    pub share: f64,
+    pub color: &'static str,
+}
+
+/// Spending per category as pie slices, largest first. Only as many
+/// categories as there are colours get their own slice.
+pub fn pie_slices(totals: &[CategoryTotal]) -> Result<Vec<Slice>, MoneyError> {
+    let mut spending: Vec<_> = totals.iter().filter(|c| c.expenses.is_positive()).collect();
+    spending.sort_by_key(|c| std::cmp::Reverse(c.expenses.minor));
+    let Some(first) = spending.first() else {
+        return Ok(Vec::new());
+    };
+    let currency = first.expenses.currency;
+    let total = Money::sum(currency, spending.iter().map(|c| c.expenses))?;
+
+    let mut parts: Vec<(String, Money)> = Vec::new();
+    for (i, c) in spending.iter().enumerate() {
+        if i < PALETTE.len() - 1 || spending.len() == PALETTE.len() {
+            parts.push((c.category.clone(), c.expenses));
+        } else if i == PALETTE.len() - 1 {
+            parts.push(("Other".to_string(), c.expenses));
+        } else if let Some((_, other)) = parts.last_mut() {
+            *other = other.checked_add(c.expenses)?;
+        }
+    }
+    Ok(parts
+        .into_iter()
+        .zip(PALETTE)
+        .map(|((category, amount), color)| Slice {
+            category,
+            amount,
+            share: amount.minor as f64 / total.minor as f64,
+            color,
+        })
+        .collect())
+}
+
+/// Height in chart units of `amount` against the largest value `max`.
+fn scale(amount: Money, max: i64) -> f64 {
+    if max <= 0 {
+        0.0
+    } else {
+        amount.minor.max(0) as f64 / max as f64 * PLOT_HEIGHT
+    }
+}
+
+/// The pie wedge between two points of the turn, measured clockwise from
+/// the top.
+fn wedge_path(start: f64, end: f64) -> String {
+    let point = |turn: f64| {
+        let angle = turn * TAU - FRAC_PI_2;
+        (PIE_CENTER + PIE_RADIUS * angle.cos(), PIE_CENTER + PIE_RADIUS * angle.sin())
+    };
+    let (x0, y0) = point(start);
+    let (x1, y1) = point(end);
+    let large_arc = if end - start > 0.5 { 1 } else { 0 };
+    format!(
+        "M {c} {c} L {x0:.2} {y0:.2} A {r} {r} 0 {large_arc} 1 {x1:.2} {y1:.2} Z",
+        c = PIE_CENTER,
+        r = PIE_RADIUS,
+    )
+}
+
+fn short_label(text: &str) -> String {
+    if text.chars().count() > 10 {
+        format!("{}…", text.chars().take(9).collect::<String>())
+    } else {
+        text.to_string()
+    }
+}
+
+/// Wraps a chart mark so hovering or focusing it shows `text` in the
+/// chart's tooltip.
+fn mark(text: String, set_tip: WriteSignal<Option<String>>, shape: impl IntoView + 'static) -> impl IntoView {
+    let (hovered, focused) = (text.clone(), text.clone());
+    view! {
+        <g
+            class="chart-mark"
+            tabindex="0"
+            aria-label=text
+            on:mouseenter=move |_| set_tip.set(Some(hovered.clone()))
+            on:mouseleave=move |_| set_tip.set(None)
+            on:focus=move |_| set_tip.set(Some(focused.clone()))
+            on:blur=move |_| set_tip.set(None)
+        >
+            {shape}
+        </g>
+    }
+}
+
+fn tooltip(tip: ReadSignal<Option<String>>, hint: &'static str) -> impl IntoView {
+    view! {
+        <p class="chart-tooltip" aria-live="polite">
+            {move || tip.get().unwrap_or_else(|| hint.to_string())}
+        </p>
+    }
+}
+
+fn empty(message: String) -> AnyView {
+    view! { <p class="chart-empty">{message}</p> }.into_any()
+}
+
+/// Horizontal guide lines at zero, half and the top value.
+fn grid(max: Money, locale: Locale) -> impl IntoView {
+    let half = Money::from_minor(max.minor / 2, max.currency);
+    [(0.0, None), (0.5, Some(half)), (1.0, Some(max))]
+        .into_iter()
+        .map(|(at, label)| {
+            let y = TOP + PLOT_HEIGHT * (1.0 - at);
+            view! {
+                <line class="chart-grid" x1=SIDE x2=WIDTH - SIDE y1=y y2=y />
+                {label.map(|label| view! {
+                    <text class="chart-axis" x=SIDE y=y - 4.0>{label.format(locale)}</text>
+                })}
+            }
+        })
+        .collect_view()
+}
+
+#[component]
+pub fn CategoryChart(
+    #[prop(into)] totals: Signal<Result<Vec<CategoryTotal>, MoneyError>>,
+    locale: Locale,
+) -> impl IntoView {
+    let (tip, set_tip) = signal::<Option<String>>(None);
+
+    let chart = move || {
+        let totals = match totals.get() {
+            Ok(totals) if totals.is_empty() => return empty("No transactions in this period".to_string()),
+            Ok(totals) => totals,
+            Err(err) => return empty(err.to_string()),
+        };
+        let shown = &totals[..totals.len().min(MAX_BARS)];
+        let max = shown.iter().map(|c| c.income.minor.max(c.expenses.minor)).max().unwrap_or(0);
+        let currency = shown[0].income.currency;
+        let group = PLOT_WIDTH / shown.len() as f64;
+        let bar = group * 0.35;
+
+        let bars = shown
+            .iter()
+            .enumerate()
+            .map(|(i, c)| {
+                let x = SIDE + group * i as f64 + group * 0.15;
+                let income_height = scale(c.income, max);
+                let expense_height = scale(c.expenses, max);
+                view! {
+                    {mark(
+                        format!("{}: income {}", c.category, c.income.format(locale)),
+                        set_tip,
+                        view! {
+                            <rect class="bar income" x=x y=TOP + PLOT_HEIGHT - income_height width=bar height=income_height />
+                        },
+                    )}
+                    {mark(
+                        format!("{}: expenses {}", c.category, c.expenses.format(locale)),
+                        set_tip,
+                        view! {
+                            <rect class="bar expense" x=x + bar y=TOP + PLOT_HEIGHT - expense_height width=bar height=expense_height />
+                        },
+                    )}
+                    <text class="chart-label" x=x + bar y=HEIGHT - BOTTOM + 16.0 text-anchor="middle">
+                        {short_label(&c.category)}
+                    </text>
+                }
+            })
+            .collect_view();
+
+        let rows = totals
+            .iter()
+            .map(|c| {
+                view! {
+                    <tr>
+                        <th scope="row">{c.category.clone()}</th>
+                        <td class="income">{c.income.format(locale)}</td>
+                        <td class="expense">{c.expenses.format(locale)}</td>
+                    </tr>
+                }
+            })
+            .collect_view();
+
+        view! {
+            <svg
+                class="chart"
+                viewBox=format!("0 0 {WIDTH} {HEIGHT}")
+                role="img"
+                aria-label="Income and expenses by category; the data table below lists every value"
+            >
+                {grid(Money::from_minor(max, currency), locale)}
+                {bars}
+            </svg>
+            <details class="chart-data">
+                <summary>"Data table"</summary>
+                <table>
+                    <thead>
+                        <tr>
+                            <th scope="col">"Category"</th>
+                            <th scope="col">"Income"</th>
+                            <th scope="col">"Expenses"</th>
+                        </tr>
+                    </thead>
+                    <tbody>{rows}</tbody>
+                </table>
+            </details>
+        }
+        .into_any()
+    };
+
+    view! {
+        <div class="chart-container">
+            <h3>"Income and Expenses by Category"</h3>
+            <div class="chart-legend">
+                <span class="swatch income"></span>"Income"
+                <span class="swatch expense"></span>"Expenses"
+            </div>
+            {chart}
+            {tooltip(tip, "Hover over or tab to a bar for its amount")}
+        </div>
+    }
+}
+
+#[component]
+pub fn SpendingOverTime(
+    #[prop(into)] months: Signal<Result<Vec<MonthSummary>, MoneyError>>,
+    locale: Locale,
+) -> impl IntoView {
+    let (tip, set_tip) = signal::<Option<String>>(None);
+
+    let chart = move || {
+        let months = match months.get() {
+            Ok(months) if months.is_empty() => return empty("No transactions in this period".to_string()),
+            Ok(months) => months,
+            Err(err) => return empty(err.to_string()),
+        };
+        let months = period::latest_months(&months);
+        let max = months.iter().map(|m| m.income.minor.max(m.expenses.minor)).max().unwrap_or(0);
+        let currency = months[0].income.currency;
+        let step = if months.len() > 1 { PLOT_WIDTH / (months.len() - 1) as f64 } else { 0.0 };
+        let x = |i: usize| if months.len() > 1 { SIDE + step * i as f64 } else { WIDTH / 2.0 };
+        let y = |amount: Money| TOP + PLOT_HEIGHT - scale(amount, max);
+        let line = |amount: fn(&MonthSummary) -> Money| {
+            months
+                .iter()
+                .enumerate()
+                .map(|(i, m)| format!("{:.2},{:.2}", x(i), y(amount(m))))
+                .collect::<Vec<_>>()
+                .join(" ")
+        };
+
+        let points = months
+            .iter()
+            .enumerate()
+            .map(|(i, m)| {
+                let month = m.month.format("%b %Y").to_string();
+                view! {
+                    {mark(
+                        format!("{}: income {}", month, m.income.format(locale)),
+                        set_tip,
+                        view! { <circle class="point income" cx=x(i) cy=y(m.income) r="4" /> },
+                    )}
+                    {mark(
+                        format!("{}: spending {}", month, m.expenses.format(locale)),
+                        set_tip,
+                        view! { <circle class="point expense" cx=x(i) cy=y(m.expenses) r="4" /> },
+                    )}
+                    <text class="chart-label" x=x(i) y=HEIGHT - BOTTOM + 16.0 text-anchor="middle">
+                        {m.month.format("%b").to_string()}
+                    </text>
+                }
+            })
+            .collect_view();
+
+        let rows = months
+            .iter()
+            .map(|m| {
+                view! {
+                    <tr>
+                        <th scope="row">{m.month.format("%B %Y").to_string()}</th>
+                        <td class="income">{m.income.format(locale)}</td>
+                        <td class="expense">{m.expenses.format(locale)}</td>
+                    </tr>
+                }
+            })
+            .collect_view();
+
+        view! {
+            <svg
+                class="chart"
+                viewBox=format!("0 0 {WIDTH} {HEIGHT}")
+                role="img"
+                aria-label="Monthly income and spending; the data table below lists every value"
+            >
+                {grid(Money::from_minor(max, currency), locale)}
+                <polyline class="line income" points=line(|m| m.income) />
+                <polyline class="line expense" points=line(|m| m.expenses) />
+                {points}
+            </svg>
+            <details class="chart-data">
+                <summary>"Data table"</summary>
+                <table>
+                    <thead>
+                        <tr>
+                            <th scope="col">"Month"</th>
+                            <th scope="col">"Income"</th>
+                            <th scope="col">"Spending"</th>
+                        </tr>
+                    </thead>
+                    <tbody>{rows}</tbody>
+                </table>
+            </details>
+        }
+        .into_any()
+    };
+
+    view! {
+        <div class="chart-container">
+            <h3>"Spending over Time"</h3>
+            <div class="chart-legend">
+                <span class="swatch expense"></span>"Spending"
+                <span class="swatch income"></span>"Income"
+            </div>
+            {chart}
+            {tooltip(tip, "Hover over or tab to a point for its amount")}
+        </div>
+    }
+}
+
+#[component]
+pub fn CategoryPie(
+    #[prop(into)] totals: Signal<Result<Vec<CategoryTotal>, MoneyError>>,
+    locale: Locale,
+) -> impl IntoView {
+    let (tip, set_tip) = signal::<Option<String>>(None);
+
+    let chart = move || {
+        let slices = match totals.get().and_then(|totals| pie_slices(&totals)) {
+            Ok(slices) if slices.is_empty() => return empty("No spending in this period".to_string()),
+            Ok(slices) => slices,
+            Err(err) => return empty(err.to_string()),
+        };
+
+        let mut start = 0.0;
+        let wedges = slices
+            .iter()
+            .map(|slice| {
+                let text = format!("{}: {} ({:.1}%)", slice.category, slice.amount.format(locale), slice.share * 100.0);
+                let end = start + slice.share;
+                // A single slice is the whole circle, which an arc cannot draw.
+                let shape = if slice.share >= 1.0 {
+                    view! { <circle cx=PIE_CENTER cy=PIE_CENTER r=PIE_RADIUS fill=slice.color /> }.into_any()
+                } else {
+                    view! { <path d=wedge_path(start, end) fill=slice.color /> }.into_any()
+                };
+                start = end;
+                mark(text, set_tip, shape)
+            })
+            .collect_view();
+
+        let legend = slices
+            .iter()
+            .map(|slice| {
+                view! {
+                    <li>
+                        <span class="swatch" style=format!("background: {}", slice.color)></span>
+                        {slice.category.clone()}
+                    </li>
+                }
+            })
+            .collect_view();
+
+        let rows = slices
+            .iter()
+            .map(|slice| {
+                view! {
+                    <tr>
+                        <th scope="row">{slice.category.clone()}</th>
+                        <td>{slice.amount.format(locale)}</td>
+                        <td>{format!("{:.1}%", slice.share * 100.0)}</td>
+                    </tr>
+                }
+            })
+            .collect_view();
+
+        view! {
+            <div class="pie">
+                <svg
+                    class="chart"
+                    viewBox=format!("0 0 {0} {0}", PIE_CENTER * 2.0)
+                    role="img"
+                    aria-label="Share of spending by category; the data table below lists every value"
+                >
+                    {wedges}
+                </svg>
+                <ul class="pie-legend">{legend}</ul>
+            </div>
+            <details class="chart-data">
+                <summary>"Data table"</summary>
+                <table>
+                    <thead>
+                        <tr>
+                            <th scope="col">"Category"</th>
+                            <th scope="col">"Spent"</th>
+                            <th scope="col">"Share"</th>
+                        </tr>
+                    </thead>
+                    <tbody>{rows}</tbody>
+                </table>
+            </details>
+        }
+        .into_any()
+    };
+
+    view! {
+        <div class="chart-container">
+            <h3>"Spending by Category"</h3>
+            {chart}
+            {tooltip(tip, "Hover over or tab to a slice for its share")}
+        </div>
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{Local, NaiveDate, TimeZone};
+
+    use super::*;
+    use crate::money::USD;
+    use crate::splits::Split;
+
+    fn usd(dollars: i64) -> Money {
+        Money::from_minor(dollars * 100, USD)
+    }
+
+    fn transaction(category: &str, dollars: i64, kind: TransactionType, day: u32) -> Transaction {
+        Transaction {
+            amount: usd(dollars),
+            category: category.to_string(),
+            date: Local.with_ymd_and_hms(2024, 5, day, 12, 0, 0).unwrap(),
+            transaction_type: kind,
+            ..Default::default()
+        }
+    }
+
+    fn total(category: &str, income: i64, expenses: i64) -> CategoryTotal {
+        CategoryTotal {
+            category: category.to_string(),
+            income: usd(income),
+            expenses: usd(expenses),
+        }
+    }
+
+    #[test]
+    fn category_totals_split_and_sort_by_size() {
+        let mut groceries = transaction("Food", 100, TransactionType::Expense, 3);
+        groceries.splits = vec![
+            Split { category: "Food".to_string(), amount: usd(70) },
+            Split { category: "Home".to_string(), amount: usd(30) },
+        ];
+        let amounts: Vec<_> = [
+            groceries,
+            transaction("Salary", 500, TransactionType::Income, 1),
+            transaction("Home", 20, TransactionType::Expense, 20),
+            transaction("Fun", 50, TransactionType::Expense, 28),
+        ]
+        .into_iter()
+        .map(|t| {
+            let amount = t.amount;
+            (t, amount)
+        })
+        .collect();
+
+        let totals = category_totals(&amounts, DateRange::default(), USD).unwrap();
+        assert_eq!(
+            totals,
+            vec![total("Salary", 500, 0), total("Food", 0, 70), total("Fun", 0, 50), total("Home", 0, 50)]
+        );
+
+        let early_may = DateRange {
+            from: NaiveDate::from_ymd_opt(2024, 5, 2),
+            to: NaiveDate::from_ymd_opt(2024, 5, 25),
+        };
+        let totals = category_totals(&amounts, early_may, USD).unwrap();
+        assert_eq!(totals, vec![total("Food", 0, 70), total("Home", 0, 50)]);
+    }
+
+    #[test]
+    fn a_single_category_is_the_whole_pie() {
+        let slices = pie_slices(&[total("Salary", 900, 0), total("Rent", 0, 800)]).unwrap();
+        assert_eq!(slices.len(), 1);
+        assert_eq!(slices[0].category, "Rent");
+        assert_eq!(slices[0].share, 1.0);
+        assert!(pie_slices(&[total("Salary", 900, 0)]).unwrap().is_empty());
+    }
+
+    #[test]
+    fn pie_slices_group_the_smallest_categories() {
+        let totals: Vec<_> = (1..=10).map(|i| total(&format!("C{i}"), 0, i)).collect();
+        let slices = pie_slices(&totals).unwrap();
+        assert_eq!(slices.len(), PALETTE.len());
+        assert_eq!(slices[0].category, "C10");
+        let other = slices.last().unwrap();
+        assert_eq!(other.category, "Other");
+        assert_eq!(other.amount, usd(1 + 2 + 3));
+        assert!((slices.iter().map(|s| s.share).sum::<f64>() - 1.0).abs() < 1e-9);
+
+        // With exactly one category per colour nothing is grouped.
+        let slices = pie_slices(&totals[2..]).unwrap();
+        assert_eq!(slices.last().unwrap().category, "C3");
+    }
+
+    #[test]
+    fn wedges_run_clockwise_from_the_top() {
+        assert_eq!(wedge_path(0.0, 0.25), "M 100 100 L 100.00 10.00 A 90 90 0 0 1 190.00 100.00 Z");
+        assert_eq!(wedge_path(0.25, 1.0), "M 100 100 L 190.00 100.00 A 90 90 0 1 1 100.00 10.00 Z");
+        assert_eq!(wedge_path(0.5, 0.75), "M 100 100 L 100.00 190.00 A 90 90 0 0 1 10.00 100.00 Z");
+    }
+}
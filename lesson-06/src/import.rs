@@ -0,0 +1,762 @@
+use chrono::NaiveDate;
+use leptos::prelude::*;
+use uuid::Uuid;
+use wasm_bindgen_futures::JsFuture;
+
+use crate::money::{Currency, Locale, Money, USD};
+use crate::period::local_date;
+use crate::rates::currency_options;
+use crate::rules::{Rule, RuleSet};
+use crate::{Transaction, TransactionType};
+
+pub const UNCATEGORIZED: &str = "Uncategorized";
+
+static DATE_FORMATS: [(&str, &str); 5] = [
+    ("%Y-%m-%d", "YYYY-MM-DD"),
+    ("%m/%d/%Y", "MM/DD/YYYY"),
+    ("%d/%m/%Y", "DD/MM/YYYY"),
+    ("%d.%m.%Y", "DD.MM.YYYY"),
+    ("%Y%m%d", "YYYYMMDD"),
+];
+
+/// Fallback categories for descriptions that have not been seen before.
+const KEYWORDS: [(&str, &str); 12] = [
+    ("salary", "Salary"),
+    ("payroll", "Salary"),
+    ("grocer", "Food"),
+    ("supermarket", "Food"),
+    ("coffee", "Food"),
+    ("restaurant", "Food"),
+    ("internet", "Utilities"),
+    ("electric", "Utilities"),
+    ("water", "Utilities"),
+    ("uber", "Transport"),
+    ("fuel", "Transport"),
+    ("rent", "Housing"),
+];
+
+/// A statement line before it becomes a `Transaction`. `amount` is signed:
+/// money out is negative.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Draft {
+    pub date: NaiveDate,
+    pub description: String,
+    pub amount: Money,
+    /// The bank's own id for the line (OFX `FITID`), when it has one.
+    pub external_id: Option<String>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Status {
+    New,
+    Duplicate,
+    Invalid(String),
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct PreviewRow {
+    pub line: usize,
+    pub draft: Option<Draft>,
+    pub category: String,
+    pub status: Status,
+}
+
+/// Splits CSV text into records, honouring quoted fields. The delimiter is
+/// whichever of `,`, `;` or tab is most common on the first line.
+pub fn parse_csv(text: &str) -> Vec<Vec<String>> {
+    let first_line = text.lines().next().unwrap_or("");
+    let delimiter = [',', ';', '\t']
+        .into_iter()
+        .max_by_key(|d| first_line.matches(*d).count())
+        .unwrap_or(',');
+
+    let mut records = Vec::new();
+    let mut record = Vec::new();
+    let mut field = String::new();
+    let mut quoted = false;
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if quoted && chars.peek() == Some(&'"') => {
+                field.push('"');
+                chars.next();
+            }
+            '"' => quoted = !quoted,
+            c if c == delimiter && !quoted => record.push(std::mem::take(&mut field)),
+            '\r' if !quoted => {}
+            '\n' if !quoted => {
+                record.push(std::mem::take(&mut field));
+                records.push(std::mem::take(&mut record));
+            }
+            c => field.push(c),
+        }
+    }
+    if !field.is_empty() || !record.is_empty() {
+        record.push(field);
+        records.push(record);
+    }
+    records.retain(|r| r.iter().any(|f| !f.trim().is_empty()));
+    records
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct CsvMapping {
+    pub has_header: bool,
+    pub date: Option<usize>,
+    pub description: Option<usize>,
+    /// A single signed amount column...
+    pub amount: Option<usize>,
+    /// ...or separate money-out and money-in columns.
+    pub debit: Option<usize>,
+    pub credit: Option<usize>,
+    pub category: Option<usize>,
+    pub date_format: &'static str,
+    pub currency: Currency,
+}
+
+impl CsvMapping {
+    /// Guesses columns from header names, e.g. `Posting Date` or `Payee`.
+    pub fn guess(header: &[String], currency: Currency) -> CsvMapping {
+        let find = |keys: &[&str]| {
+            header
+                .iter()
+                .position(|name| keys.iter().any(|key| name.to_lowercase().contains(key)))
+        };
+        let mapping = CsvMapping {
+            has_header: true,
+            date: find(&["date"]),
+            description: find(&["desc", "payee", "name", "memo", "details"]),
+            amount: find(&["amount"]),
+            debit: find(&["debit", "withdrawal", "paid out"]),
+            credit: find(&["credit", "deposit", "paid in"]),
+            category: find(&["category"]),
+            date_format: DATE_FORMATS[0].0,
+            currency,
+        };
+        if mapping.date.is_none() && mapping.description.is_none() {
+            CsvMapping {
+                has_header: false,
+                date: Some(0),
+                description: Some(1),
+                amount: Some(2),
+                ..mapping
+            }
+        } else {
+            mapping
+        }
+    }
+
+    fn draft(&self, record: &[String], locale: Locale) -> Result<(Draft, Option<String>), String> {
+        let cell = |column: Option<usize>| column.and_then(|i| record.get(i)).map(|s| s.trim()).unwrap_or("");
+        let amount = |column: Option<usize>| -> Result<Option<Money>, String> {
+            match cell(column) {
+                "" => Ok(None),
+                text => Money::parse(text, self.currency, locale)
+                    .map(Some)
+                    .map_err(|err| format!("{}: {:?}", err, text)),
+            }
+        };
+
+        let date = NaiveDate::parse_from_str(cell(self.date), self.date_format)
+            .map_err(|_| format!("Unreadable date {:?}", cell(self.date)))?;
+        let description = cell(self.description).to_string();
+        if description.is_empty() {
+            return Err("Missing description".to_string());
+        }
+        let amount = match (amount(self.amount)?, amount(self.debit)?, amount(self.credit)?) {
+            (Some(amount), _, _) => amount,
+            (None, Some(debit), _) if debit.minor != 0 => Money::from_minor(-debit.minor.abs(), self.currency),
+            (None, _, Some(credit)) => Money::from_minor(credit.minor.abs(), self.currency),
+            _ => return Err("Missing amount".to_string()),
+        };
+        let category = Some(cell(self.category).to_string()).filter(|c| !c.is_empty());
+        Ok((
+            Draft {
+                date,
+                description,
+                amount,
+                external_id: None,
+            },
+            category,
+        ))
+    }
+}
+
+/// The text of a leaf element such as `<TRNAMT>-12.50`. OFX 1.x is SGML
+/// and often leaves these unclosed, so the value runs to the next tag.
+fn ofx_value<'a>(block: &'a str, tag: &str) -> Option<&'a str> {
+    let start = block.find(&format!("<{tag}>"))? + tag.len() + 2;
+    let rest = &block[start..];
+    let value = rest[..rest.find(['<', '\n']).unwrap_or(rest.len())].trim();
+    (!value.is_empty()).then_some(value)
+}
+
+/// Reads the `<STMTTRN>` entries of an OFX or QFX statement.
+pub fn parse_ofx(text: &str) -> Result<Vec<Result<Draft, String>>, String> {
+    let code = ofx_value(text, "CURDEF").unwrap_or(USD.code);
+    let currency = Currency::from_code(code).ok_or_else(|| format!("Unsupported currency {code}"))?;
+
+    let blocks: Vec<&str> = text.split("<STMTTRN>").skip(1).collect();
+    if blocks.is_empty() {
+        return Err("No transactions found in the statement".to_string());
+    }
+    Ok(blocks
+        .into_iter()
+        .map(|block| {
+            let block = &block[..block.find("</STMTTRN>").unwrap_or(block.len())];
+            let posted = ofx_value(block, "DTPOSTED").ok_or("Missing DTPOSTED")?;
+            let date = posted
+                .get(..8)
+                .and_then(|day| NaiveDate::parse_from_str(day, "%Y%m%d").ok())
+                .ok_or_else(|| format!("Unreadable date {posted:?}"))?;
+            let amount_text = ofx_value(block, "TRNAMT").ok_or("Missing TRNAMT")?;
+            let amount = Money::parse(amount_text, currency, Locale::default())
+                .map_err(|err| format!("{}: {:?}", err, amount_text))?;
+            let description = ofx_value(block, "NAME")
+                .or_else(|| ofx_value(block, "MEMO"))
+                .ok_or("Missing NAME")?
+                .to_string();
+            Ok(Draft {
+                date,
+                description,
+                amount,
+                external_id: ofx_value(block, "FITID").map(str::to_string),
+            })
+        })
+        .collect())
+}
+
+fn normalize(description: &str) -> String {
+    description
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .to_lowercase()
+}
+
+/// The category most often used for the same description, else a keyword
+/// match, else `Uncategorized`.
+pub fn categorize(description: &str, existing: &[Transaction]) -> String {
+    let key = normalize(description);
+    let mut counts = std::collections::HashMap::new();
+    for t in existing.iter().filter(|t| normalize(&t.description) == key) {
+        *counts.entry(t.category.as_str()).or_insert(0) += 1;
+    }
+    if let Some((category, _)) = counts.into_iter().max_by_key(|(_, count)| *count) {
+        return category.to_string();
+    }
+    KEYWORDS
+        .iter()
+        .find(|(keyword, _)| key.contains(keyword))
+        .map_or(UNCATEGORIZED, |(_, category)| category)
+        .to_string()
+}
+
+/// A line is a duplicate when the bank id matches, or when the day, signed
+/// amount and description all match an existing transaction.
+pub fn is_duplicate(draft: &Draft, existing: &[Transaction]) -> bool {
+    let key = normalize(&draft.description);
+    existing.iter().any(|t| {
+        if let (Some(a), Some(b)) = (&draft.external_id, &t.external_id) {
+            return a == b;
+        }
+        t.date.date_naive() == draft.date
+            && signed(t) == draft.amount
+            && normalize(&t.description) == key
+    })
+}
+
+fn signed(t: &Transaction) -> Money {
+    match t.transaction_type {
+        TransactionType::Income => t.amount,
+        TransactionType::Expense => Money::from_minor(-t.amount.minor, t.amount.currency),
+    }
+}
+
+/// User rules come first, then a category column in the file, then
+/// `categorize`.
+fn preview_row(
+    line: usize,
+    draft: Result<(Draft, Option<String>), String>,
+    existing: &[Transaction],
+    rules: &RuleSet,
+) -> PreviewRow {
+    match draft {
+        Ok((draft, category)) => PreviewRow {
+            line,
+            category: rules
+                .category_for(&draft.description, draft.amount.abs(), &draft.kind())
+                .or(category)
+                .unwrap_or_else(|| categorize(&draft.description, existing)),
+            status: if draft.amount.minor == 0 {
+                Status::Invalid("Zero amount".to_string())
+            } else if is_duplicate(&draft, existing) {
+                Status::Duplicate
+            } else {
+                Status::New
+            },
+            draft: Some(draft),
+        },
+        Err(err) => PreviewRow {
+            line,
+            draft: None,
+            category: String::new(),
+            status: Status::Invalid(err),
+        },
+    }
+}
+
+impl Draft {
+    fn kind(&self) -> TransactionType {
+        if self.amount.is_negative() {
+            TransactionType::Expense
+        } else {
+            TransactionType::Income
+        }
+    }
+}
+
+impl PreviewRow {
+    pub fn to_transaction(&self) -> Option<Transaction> {
+        let draft = self.draft.as_ref().filter(|_| self.status == Status::New)?;
+        Some(Transaction {
+            id: Uuid::new_v4().to_string(),
+            description: draft.description.clone(),
+            amount: draft.amount.abs(),
+            category: self.category.clone(),
+            date: local_date(draft.date),
+            transaction_type: draft.kind(),
+            external_id: draft.external_id.clone(),
+            splits: Vec::new(),
+            version: 0,
+        })
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Source {
+    Csv(Vec<Vec<String>>),
+    Ofx(Vec<Result<Draft, String>>),
+}
+
+async fn read_file(file: web_sys::File) -> Result<String, String> {
+    JsFuture::from(file.text())
+        .await
+        .ok()
+        .and_then(|value| value.as_string())
+        .ok_or_else(|| format!("Could not read {}", file.name()))
+}
+
+#[component]
+pub fn StatementImport(
+    transactions: ReadSignal<Vec<Transaction>>,
+    set_transactions: WriteSignal<Vec<Transaction>>,
+    rules: ReadSignal<Vec<Rule>>,
+    #[prop(into)] default_currency: Signal<Currency>,
+    locale: Locale,
+) -> impl IntoView {
+    let (source, set_source) = signal::<Option<Source>>(None);
+    let (mapping, set_mapping) = signal::<Option<CsvMapping>>(None);
+    let (message, set_message) = signal::<Option<String>>(None);
+
+    let choose_file = move |ev: leptos::ev::Event| {
+        let input: web_sys::HtmlInputElement = event_target(&ev);
+        let Some(file) = input.files().and_then(|files| files.get(0)) else {
+            return;
+        };
+        input.set_value("");
+        leptos::task::spawn_local(async move {
+            let text = match read_file(file).await {
+                Ok(text) => text,
+                Err(err) => return set_message.set(Some(err)),
+            };
+            if text.contains("<OFX>") || text.contains("OFXHEADER") {
+                match parse_ofx(&text) {
+                    Ok(drafts) => {
+                        set_mapping.set(None);
+                        set_source.set(Some(Source::Ofx(drafts)));
+                        set_message.set(None);
+                    }
+                    Err(err) => set_message.set(Some(err)),
+                }
+            } else {
+                let records = parse_csv(&text);
+                let Some(header) = records.first() else {
+                    return set_message.set(Some("The file is empty".to_string()));
+                };
+                set_mapping.set(Some(CsvMapping::guess(header, default_currency.get_untracked())));
+                set_source.set(Some(Source::Csv(records)));
+                set_message.set(None);
+            }
+        });
+    };
+
+    let preview = Memo::new(move |_| {
+        let existing = transactions.get();
+        let rules = rules.with(|rules| RuleSet::new(rules));
+        match source.get() {
+            None => Vec::new(),
+            Some(Source::Ofx(drafts)) => drafts
+                .into_iter()
+                .enumerate()
+                .map(|(i, draft)| preview_row(i + 1, draft.map(|d| (d, None)), &existing, &rules))
+                .collect(),
+            Some(Source::Csv(records)) => {
+                let Some(mapping) = mapping.get() else {
+                    return Vec::new();
+                };
+                records
+                    .iter()
+                    .enumerate()
+                    .skip(usize::from(mapping.has_header))
+                    .map(|(i, record)| preview_row(i + 1, mapping.draft(record, locale), &existing, &rules))
+                    .collect()
+            }
+        }
+    });
+
+    let new_count = move || preview.with(|rows| rows.iter().filter(|r| r.status == Status::New).count());
+    let duplicate_count = move || preview.with(|rows| rows.iter().filter(|r| r.status == Status::Duplicate).count());
+    let invalid_count = move || {
+        preview.with(|rows| rows.iter().filter(|r| matches!(r.status, Status::Invalid(_))).count())
+    };
+
+    let cancel = move |_| {
+        set_source.set(None);
+        set_mapping.set(None);
+    };
+
+    let import = move |_| {
+        let added: Vec<Transaction> = preview.with(|rows| rows.iter().filter_map(PreviewRow::to_transaction).collect());
+        set_message.set(Some(format!("Imported {} transactions", added.len())));
+        set_transactions.update(|transactions| transactions.extend(added));
+        set_source.set(None);
+        set_mapping.set(None);
+    };
+
+    let column_names = move || match source.get() {
+        Some(Source::Csv(records)) => {
+            let header = records.first().cloned().unwrap_or_default();
+            let has_header = mapping.get().is_some_and(|m| m.has_header);
+            header
+                .iter()
+                .enumerate()
+                .map(|(i, name)| if has_header { name.clone() } else { format!("Column {}", i + 1) })
+                .collect::<Vec<_>>()
+        }
+        _ => Vec::new(),
+    };
+
+    let column_select = move |label: &'static str,
+                              get: fn(&CsvMapping) -> Option<usize>,
+                              set: fn(&mut CsvMapping, Option<usize>)| {
+        view! {
+            <label class="mapping-field">
+                <span>{label}</span>
+                <select on:change=move |ev| {
+                    let column = event_target_value(&ev).parse().ok();
+                    set_mapping.update(|m| if let Some(m) = m { set(m, column) });
+                }>
+                    <option value="" selected=move || mapping.get().is_some_and(|m| get(&m).is_none())>"—"</option>
+                    {move || column_names().into_iter().enumerate().map(|(i, name)| view! {
+                        <option
+                            value=i.to_string()
+                            selected=move || mapping.get().is_some_and(|m| get(&m) == Some(i))
+                        >
+                            {name}
+                        </option>
+                    }).collect_view()}
+                </select>
+            </label>
+        }
+    };
+
+    view! {
+        <div class="import-section">
+            <h2>"Import Statement"</h2>
+            <div class="import-controls">
+                <label class="reset-btn file-btn">
+                    "Choose CSV, OFX or QFX file…"
+                    <input type="file" accept=".csv,.ofx,.qfx,text/csv" on:change=choose_file />
+                </label>
+                {move || message.get().map(|message| view! { <span class="import-message">{message}</span> })}
+            </div>
+
+            <Show when=move || mapping.get().is_some()>
+                <div class="mapping">
+                    <label class="mapping-field">
+                        <input
+                            type="checkbox"
+                            prop:checked=move || mapping.get().is_some_and(|m| m.has_header)
+                            on:change=move |ev| {
+                                let checked = event_target_checked(&ev);
+                                set_mapping.update(|m| if let Some(m) = m { m.has_header = checked });
+                            }
+                        />
+                        <span>"First row is a header"</span>
+                    </label>
+                    {column_select("Date", |m| m.date, |m, c| m.date = c)}
+                    {column_select("Description", |m| m.description, |m, c| m.description = c)}
+                    {column_select("Amount (signed)", |m| m.amount, |m, c| m.amount = c)}
+                    {column_select("Money out", |m| m.debit, |m, c| m.debit = c)}
+                    {column_select("Money in", |m| m.credit, |m, c| m.credit = c)}
+                    {column_select("Category", |m| m.category, |m, c| m.category = c)}
+                    <label class="mapping-field">
+                        <span>"Date format"</span>
+                        <select on:change=move |ev| {
+                            let value = event_target_value(&ev);
+                            if let Some(&(format, _)) = DATE_FORMATS.iter().find(|(format, _)| *format == value) {
+                                set_mapping.update(|m| if let Some(m) = m { m.date_format = format });
+                            }
+                        }>
+                            {DATE_FORMATS.iter().map(|(format, label)| view! {
+                                <option
+                                    value=*format
+                                    selected=move || mapping.get().is_some_and(|m| m.date_format == *format)
+                                >
+                                    {*label}
+                                </option>
+                            }).collect_view()}
+                        </select>
+                    </label>
+                    <label class="mapping-field">
+                        <span>"Currency"</span>
+                        <select on:change=move |ev| {
+                            if let Some(currency) = Currency::from_code(&event_target_value(&ev)) {
+                                set_mapping.update(|m| if let Some(m) = m { m.currency = currency });
+                            }
+                        }>
+                            {currency_options(Signal::derive(move || mapping.get().map_or(USD, |m| m.currency)))}
+                        </select>
+                    </label>
+                </div>
+            </Show>
+
+            <Show when=move || source.get().is_some()>
+                <p class="import-summary">
+                    {move || format!(
+                        "Dry run: {} to add, {} duplicates skipped, {} unreadable",
+                        new_count(),
+                        duplicate_count(),
+                        invalid_count(),
+                    )}
+                </p>
+                <table class="import-preview">
+                    <thead>
+                        <tr>
+                            <th>"Line"</th>
+                            <th>"Date"</th>
+                            <th>"Description"</th>
+                            <th>"Amount"</th>
+                            <th>"Category"</th>
+                            <th>"Status"</th>
+                        </tr>
+                    </thead>
+                    <tbody>
+                        {move || preview.get().into_iter().map(|row| {
+                            let (status, class) = match &row.status {
+                                Status::New => ("Will be added".to_string(), "new"),
+                                Status::Duplicate => ("Duplicate, skipped".to_string(), "duplicate"),
+                                Status::Invalid(err) => (err.clone(), "invalid"),
+                            };
+                            let draft = row.draft.clone();
+                            view! {
+                                <tr class=class>
+                                    <td>{row.line}</td>
+                                    <td>{draft.as_ref().map(|d| d.date.format("%b %d, %Y").to_string())}</td>
+                                    <td>{draft.as_ref().map(|d| d.description.clone())}</td>
+                                    <td>{draft.as_ref().map(|d| d.amount.format(locale))}</td>
+                                    <td>{row.category}</td>
+                                    <td>{status}</td>
+                                </tr>
+                            }
+                        }).collect_view()}
+                    </tbody>
+                </table>
+                <div class="import-actions">
+                    <button class="reset-btn" on:click=cancel>"Cancel"</button>
+                    <button class="submit-btn" on:click=import disabled=move || new_count() == 0>
+                        {move || format!("Import {} Transactions", new_count())}
+                    </button>
+                </div>
+            </Show>
+        </div>
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn strings(fields: &[&str]) -> Vec<String> {
+        fields.iter().map(|f| f.to_string()).collect()
+    }
+
+    fn date(y: i32, m: u32, d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(y, m, d).unwrap()
+    }
+
+    fn usd(minor: i64) -> Money {
+        Money::from_minor(minor, USD)
+    }
+
+    fn draft(on: NaiveDate, description: &str, amount: Money, external_id: Option<&str>) -> Draft {
+        Draft {
+            date: on,
+            description: description.to_string(),
+            amount,
+            external_id: external_id.map(str::to_string),
+        }
+    }
+
+    fn transaction(description: &str, category: &str, expense: Option<i64>) -> Transaction {
+        let (transaction_type, minor) = match expense {
+            Some(minor) => (TransactionType::Expense, minor),
+            None => (TransactionType::Income, 100),
+        };
+        Transaction {
+            description: description.to_string(),
+            category: category.to_string(),
+            amount: usd(minor),
+            date: local_date(date(2024, 1, 5)),
+            transaction_type,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn csv_fields_may_quote_delimiters_quotes_and_newlines() {
+        let text = "Date,Description,Amount\r\n2024-01-02,\"Coffee, large\",-3.50\r\n\r\n\"2024-01-03\",\"Say \"\"hi\"\"\nagain\",10";
+        assert_eq!(
+            parse_csv(text),
+            vec![
+                strings(&["Date", "Description", "Amount"]),
+                strings(&["2024-01-02", "Coffee, large", "-3.50"]),
+                strings(&["2024-01-03", "Say \"hi\"\nagain", "10"]),
+            ]
+        );
+    }
+
+    #[test]
+    fn csv_delimiter_comes_from_the_first_line() {
+        let semicolons = "Datum;Beschreibung;Betrag\n02.01.2024;Kaffee, groß;-3,50\n";
+        assert_eq!(parse_csv(semicolons)[1], strings(&["02.01.2024", "Kaffee, groß", "-3,50"]));
+        let tabs = "Date\tPayee\tAmount\n2024-01-02\tShop; Co\t1,234.00\n";
+        assert_eq!(parse_csv(tabs)[1], strings(&["2024-01-02", "Shop; Co", "1,234.00"]));
+        assert_eq!(parse_csv(""), Vec::<Vec<String>>::new());
+    }
+
+    #[test]
+    fn guess_finds_debit_and_credit_columns() {
+        let header = strings(&["Posting Date", "Payee", "Paid out", "Paid in", "Category"]);
+        let mapping = CsvMapping::guess(&header, USD);
+        assert!(mapping.has_header);
+        assert_eq!(
+            (mapping.date, mapping.description, mapping.amount, mapping.debit, mapping.credit, mapping.category),
+            (Some(0), Some(1), None, Some(2), Some(3), Some(4))
+        );
+
+        let row = |cells: &[&str]| mapping.draft(&strings(cells), Locale::default());
+        let (debit, category) = row(&["2024-01-05", "Grocer", "12.50", "", "Food"]).unwrap();
+        assert_eq!(debit.amount, usd(-1250));
+        assert_eq!(category.as_deref(), Some("Food"));
+        let (credit, category) = row(&["2024-01-05", "Pay", "0.00", "100", ""]).unwrap();
+        assert_eq!(credit.amount, usd(10000));
+        assert_eq!(category, None);
+        // A bank writing debits as negative numbers still means money out.
+        assert_eq!(row(&["2024-01-05", "Grocer", "-12.50", "", ""]).unwrap().0.amount, usd(-1250));
+        assert_eq!(row(&["2024-01-05", "Nothing", "", "", ""]), Err("Missing amount".to_string()));
+        assert_eq!(row(&["2024-01-05", "", "1", "", ""]), Err("Missing description".to_string()));
+        assert!(row(&["2024-01-05", "Grocer", "12.5.0", "", ""]).unwrap_err().ends_with(": \"12.5.0\""));
+    }
+
+    #[test]
+    fn guess_without_a_header_uses_date_description_amount() {
+        let mapping = CsvMapping::guess(&strings(&["2024-01-02", "Coffee", "-3.50"]), USD);
+        assert!(!mapping.has_header);
+        assert_eq!((mapping.date, mapping.description, mapping.amount), (Some(0), Some(1), Some(2)));
+        let (draft, _) = mapping.draft(&strings(&["2024-01-02", "Coffee", "-3.50"]), Locale::default()).unwrap();
+        assert_eq!(draft, self::draft(date(2024, 1, 2), "Coffee", usd(-350), None));
+    }
+
+    #[test]
+    fn draft_reads_each_date_format() {
+        let samples = ["2024-03-04", "03/04/2024", "04/03/2024", "04.03.2024", "20240304"];
+        for ((format, label), text) in DATE_FORMATS.iter().zip(samples) {
+            let mapping = CsvMapping {
+                date_format: format,
+                ..CsvMapping::guess(&strings(&["Date", "Description", "Amount"]), USD)
+            };
+            let (draft, _) = mapping.draft(&strings(&[text, "Rent", "-1,200.00"]), Locale::default()).unwrap();
+            assert_eq!(draft.date, date(2024, 3, 4), "{}", label);
+            assert_eq!(draft.amount, usd(-120000));
+        }
+        let mapping = CsvMapping::guess(&strings(&["Date", "Description", "Amount"]), USD);
+        let german = Locale { decimal: ',', group: '.' };
+        assert_eq!(
+            mapping.draft(&strings(&["04.03.2024", "Rent", "-1.200,00"]), german),
+            Err("Unreadable date \"04.03.2024\"".to_string())
+        );
+        let mapping = CsvMapping { date_format: "%d.%m.%Y", ..mapping };
+        assert_eq!(mapping.draft(&strings(&["04.03.2024", "Rent", "-1.200,00"]), german).unwrap().0.amount, usd(-120000));
+    }
+
+    #[test]
+    fn ofx_reads_unclosed_sgml_elements() {
+        let statement = "OFXHEADER:100\n<OFX><STMTRS><CURDEF>EUR\n<BANKTRANLIST>\n\
+            <STMTTRN>\n<TRNTYPE>DEBIT\n<DTPOSTED>20240105120000[-5:EST]\n<TRNAMT>-42.10\n<FITID>A1\n<NAME>Grocery Store\n</STMTTRN>\n\
+            <STMTTRN><DTPOSTED>20240106</DTPOSTED><TRNAMT>1000.00</TRNAMT><MEMO>Payroll</MEMO></STMTTRN>\n\
+            <STMTTRN>\n<DTPOSTED>2024\n<TRNAMT>1\n<NAME>Short date\n</STMTTRN>\n\
+            <STMTTRN>\n<DTPOSTED>20240107\n<NAME>No amount\n</BANKTRANLIST>";
+        let eur = Currency::from_code("EUR").unwrap();
+        assert_eq!(
+            parse_ofx(statement).unwrap(),
+            vec![
+                Ok(draft(date(2024, 1, 5), "Grocery Store", Money::from_minor(-4210, eur), Some("A1"))),
+                Ok(draft(date(2024, 1, 6), "Payroll", Money::from_minor(100000, eur), None)),
+                Err("Unreadable date \"2024\"".to_string()),
+                Err("Missing TRNAMT".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn ofx_statement_errors() {
+        assert_eq!(parse_ofx("<OFX><CURDEF>XYZ<STMTTRN>"), Err("Unsupported currency XYZ".to_string()));
+        assert_eq!(parse_ofx("<OFX></OFX>"), Err("No transactions found in the statement".to_string()));
+        // Without CURDEF the statement is taken to be in dollars.
+        let drafts = parse_ofx("<STMTTRN><DTPOSTED>20240105<TRNAMT>-1.5<NAME>Fee").unwrap();
+        assert_eq!(drafts, vec![Ok(draft(date(2024, 1, 5), "Fee", usd(-150), None))]);
+    }
+
+    #[test]
+    fn duplicates_match_by_bank_id_or_by_day_amount_and_description() {
+        let mut existing = transaction("Grocery Store", "Food", Some(4210));
+        existing.external_id = Some("A1".to_string());
+        let existing = [existing, transaction("Corner  SHOP", "Food", Some(500))];
+        let day = date(2024, 1, 5);
+
+        assert!(is_duplicate(&draft(date(2024, 2, 1), "Other", usd(-1), Some("A1")), &existing));
+        // Different bank ids are different lines even when everything else matches.
+        assert!(!is_duplicate(&draft(day, "Grocery Store", usd(-4210), Some("B2")), &existing));
+        assert!(is_duplicate(&draft(day, " grocery   store ", usd(-4210), None), &existing));
+        assert!(is_duplicate(&draft(day, "corner shop", usd(-500), Some("C3")), &existing));
+        assert!(!is_duplicate(&draft(day, "Grocery Store", usd(4210), None), &existing));
+        assert!(!is_duplicate(&draft(date(2024, 1, 6), "Grocery Store", usd(-4210), None), &existing));
+        assert!(!is_duplicate(&draft(day, "Grocery Store", usd(-4211), None), &existing));
+    }
+
+    #[test]
+    fn categorize_prefers_history_then_keywords() {
+        let existing = [
+            transaction("Coffee  Shop", "Treats", Some(300)),
+            transaction("coffee shop", "Treats", Some(300)),
+            transaction("Coffee Shop", "Food", Some(300)),
+        ];
+        assert_eq!(categorize("COFFEE SHOP", &existing), "Treats");
+        assert_eq!(categorize("Coffee Bar", &existing), "Food");
+        assert_eq!(categorize("ACME PAYROLL JAN", &existing), "Salary");
+        assert_eq!(categorize("Hardware store", &existing), UNCATEGORIZED);
+        assert_eq!(categorize("Hardware store", &[]), UNCATEGORIZED);
+    }
+}
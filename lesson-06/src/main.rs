@@ -1,19 +1,54 @@
 use leptos::prelude::*;
+use leptos::task::spawn_local;
 use leptos_meta::*;
-use chrono::{DateTime, Local};
+use chrono::{DateTime, Local, NaiveDate};
+use serde::{Deserialize, Serialize};
 use uuid::Uuid;
+use std::rc::Rc;
 
-#[derive(Clone, Debug)]
+use money::{Currency, Locale, Money, MoneyError, USD};
+use budget::{Budget, Budgets};
+use charts::{CategoryChart, CategoryPie, SpendingOverTime};
+use import::StatementImport;
+use period::{DateRange, MonthOverMonth, Preset};
+use rates::{currency_options, ExchangeRate, ExchangeRates};
+use recurring::{Recurring, RecurringEditor};
+use rules::{Rule, RuleSet, RulesEditor};
+use splits::{Split, SplitBreakdown, SplitEditor, SplitInput};
+use storage::{Database, OpenError};
+
+mod budget;
+mod charts;
+mod import;
+mod money;
+mod period;
+mod rates;
+mod recurring;
+mod rules;
+mod splits;
+mod storage;
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
 struct Transaction {
     id: String,
     description: String,
-    amount: f64,
+    amount: Money,
     category: String,
     date: DateTime<Local>,
     transaction_type: TransactionType,
+    /// The bank's id for an imported statement line, used to spot re-imports.
+    #[serde(default)]
+    external_id: Option<String>,
+    /// How the amount divides between categories; empty when it all goes
+    /// to `category`, which is otherwise the first split's.
+    #[serde(default)]
+    splits: Vec<Split>,
+    /// Bumped on every edit so the list redraws the row.
+    #[serde(default)]
+    version: u32,
 }
 
-#[derive(Clone, Debug, PartialEq)]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 enum TransactionType {
     Income,
     Expense,
@@ -33,84 +68,236 @@ impl Default for Transaction {
         Self {
             id: Uuid::new_v4().to_string(),
             description: String::new(),
-            amount: 0.0,
+            amount: Money::zero(USD),
             category: String::new(),
             date: Local::now(),
             transaction_type: TransactionType::Expense,
+            external_id: None,
+            splits: Vec::new(),
+            version: 0,
         }
     }
 }
 
-#[component]
-fn ExpenseTracker() -> impl IntoView {
-    // Initial transactions
-    let initial_transactions = vec![
+/// Demo data, seeded on first run or loaded on request.
+fn sample_transactions() -> Vec<Transaction> {
+    vec![
         Transaction {
             description: "Monthly Salary".to_string(),
-            amount: 3500.00,
+            amount: Money::from_minor(350000, USD),
             category: "Salary".to_string(),
             transaction_type: TransactionType::Income,
             ..Default::default()
         },
         Transaction {
             description: "Groceries".to_string(),
-            amount: 125.75,
+            amount: Money::from_minor(12575, USD),
             category: "Food".to_string(),
             ..Default::default()
         },
         Transaction {
             description: "Internet Bill".to_string(),
-            amount: 65.00,
+            amount: Money::from_minor(6500, USD),
             category: "Utilities".to_string(),
             ..Default::default()
         },
         Transaction {
             description: "Coffee Shop".to_string(),
-            amount: 4.50,
+            amount: Money::from_minor(450, USD),
             category: "Food".to_string(),
             ..Default::default()
         },
         Transaction {
             description: "Freelance Work".to_string(),
-            amount: 800.00,
+            amount: Money::from_minor(80000, USD),
             category: "Freelance".to_string(),
             transaction_type: TransactionType::Income,
             ..Default::default()
         },
-    ];
+    ]
+}
+
+/// Writes `values` to `store` whenever they change. Saving waits for the
+/// initial load so it cannot overwrite stored data with the empty starting
+/// state. Writes to a store run one at a time, and changes made while one
+/// is in flight collapse into a single write of the latest values.
+fn persist<T>(
+    store: &'static str,
+    values: ReadSignal<Vec<T>>,
+    loaded: ReadSignal<bool>,
+    database: StoredValue<Option<Rc<Database>>, LocalStorage>,
+    (storage_error, set_storage_error): (ReadSignal<Option<String>>, WriteSignal<Option<String>>),
+) where
+    T: Serialize + Clone + Send + Sync + 'static,
+{
+    let pending = StoredValue::new(None::<Vec<T>>);
+    let saving = StoredValue::new(false);
+    Effect::new(move |_| {
+        let values = values.get();
+        if !loaded.get() || storage_error.get_untracked().is_some() {
+            return;
+        }
+        let Some(db) = database.get_value() else {
+            return;
+        };
+        pending.set_value(Some(values));
+        if saving.get_value() {
+            return;
+        }
+        saving.set_value(true);
+        spawn_local(async move {
+            while let Some(values) = pending.try_update_value(Option::take).flatten() {
+                if db.replace_all(store, &values).await.is_err() {
+                    set_storage_error.set(Some("Changes could not be saved.".to_string()));
+                    break;
+                }
+            }
+            saving.set_value(false);
+        });
+    });
+}
 
+#[component]
+fn ExpenseTracker() -> impl IntoView {
     // State - FIXED: Using signal() instead of create_signal()
-    let (transactions, set_transactions) = signal(initial_transactions);
-    let (form, set_form) = signal(Transaction::default());
+    let (transactions, set_transactions) = signal(Vec::<Transaction>::new());
+    let (loaded, set_loaded) = signal(false);
+    let (storage_error, set_storage_error) = signal::<Option<String>>(None);
+    let (rates, set_rates) = signal(Vec::<ExchangeRate>::new());
+    let (rules, set_rules) = signal(Vec::<Rule>::new());
+    let (budgets, set_budgets) = signal(Vec::<Budget>::new());
+    let (recurring, set_recurring) = signal(Vec::<Recurring>::new());
+    let (today, set_today) = signal(period::today());
+    let (base_currency, set_base_currency) = signal(rates::load_base_currency());
+    let new_form = move || Transaction {
+        amount: Money::zero(base_currency.get_untracked()),
+        ..Default::default()
+    };
+    let (form, set_form) = signal(new_form());
+    let (amount_input, set_amount_input) = signal(String::new());
+    let (split_inputs, set_split_inputs) = signal(Vec::<SplitInput>::new());
+    let (form_error, set_form_error) = signal::<Option<String>>(None);
+    let locale = Locale::detect();
     let (filter_category, set_filter_category) = signal(String::new());
     let (filter_type, set_filter_type) = signal::<Option<TransactionType>>(None);
     let (editing_id, set_editing_id) = signal::<Option<String>>(None);
+    let (preset, set_preset) = signal(Preset::AllTime);
+    let (date_range, set_date_range) = signal(DateRange::default());
+
+    let database = StoredValue::new_local(None::<Rc<Database>>);
+    spawn_local(async move {
+        let on_blocked = move || {
+            set_storage_error.set(Some("Close the tracker's other tabs to finish updating its storage.".to_string()));
+        };
+        let opened = storage::open(on_blocked).await.map(Rc::new);
+        if let Ok(db) = &opened {
+            set_storage_error.set(None);
+            database.set_value(Some(db.clone()));
+        }
+        match opened {
+            Ok(db) if db.created => set_transactions.set(sample_transactions()),
+            Ok(db) => match db.load_all().await {
+                Ok(mut stored) => {
+                    stored.transactions.sort_by_key(|t| t.date);
+                    set_transactions.set(stored.transactions);
+                    set_rates.set(stored.rates);
+                    set_rules.set(stored.rules);
+                    set_budgets.set(stored.budgets);
+                    set_recurring.set(stored.recurring);
+                }
+                Err(_) => set_storage_error.set(Some("Saved transactions could not be read.".to_string())),
+            },
+            Err(OpenError::Upgrade { from_version, message }) => set_storage_error.set(Some(format!(
+                "Storage could not be updated from version {} ({}); changes will not be saved.",
+                from_version, message
+            ))),
+            Err(OpenError::Unavailable) => {
+                set_storage_error.set(Some("Storage is unavailable; changes will not be saved.".to_string()))
+            }
+        }
+        set_loaded.set(true);
+    });
+
+    persist(storage::TRANSACTIONS, transactions, loaded, database, (storage_error, set_storage_error));
+    persist(storage::RATES, rates, loaded, database, (storage_error, set_storage_error));
+    persist(storage::RULES, rules, loaded, database, (storage_error, set_storage_error));
+    persist(storage::BUDGETS, budgets, loaded, database, (storage_error, set_storage_error));
+    persist(storage::RECURRING, recurring, loaded, database, (storage_error, set_storage_error));
+    Effect::new(move |_| rates::save_base_currency(base_currency.get()));
+
+    // Recurring occurrences become transactions once their date arrives,
+    // including on a new day in a tab that was left open.
+    set_interval(
+        move || {
+            let now = period::today();
+            if now != today.get_untracked() {
+                set_today.set(now);
+            }
+        },
+        std::time::Duration::from_secs(60),
+    );
+    Effect::new(move |_| {
+        if !loaded.get() {
+            return;
+        }
+        let mut schedules = recurring.get();
+        let due = recurring::materialize(&mut schedules, today.get());
+        if schedules != recurring.get_untracked() {
+            set_transactions.update(|transactions| transactions.extend(due));
+            set_recurring.set(schedules);
+        }
+    });
 
     // Computed values
-    let total_income = move || {
-        transactions.get()
-            .iter()
-            .filter(|t| t.transaction_type == TransactionType::Income)
-            .map(|t| t.amount)
-            .sum::<f64>()
+    // Each amount converted into the base currency at the rate in effect on
+    // its date. Transactions with no such rate are left out of the totals.
+    let converted = move || {
+        let base = base_currency.get();
+        rates.with(|rates| {
+            transactions.with(|transactions| {
+                transactions
+                    .iter()
+                    .filter_map(|t| match rates::convert(rates, t.amount, base, t.date.date_naive()) {
+                        Err(MoneyError::MissingRate(..)) => None,
+                        result => Some((t.clone(), result.map(|(amount, _)| amount))),
+                    })
+                    .collect::<Vec<_>>()
+            })
+        })
     };
 
-    let total_expenses = move || {
-        transactions.get()
-            .iter()
-            .filter(|t| t.transaction_type == TransactionType::Expense)
-            .map(|t| t.amount)
-            .sum::<f64>()
+    let unconverted_count = move || {
+        let base = base_currency.get();
+        rates.with(|rates| {
+            transactions.with(|transactions| {
+                transactions
+                    .iter()
+                    .filter(|t| rates::lookup(rates, t.amount.currency, base, t.date.date_naive()).is_none())
+                    .count()
+            })
+        })
     };
 
-    let balance_value = move || total_income() - total_expenses();
-    let is_positive = move || balance_value() >= 0.0;
-    let is_negative = move || balance_value() < 0.0;
+    let total_of = move |kind: TransactionType| {
+        let amounts = converted()
+            .into_iter()
+            .filter(|(t, _)| t.transaction_type == kind)
+            .map(|(_, amount)| amount)
+            .collect::<Result<Vec<_>, _>>()?;
+        Money::sum(base_currency.get(), amounts)
+    };
+
+    let total_income = move || total_of(TransactionType::Income);
+    let total_expenses = move || total_of(TransactionType::Expense);
+
+    let balance_value = move || total_income()?.checked_sub(total_expenses()?);
+    let is_positive = move || balance_value().is_ok_and(|b| !b.is_negative());
+    let is_negative = move || balance_value().is_ok_and(|b| b.is_negative());
 
     let categories = move || {
         let mut cats: Vec<String> = transactions.get()
             .iter()
-            .map(|t| t.category.clone())
+            .flat_map(|t| splits::categories(t).into_iter().map(str::to_string))
             .collect();
         cats.sort();
         cats.dedup();
@@ -118,55 +305,105 @@ fn ExpenseTracker() -> impl IntoView {
     };
 
     let filtered_transactions = move || {
-        transactions.get()
+        let mut filtered = transactions.get()
             .into_iter()
             .filter(|t| {
-                let category_match = filter_category.get().is_empty() || 
-                    t.category.to_lowercase().contains(&filter_category.get().to_lowercase());
+                let category_match = filter_category.get().is_empty() || splits::categories(t)
+                    .iter()
+                    .any(|category| category.to_lowercase().contains(&filter_category.get().to_lowercase()));
                 let type_match = filter_type.get().is_none() || 
                     Some(&t.transaction_type) == filter_type.get().as_ref();
-                category_match && type_match
+                let date_match = date_range.get().contains(t.date.date_naive());
+                category_match && type_match && date_match
             })
-            .collect::<Vec<_>>()
+            .collect::<Vec<_>>();
+        filtered.sort_by_key(|t| std::cmp::Reverse(t.date));
+        filtered
+    };
+
+    // Converted amounts for the reports, or the first conversion error.
+    let report_amounts = move || {
+        converted()
+            .into_iter()
+            .map(|(t, amount)| amount.map(|amount| (t, amount)))
+            .collect::<Result<Vec<_>, _>>()
+    };
+
+    let monthly = move || period::monthly_summaries(&report_amounts()?, date_range.get(), base_currency.get(), period::today());
+
+    let category_totals = move || charts::category_totals(&report_amounts()?, date_range.get(), base_currency.get());
+
+    // Actions
+    let reset_form = move || {
+        set_form.set(new_form());
+        set_amount_input.set(String::new());
+        set_split_inputs.set(Vec::new());
+        set_form_error.set(None);
     };
 
-    let category_totals = move || {
-        let mut totals = std::collections::HashMap::new();
-        for transaction in transactions.get().iter() {
-            let entry = totals.entry(transaction.category.clone()).or_insert((0.0, 0.0));
-            match transaction.transaction_type {
-                TransactionType::Income => entry.0 += transaction.amount,
-                TransactionType::Expense => entry.1 += transaction.amount,
+    // The form with its amount parsed, or `None` after reporting why not.
+    let validated_form = move || {
+        let mut transaction = form.get();
+        if transaction.description.trim().is_empty() {
+            set_form_error.set(Some("Enter a description".to_string()));
+            return None;
+        }
+        match Money::parse(&amount_input.get(), transaction.amount.currency, locale) {
+            Ok(amount) if amount.is_positive() => {
+                transaction.amount = amount;
+                match splits::parse_splits(&split_inputs.get(), amount, locale) {
+                    Ok(splits) => transaction.splits = splits,
+                    Err(err) => {
+                        set_form_error.set(Some(err));
+                        return None;
+                    }
+                }
+                if let Some(first) = transaction.splits.first() {
+                    transaction.category = first.category.clone();
+                } else if transaction.category.trim().is_empty() {
+                    transaction.category = rules
+                        .with(|rules| RuleSet::new(rules).category_for(&transaction.description, amount, &transaction.transaction_type))
+                        .unwrap_or_else(|| import::UNCATEGORIZED.to_string());
+                }
+                Some(transaction)
+            }
+            Ok(_) => {
+                set_form_error.set(Some("Amount must be greater than zero".to_string()));
+                None
+            }
+            Err(err) => {
+                set_form_error.set(Some(err.to_string()));
+                None
             }
         }
-        totals
     };
 
-    // Actions
     let add_transaction = move |_| {
-        if !form.get().description.trim().is_empty() && form.get().amount > 0.0 {
-            set_transactions.update(|transactions| {
-                let mut new_transaction = form.get();
-                new_transaction.id = Uuid::new_v4().to_string();
-                new_transaction.date = Local::now();
-                transactions.push(new_transaction);
-            });
-            set_form.set(Transaction::default());
+        if let Some(mut new_transaction) = validated_form() {
+            new_transaction.id = Uuid::new_v4().to_string();
+            set_transactions.update(|transactions| transactions.push(new_transaction));
+            reset_form();
         }
     };
 
     let update_transaction = move |_| {
         if let Some(id) = editing_id.get() {
+            let Some(edited) = validated_form() else {
+                return;
+            };
             set_transactions.update(|transactions| {
                 if let Some(transaction) = transactions.iter_mut().find(|t| t.id == id) {
-                    transaction.description = form.get().description.clone();
-                    transaction.amount = form.get().amount;
-                    transaction.category = form.get().category.clone();
-                    transaction.transaction_type = form.get().transaction_type.clone();
+                    transaction.description = edited.description;
+                    transaction.amount = edited.amount;
+                    transaction.category = edited.category;
+                    transaction.transaction_type = edited.transaction_type;
+                    transaction.date = edited.date;
+                    transaction.splits = edited.splits;
+                    transaction.version += 1;
                 }
             });
             set_editing_id.set(None);
-            set_form.set(Transaction::default());
+            reset_form();
         }
     };
 
@@ -177,24 +414,59 @@ fn ExpenseTracker() -> impl IntoView {
     };
 
     let edit_transaction = move |transaction: Transaction| {
+        set_amount_input.set(transaction.amount.format_number(locale));
+        set_split_inputs.set(transaction.splits.iter().map(|split| SplitInput::from_split(split, locale)).collect());
+        set_form_error.set(None);
         set_form.set(transaction.clone());
         set_editing_id.set(Some(transaction.id));
     };
 
     let cancel_edit = move |_| {
         set_editing_id.set(None);
-        set_form.set(Transaction::default());
+        reset_form();
     };
 
     let reset_filters = move |_| {
         set_filter_category.set(String::new());
         set_filter_type.set(None);
+        set_preset.set(Preset::AllTime);
+        set_date_range.set(DateRange::default());
+    };
+
+    let choose_preset = move |chosen: Preset| {
+        set_preset.set(chosen);
+        if let Some(range) = chosen.range(period::today()) {
+            set_date_range.set(range);
+        }
+    };
+
+    // Editing either end of the range by hand switches to a custom range.
+    let set_range_end = move |value: String, is_start: bool| {
+        let date = NaiveDate::parse_from_str(&value, "%Y-%m-%d").ok();
+        set_preset.set(Preset::Custom);
+        set_date_range.update(|range| {
+            if is_start {
+                range.from = date;
+            } else {
+                range.to = date;
+            }
+        });
     };
+    let range_value = move |date: Option<NaiveDate>| date.map(|d| d.to_string()).unwrap_or_default();
 
     let clear_all = move |_| {
         set_transactions.set(vec![]);
     };
 
+    // Only offered while the ledger is empty, so it never duplicates entries.
+    let load_sample_data = move |_| {
+        set_transactions.update(|transactions| {
+            if transactions.is_empty() {
+                *transactions = sample_transactions();
+            }
+        });
+    };
+
     view! {
         <div class="expense-tracker">
             <div class="header">
@@ -202,14 +474,42 @@ fn ExpenseTracker() -> impl IntoView {
                 <p>"Track your income and expenses in real-time"</p>
             </div>
 
+            {move || storage_error.get().map(|message| view! {
+                <div class="storage-error">{message}</div>
+            })}
+
+            <div class="base-currency">
+                <label for="base-currency">"Show totals in"</label>
+                <select id="base-currency" on:change=move |ev| {
+                    if let Some(currency) = Currency::from_code(&event_target_value(&ev)) {
+                        set_base_currency.set(currency);
+                    }
+                }>
+                    {currency_options(base_currency.into())}
+                </select>
+                {move || match unconverted_count() {
+                    0 => None,
+                    count => Some(view! {
+                        <span class="missing-rates">
+                            {format!(
+                                "{} transaction{} without a rate into {} left out",
+                                count,
+                                if count == 1 { "" } else { "s" },
+                                base_currency.get().code,
+                            )}
+                        </span>
+                    }),
+                }}
+            </div>
+
             <div class="summary">
                 <div class="summary-item">
                     <h3>"Total Income"</h3>
-                    <p class="income">{format!("${:.2}", total_income())}</p>
+                    <p class="income">{move || show_total(total_income(), locale)}</p>
                 </div>
                 <div class="summary-item">
                     <h3>"Total Expenses"</h3>
-                    <p class="expense">{format!("${:.2}", total_expenses())}</p>
+                    <p class="expense">{move || show_total(total_expenses(), locale)}</p>
                 </div>
                 <div class="summary-item">
                     <h3>"Current Balance"</h3>
@@ -218,7 +518,7 @@ fn ExpenseTracker() -> impl IntoView {
                         class:positive=is_positive
                         class:negative=is_negative
                     >
-                        {format!("${:.2}", balance_value())}
+                        {move || show_total(balance_value(), locale)}
                     </p>
                 </div>
             </div>
@@ -240,19 +540,24 @@ fn ExpenseTracker() -> impl IntoView {
                         </div>
                         
                         <div class="form-group">
-                            <label for="amount">"Amount ($)"</label>
-                            <input
-                                id="amount"
-                                type="number"
-                                step="0.01"
-                                min="0"
-                                placeholder="0.00"
-                                prop:value=move || form.get().amount.to_string()
-                                on:input=move |ev| {
-                                    let value = event_target_value(&ev).parse::<f64>().unwrap_or(0.0);
-                                    set_form.update(|f| f.amount = value);
-                                }
-                            />
+                            <label for="amount">"Amount"</label>
+                            <div class="amount-input">
+                                <input
+                                    id="amount"
+                                    type="text"
+                                    inputmode="decimal"
+                                    placeholder=move || Money::zero(form.get().amount.currency).format_number(locale)
+                                    prop:value=amount_input
+                                    on:input=move |ev| set_amount_input.set(event_target_value(&ev))
+                                />
+                                <select on:change=move |ev| {
+                                    if let Some(currency) = Currency::from_code(&event_target_value(&ev)) {
+                                        set_form.update(|f| f.amount.currency = currency);
+                                    }
+                                }>
+                                    {currency_options(Signal::derive(move || form.get().amount.currency))}
+                                </select>
+                            </div>
                         </div>
                     </div>
 
@@ -264,6 +569,7 @@ fn ExpenseTracker() -> impl IntoView {
                                 type="text"
                                 placeholder="e.g., Food, Salary, Utilities"
                                 list="categories"
+                                prop:disabled=move || !split_inputs.get().is_empty()
                                 prop:value=move || form.get().category
                                 on:input=move |ev| set_form.update(|f| f.category = event_target_value(&ev))
                             />
@@ -299,6 +605,34 @@ fn ExpenseTracker() -> impl IntoView {
                         </div>
                     </div>
 
+                    <div class="form-row">
+                        <div class="form-group">
+                            <label for="date">"Date"</label>
+                            <input
+                                id="date"
+                                type="date"
+                                prop:value=move || form.get().date.date_naive().to_string()
+                                on:input=move |ev| {
+                                    if let Ok(date) = NaiveDate::parse_from_str(&event_target_value(&ev), "%Y-%m-%d") {
+                                        set_form.update(|f| f.date = period::local_date(date));
+                                    }
+                                }
+                            />
+                        </div>
+                    </div>
+
+                    <SplitEditor
+                        inputs=split_inputs
+                        set_inputs=set_split_inputs
+                        total=Signal::derive(move || Money::parse(&amount_input.get(), form.get().amount.currency, locale).ok())
+                        currency=Signal::derive(move || form.get().amount.currency)
+                        locale=locale
+                    />
+
+                    {move || form_error.get().map(|message| view! {
+                        <p class="form-error">{message}</p>
+                    })}
+
                     <div class="form-actions">
                         <Show when=move || editing_id.get().is_some()
                             fallback=move || view! {
@@ -341,10 +675,39 @@ fn ExpenseTracker() -> impl IntoView {
                         <option value="expense">"Expense Only"</option>
                     </select>
                     
+                    <select on:change=move |ev| {
+                        let value = event_target_value(&ev);
+                        if let Some(chosen) = Preset::ALL.into_iter().find(|p| p.label() == value) {
+                            choose_preset(chosen);
+                        }
+                    }>
+                        {Preset::ALL.into_iter().map(|p| view! {
+                            <option value=p.label() selected=move || preset.get() == p>{p.label()}</option>
+                        }).collect_view()}
+                    </select>
+
+                    <div class="date-range">
+                        <input
+                            type="date"
+                            aria-label="From"
+                            prop:value=move || range_value(date_range.get().from)
+                            on:input=move |ev| set_range_end(event_target_value(&ev), true)
+                        />
+                        <span>"to"</span>
+                        <input
+                            type="date"
+                            aria-label="To"
+                            prop:value=move || range_value(date_range.get().to)
+                            on:input=move |ev| set_range_end(event_target_value(&ev), false)
+                        />
+                    </div>
+                    
                     <button class="reset-btn" on:click=reset_filters>
                         "Clear Filters"
                     </button>
                 </div>
+
+                <MonthOverMonth months=Signal::derive(monthly) locale=locale />
             </div>
 
             <div class="transactions-section">
@@ -353,18 +716,29 @@ fn ExpenseTracker() -> impl IntoView {
                 <Show when=move || !filtered_transactions().is_empty()
                     fallback=move || view! {
                         <div class="empty-state">
-                            <h3>"No transactions found"</h3>
-                            <p>"Add your first transaction using the form above!"</p>
+                            <Show when=move || loaded.get() fallback=|| view! { <h3>"Loading transactions…"</h3> }>
+                                <h3>"No transactions found"</h3>
+                                <p>"Add your first transaction using the form above!"</p>
+                                <Show when=move || transactions.with(Vec::is_empty)>
+                                    <button class="reset-btn" on:click=load_sample_data>
+                                        "Load Sample Data"
+                                    </button>
+                                </Show>
+                            </Show>
                         </div>
                     }>
                     <div class="transactions-list">
                         <For
                             each=filtered_transactions
-                            key=|transaction| transaction.id.clone()
+                            key=|transaction| (transaction.id.clone(), transaction.version)
                             children=move |transaction| {
                                 let tx_for_edit = transaction.clone();
                                 let tx_for_delete = transaction.clone();
                                 let is_income = transaction.transaction_type == TransactionType::Income;
+                                let category = splits::categories(&transaction).join(", ");
+                                let breakdown = (!transaction.splits.is_empty()).then(|| view! {
+                                    <SplitBreakdown splits=transaction.splits.clone() locale=locale />
+                                });
                                 view! {
                                     <div class="transaction"
                                          class:income=is_income
@@ -372,10 +746,11 @@ fn ExpenseTracker() -> impl IntoView {
                                         <div class="transaction-info">
                                             <h3>{transaction.description}</h3>
                                             <div class="transaction-meta">
-                                                <span class="category">{transaction.category}</span>
+                                                <span class="category">{category}</span>
                                                 <span class="date">{transaction.date.format("%b %d, %Y").to_string()}</span>
                                                 <span class="type">{transaction.transaction_type.as_str()}</span>
                                             </div>
+                                            {breakdown}
                                         </div>
                                         <div class="transaction-amount"
                                              class:positive=is_income
@@ -385,7 +760,21 @@ fn ExpenseTracker() -> impl IntoView {
                                             } else {
                                                 "-"
                                             }}
-                                            {format!("${:.2}", transaction.amount)}
+                                            {transaction.amount.format_abs(locale)}
+                                            {move || {
+                                                let base = base_currency.get();
+                                                (transaction.amount.currency != base).then(|| {
+                                                    let line = match rates.with(|rates| rates::convert(rates, transaction.amount, base, transaction.date.date_naive())) {
+                                                        Ok((amount, applied)) => format!(
+                                                            "≈ {} at {}",
+                                                            amount.format_abs(locale),
+                                                            applied.describe(transaction.amount.currency, base),
+                                                        ),
+                                                        Err(err) => err.to_string(),
+                                                    };
+                                                    view! { <div class="converted">{line}</div> }
+                                                })
+                                            }}
                                         </div>
                                         <div class="transaction-actions">
                                             <button class="edit-btn" on:click=move |_| edit_transaction(tx_for_edit.clone())>
@@ -401,7 +790,7 @@ fn ExpenseTracker() -> impl IntoView {
                         />
                     </div>
                     
-                    <div style="margin-top: 20px; text-align: center;">
+                    <div style="margin-top: 20px; display: flex; gap: 10px; justify-content: center;">
                         <button class="reset-btn" on:click=clear_all>
                             "Clear All Transactions"
                         </button>
@@ -409,45 +798,56 @@ fn ExpenseTracker() -> impl IntoView {
                 </Show>
             </div>
 
+            <StatementImport
+                transactions=transactions
+                set_transactions=set_transactions
+                rules=rules
+                default_currency=base_currency
+                locale=locale
+            />
+
+            <RecurringEditor
+                recurring=recurring
+                set_recurring=set_recurring
+                default_currency=base_currency
+                locale=locale
+            />
+
+            <RulesEditor
+                rules=rules
+                set_rules=set_rules
+                transactions=transactions
+                default_currency=base_currency
+                locale=locale
+            />
+
+            <ExchangeRates rates=rates set_rates=set_rates />
+
             <div class="chart-section">
-                <div class="chart-container">
-                    <h3>"Spending by Category"</h3>
-                    <div class="chart-bars">
-                        <For
-                            each=move || {
-                                let totals = category_totals();
-                                let max = totals.values()
-                                    .map(|(income, expense)| income + expense)
-                                    .fold(0.0, f64::max);
-                                
-                                let mut items: Vec<_> = totals.into_iter()
-                                    .map(move |(category, (income, expense))| (category, income + expense, max))
-                                    .collect();
-                                items.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap_or(std::cmp::Ordering::Equal));
-                                items
-                            }
-                            key=|(category, _, _)| category.clone()
-                            children=move |(category, total, max)| {
-                                let height = if max > 0.0 { (total / max) * 180.0 } else { 0.0 };
-                                view! {
-                                    <div class="chart-bar" style=format!("height: {}px", height)>
-                                        <div class="chart-bar-label">
-                                            <div>{category}</div>
-                                            <div style="font-size: 0.8rem; color: #94a3b8;">
-                                                {format!("${:.0}", total)}
-                                            </div>
-                                        </div>
-                                    </div>
-                                }
-                            }
-                        />
-                    </div>
-                </div>
+                <CategoryChart totals=Signal::derive(category_totals) locale=locale />
+                <SpendingOverTime months=Signal::derive(monthly) locale=locale />
+                <CategoryPie totals=Signal::derive(category_totals) locale=locale />
+
+                <Budgets
+                    budgets=budgets
+                    set_budgets=set_budgets
+                    transactions=transactions
+                    rates=rates
+                    default_currency=base_currency
+                    locale=locale
+                />
             </div>
         </div>
     }
 }
 
+fn show_total(total: Result<Money, MoneyError>, locale: Locale) -> String {
+    match total {
+        Ok(total) => total.format(locale),
+        Err(err) => err.to_string(),
+    }
+}
+
 #[component]
 fn App() -> impl IntoView {
     provide_meta_context();
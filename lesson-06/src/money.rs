@@ -0,0 +1,345 @@
+use std::fmt;
+
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Currency {
+    pub code: &'static str,
+    pub symbol: &'static str,
+    /// Digits after the decimal point, e.g. 2 for cents.
+    pub exponent: u32,
+}
+
+pub const USD: Currency = Currency { code: "USD", symbol: "$", exponent: 2 };
+
+pub const CURRENCIES: &[Currency] = &[
+    USD,
+    Currency { code: "EUR", symbol: "€", exponent: 2 },
+    Currency { code: "GBP", symbol: "£", exponent: 2 },
+    Currency { code: "JPY", symbol: "¥", exponent: 0 },
+    Currency { code: "CAD", symbol: "CA$", exponent: 2 },
+    Currency { code: "AUD", symbol: "A$", exponent: 2 },
+    Currency { code: "CHF", symbol: "CHF ", exponent: 2 },
+    Currency { code: "INR", symbol: "₹", exponent: 2 },
+];
+
+impl Currency {
+    pub fn from_code(code: &str) -> Option<Currency> {
+        CURRENCIES.iter().copied().find(|c| c.code.eq_ignore_ascii_case(code))
+    }
+
+    fn scale(self) -> i64 {
+        10i64.pow(self.exponent)
+    }
+}
+
+impl Serialize for Currency {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.code)
+    }
+}
+
+impl<'de> Deserialize<'de> for Currency {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let code = String::deserialize(deserializer)?;
+        Currency::from_code(&code)
+            .ok_or_else(|| serde::de::Error::custom(format!("unknown currency {code}")))
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum MoneyError {
+    Invalid,
+    TooPrecise(u32),
+    Overflow,
+    CurrencyMismatch(&'static str, &'static str),
+    MissingRate(&'static str, &'static str),
+}
+
+impl fmt::Display for MoneyError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            MoneyError::Invalid => write!(f, "Not a valid amount"),
+            MoneyError::TooPrecise(0) => write!(f, "This currency has no fractional units"),
+            MoneyError::TooPrecise(digits) => write!(f, "At most {digits} decimal places"),
+            MoneyError::Overflow => write!(f, "Amount is too large"),
+            MoneyError::CurrencyMismatch(a, b) => write!(f, "Cannot combine {a} with {b}"),
+            MoneyError::MissingRate(a, b) => write!(f, "No {a} to {b} rate"),
+        }
+    }
+}
+
+/// Number separators for the user's locale.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Locale {
+    pub decimal: char,
+    pub group: char,
+}
+
+impl Default for Locale {
+    fn default() -> Self {
+        Self { decimal: '.', group: ',' }
+    }
+}
+
+impl Locale {
+    /// Separators for a BCP 47 tag such as `de-DE`. Only the common
+    /// conventions are covered; anything else falls back to `1,234.56`.
+    pub fn from_tag(tag: &str) -> Locale {
+        let tag = tag.to_ascii_lowercase();
+        let language = tag.split(['-', '_']).next().unwrap_or("");
+        if tag == "de-ch" {
+            return Locale { decimal: '.', group: '\'' };
+        }
+        match language {
+            "de" | "es" | "it" | "nl" | "pt" | "da" | "tr" | "id" | "el" => Locale { decimal: ',', group: '.' },
+            "fr" | "ru" | "sv" | "pl" | "nb" | "no" | "fi" | "cs" | "uk" | "sk" => Locale { decimal: ',', group: '\u{a0}' },
+            _ => Locale::default(),
+        }
+    }
+
+    pub fn detect() -> Locale {
+        leptos::prelude::window()
+            .navigator()
+            .language()
+            .map(|tag| Locale::from_tag(&tag))
+            .unwrap_or_default()
+    }
+}
+
+/// An exact amount held in the currency's minor units (cents for USD).
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize)]
+pub struct Money {
+    pub minor: i64,
+    pub currency: Currency,
+}
+
+impl Money {
+    pub fn zero(currency: Currency) -> Money {
+        Money { minor: 0, currency }
+    }
+
+    pub fn from_minor(minor: i64, currency: Currency) -> Money {
+        Money { minor, currency }
+    }
+
+    pub fn is_positive(self) -> bool {
+        self.minor > 0
+    }
+
+    pub fn is_negative(self) -> bool {
+        self.minor < 0
+    }
+
+    pub fn abs(self) -> Money {
+        Money { minor: self.minor.abs(), ..self }
+    }
+
+    fn same_currency(self, other: Money) -> Result<(), MoneyError> {
+        if self.currency == other.currency {
+            Ok(())
+        } else {
+            Err(MoneyError::CurrencyMismatch(self.currency.code, other.currency.code))
+        }
+    }
+
+    pub fn checked_add(self, other: Money) -> Result<Money, MoneyError> {
+        self.same_currency(other)?;
+        let minor = self.minor.checked_add(other.minor).ok_or(MoneyError::Overflow)?;
+        Ok(Money { minor, ..self })
+    }
+
+    pub fn checked_sub(self, other: Money) -> Result<Money, MoneyError> {
+        self.same_currency(other)?;
+        let minor = self.minor.checked_sub(other.minor).ok_or(MoneyError::Overflow)?;
+        Ok(Money { minor, ..self })
+    }
+
+    pub fn sum(currency: Currency, amounts: impl IntoIterator<Item = Money>) -> Result<Money, MoneyError> {
+        amounts
+            .into_iter()
+            .try_fold(Money::zero(currency), |total, amount| total.checked_add(amount))
+    }
+
+    /// Parses user input such as `1,234.5`, `1.234,50 €` or `-$12`. Group
+    /// separators (or spaces) must split the whole part into groups of three,
+    /// so `12,50` is rejected rather than read as 1250. `.` is also accepted
+    /// as the decimal point when the locale does not use it for grouping.
+    pub fn parse(input: &str, currency: Currency, locale: Locale) -> Result<Money, MoneyError> {
+        let mut text = input.trim();
+        let negative = text.starts_with(['-', '−']);
+        if negative {
+            text = text[text.chars().next().map_or(0, char::len_utf8)..].trim_start();
+        }
+        for affix in [currency.symbol.trim(), currency.code] {
+            text = text.strip_prefix(affix).unwrap_or(text).trim();
+            text = text.strip_suffix(affix).unwrap_or(text).trim();
+        }
+
+        let mut whole = String::new();
+        // Digits before each group separator seen so far.
+        let mut groups: Vec<String> = Vec::new();
+        let mut fraction: Option<String> = None;
+        for c in text.chars() {
+            if c == locale.decimal || (c == '.' && locale.group != '.') {
+                if fraction.is_some() {
+                    return Err(MoneyError::Invalid);
+                }
+                fraction = Some(String::new());
+            } else if c == locale.group || c.is_whitespace() {
+                if fraction.is_some() {
+                    return Err(MoneyError::Invalid);
+                }
+                groups.push(std::mem::take(&mut whole));
+            } else if c.is_ascii_digit() {
+                fraction.as_mut().unwrap_or(&mut whole).push(c);
+            } else {
+                return Err(MoneyError::Invalid);
+            }
+        }
+
+        if let Some((first, rest)) = groups.split_first()
+            && (!(1..=3).contains(&first.len()) || rest.iter().chain([&whole]).any(|group| group.len() != 3))
+        {
+            return Err(MoneyError::Invalid);
+        }
+        groups.push(whole);
+        let whole = groups.concat();
+        let fraction = fraction.unwrap_or_default();
+        if whole.is_empty() && fraction.is_empty() {
+            return Err(MoneyError::Invalid);
+        }
+        if fraction.len() > currency.exponent as usize {
+            return Err(MoneyError::TooPrecise(currency.exponent));
+        }
+
+        let digits = format!("{whole}{fraction:0<width$}", width = currency.exponent as usize);
+        let minor: i64 = digits.parse().map_err(|_| MoneyError::Overflow)?;
+        Ok(Money {
+            minor: if negative { -minor } else { minor },
+            currency,
+        })
+    }
+
+    fn digits(self, locale: Locale) -> String {
+        let scale = self.currency.scale().unsigned_abs();
+        let whole = (self.minor.unsigned_abs() / scale).to_string();
+        let mut out = String::new();
+        for (i, c) in whole.chars().enumerate() {
+            if i > 0 && (whole.len() - i).is_multiple_of(3) {
+                out.push(locale.group);
+            }
+            out.push(c);
+        }
+        if self.currency.exponent > 0 {
+            let fraction = self.minor.unsigned_abs() % scale;
+            out.push(locale.decimal);
+            out.push_str(&format!("{fraction:0width$}", width = self.currency.exponent as usize));
+        }
+        out
+    }
+
+    fn sign(self) -> &'static str {
+        if self.is_negative() { "-" } else { "" }
+    }
+
+    /// The number alone, e.g. `1.234,50`, as it would be typed back in.
+    pub fn format_number(self, locale: Locale) -> String {
+        format!("{}{}", self.sign(), self.digits(locale))
+    }
+
+    /// The amount with its currency symbol, e.g. `-$1,234.50`.
+    pub fn format(self, locale: Locale) -> String {
+        format!("{}{}{}", self.sign(), self.currency.symbol, self.digits(locale))
+    }
+
+    /// Like `format`, but without a sign.
+    pub fn format_abs(self, locale: Locale) -> String {
+        format!("{}{}", self.currency.symbol, self.digits(locale))
+    }
+}
+
+/// Ledgers saved before amounts were exact hold a bare float in dollars;
+/// those are read back as USD.
+impl<'de> Deserialize<'de> for Money {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        #[derive(Deserialize)]
+        #[serde(untagged)]
+        enum Stored {
+            Exact { minor: i64, currency: Currency },
+            Legacy(f64),
+        }
+
+        Ok(match Stored::deserialize(deserializer)? {
+            Stored::Exact { minor, currency } => Money { minor, currency },
+            Stored::Legacy(amount) => Money {
+                minor: (amount * USD.scale() as f64).round() as i64,
+                currency: USD,
+            },
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EUR: Currency = Currency { code: "EUR", symbol: "€", exponent: 2 };
+    const JPY: Currency = Currency { code: "JPY", symbol: "¥", exponent: 0 };
+
+    fn locales() -> Vec<Locale> {
+        ["en-US", "de-DE", "fr-FR", "de-CH"].into_iter().map(Locale::from_tag).collect()
+    }
+
+    #[test]
+    fn formatted_amounts_parse_back_in_every_locale() {
+        for locale in locales() {
+            for currency in [USD, EUR, JPY] {
+                for minor in [0, 5, 1_250, -99_999, 123_456_789, -1_000_000_000_000] {
+                    let money = Money::from_minor(minor, currency);
+                    for text in [money.format(locale), money.format_number(locale)] {
+                        assert_eq!(Money::parse(&text, currency, locale), Ok(money), "{text:?} in {locale:?}");
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn formats_with_the_locale_separators() {
+        let money = Money::from_minor(123_456_789, EUR);
+        assert_eq!(money.format(Locale::from_tag("en-GB")), "€1,234,567.89");
+        assert_eq!(money.format(Locale::from_tag("de-DE")), "€1.234.567,89");
+        assert_eq!(money.format(Locale::from_tag("fr-FR")), "€1\u{a0}234\u{a0}567,89");
+        assert_eq!(money.format(Locale::from_tag("de-CH")), "€1'234'567.89");
+        assert_eq!(Money::from_minor(-1_234, JPY).format(Locale::default()), "-¥1,234");
+    }
+
+    #[test]
+    fn parses_common_input() {
+        let en = Locale::default();
+        let de = Locale::from_tag("de");
+        let fr = Locale::from_tag("fr");
+        assert_eq!(Money::parse("1,234.5", USD, en), Ok(Money::from_minor(123_450, USD)));
+        assert_eq!(Money::parse("1234.5", USD, en), Ok(Money::from_minor(123_450, USD)));
+        assert_eq!(Money::parse("-$12", USD, en), Ok(Money::from_minor(-1_200, USD)));
+        assert_eq!(Money::parse("1.234,50 €", EUR, de), Ok(Money::from_minor(123_450, EUR)));
+        assert_eq!(Money::parse("1.234", EUR, de), Ok(Money::from_minor(123_400, EUR)));
+        assert_eq!(Money::parse("1 234,5", EUR, fr), Ok(Money::from_minor(123_450, EUR)));
+        assert_eq!(Money::parse("12.5", EUR, fr), Ok(Money::from_minor(1_250, EUR)));
+    }
+
+    #[test]
+    fn rejects_misplaced_group_separators() {
+        let en = Locale::default();
+        let de = Locale::from_tag("de");
+        for text in ["12,50", "1,2345", ",123", "1,234,", "1,,234", "1234,567", "1.234,5"] {
+            assert_eq!(Money::parse(text, USD, en), Err(MoneyError::Invalid), "{text:?}");
+        }
+        for text in ["12.5", "1.2345", "1.234.5", "1,234.5"] {
+            assert_eq!(Money::parse(text, EUR, de), Err(MoneyError::Invalid), "{text:?}");
+        }
+        assert_eq!(Money::parse("1.234", USD, en), Err(MoneyError::TooPrecise(2)));
+        assert_eq!(Money::parse("12.5", JPY, en), Err(MoneyError::TooPrecise(0)));
+    }
+}
@@ -0,0 +1,418 @@
+use chrono::{DateTime, Datelike, Local, Months, NaiveDate, NaiveTime, TimeZone};
+use leptos::prelude::*;
+
+use crate::money::{Currency, Locale, Money, MoneyError};
+use crate::{Transaction, TransactionType};
+
+/// Comparisons show at most this many of the latest months.
+const MAX_REPORT_MONTHS: usize = 12;
+
+pub fn month_start(date: NaiveDate) -> NaiveDate {
+    date.with_day(1).unwrap_or(date)
+}
+
+pub fn this_month() -> NaiveDate {
+    month_start(today())
+}
+
+pub fn today() -> NaiveDate {
+    Local::now().date_naive()
+}
+
+pub fn add_months(month: NaiveDate, offset: i32) -> NaiveDate {
+    let shifted = if offset >= 0 {
+        month.checked_add_months(Months::new(offset as u32))
+    } else {
+        month.checked_sub_months(Months::new(offset.unsigned_abs()))
+    };
+    shifted.unwrap_or(month)
+}
+
+/// Midday on `date`, so the day survives a later change of time zone.
+pub fn local_date(date: NaiveDate) -> DateTime<Local> {
+    let noon = date.and_time(NaiveTime::from_hms_opt(12, 0, 0).unwrap_or(NaiveTime::MIN));
+    Local.from_local_datetime(&noon).earliest().unwrap_or_else(Local::now)
+}
+
+/// An inclusive range of days; a missing end is unbounded.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct DateRange {
+    pub from: Option<NaiveDate>,
+    pub to: Option<NaiveDate>,
+}
+
+impl DateRange {
+    pub fn contains(&self, date: NaiveDate) -> bool {
+        self.from.is_none_or(|from| date >= from) && self.to.is_none_or(|to| date <= to)
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Preset {
+    AllTime,
+    ThisMonth,
+    LastMonth,
+    ThisQuarter,
+    LastQuarter,
+    YearToDate,
+    LastTwelveMonths,
+    Custom,
+}
+
+impl Preset {
+    pub const ALL: [Preset; 8] = [
+        Preset::AllTime,
+        Preset::ThisMonth,
+        Preset::LastMonth,
+        Preset::ThisQuarter,
+        Preset::LastQuarter,
+        Preset::YearToDate,
+        Preset::LastTwelveMonths,
+        Preset::Custom,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Preset::AllTime => "All time",
+            Preset::ThisMonth => "This month",
+            Preset::LastMonth => "Last month",
+            Preset::ThisQuarter => "This quarter",
+            Preset::LastQuarter => "Last quarter",
+            Preset::YearToDate => "Year to date",
+            Preset::LastTwelveMonths => "Last 12 months",
+            Preset::Custom => "Custom range",
+        }
+    }
+
+    /// The range as of `today`, or `None` for `Custom`.
+    pub fn range(self, today: NaiveDate) -> Option<DateRange> {
+        let month = month_start(today);
+        let quarter = add_months(month, -(month.month0() as i32 % 3));
+        let span = |from: NaiveDate, months: i32| DateRange {
+            from: Some(from),
+            to: add_months(from, months).pred_opt(),
+        };
+        Some(match self {
+            Preset::AllTime => DateRange::default(),
+            Preset::ThisMonth => span(month, 1),
+            Preset::LastMonth => span(add_months(month, -1), 1),
+            Preset::ThisQuarter => span(quarter, 3),
+            Preset::LastQuarter => span(add_months(quarter, -3), 3),
+            Preset::YearToDate => DateRange {
+                from: today.with_ordinal(1),
+                to: Some(today),
+            },
+            Preset::LastTwelveMonths => span(add_months(month, -11), 12),
+            Preset::Custom => return None,
+        })
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct MonthSummary {
+    pub month: NaiveDate,
+    pub income: Money,
+    pub expenses: Money,
+}
+
+impl MonthSummary {
+    pub fn net(&self) -> Result<Money, MoneyError> {
+        self.income.checked_sub(self.expenses)
+    }
+}
+
+/// Percentage change from `previous` to `current`, if there was a previous
+/// amount to compare with.
+pub fn change(previous: Money, current: Money) -> Option<f64> {
+    (previous.minor != 0).then(|| (current.minor as f64 - previous.minor as f64) / previous.minor.abs() as f64 * 100.0)
+}
+
+/// Income and expenses per calendar month within `range`, oldest first.
+/// `amounts` are already converted into `currency`. Months with no
+/// transactions are included so the comparison has no gaps, up to the month
+/// of `today` or of the last transaction, whichever is later.
+pub fn monthly_summaries(
+    amounts: &[(Transaction, Money)],
+    range: DateRange,
+    currency: Currency,
+    today: NaiveDate,
+) -> Result<Vec<MonthSummary>, MoneyError> {
+    let dates = amounts.iter().map(|(t, _)| t.date.date_naive()).filter(|d| range.contains(*d));
+    let (Some(first), Some(last)) = (dates.clone().min(), dates.max()) else {
+        return Ok(Vec::new());
+    };
+    let first = month_start(range.from.unwrap_or(first));
+    // A range running into the future stops at the current month.
+    let last = month_start(range.to.unwrap_or(last).min(last.max(today)));
+
+    let mut months = Vec::new();
+    let mut month = first;
+    while month <= last {
+        months.push(MonthSummary {
+            month,
+            income: Money::zero(currency),
+            expenses: Money::zero(currency),
+        });
+        month = add_months(month, 1);
+    }
+    for (t, amount) in amounts {
+        let date = t.date.date_naive();
+        if !range.contains(date) {
+            continue;
+        }
+        if let Some(summary) = months.iter_mut().find(|s| s.month == month_start(date)) {
+            match t.transaction_type {
+                TransactionType::Income => summary.income = summary.income.checked_add(*amount)?,
+                TransactionType::Expense => summary.expenses = summary.expenses.checked_add(*amount)?,
+            }
+        }
+    }
+    Ok(months)
+}
+
+/// The months a report shows: the latest `MAX_REPORT_MONTHS`.
+pub fn latest_months(months: &[MonthSummary]) -> &[MonthSummary] {
+    &months[months.len().saturating_sub(MAX_REPORT_MONTHS)..]
+}
+
+fn change_cell(change: Option<f64>, higher_is_good: bool) -> impl IntoView {
+    let text = change.map_or("—".to_string(), |c| format!("{:+.0}%", c));
+    let good = change.is_some_and(|c| (c > 0.0) == higher_is_good && c != 0.0);
+    let bad = change.is_some_and(|c| (c > 0.0) != higher_is_good && c != 0.0);
+    view! { <td class="change" class:positive=good class:negative=bad>{text}</td> }
+}
+
+#[component]
+pub fn MonthOverMonth(
+    #[prop(into)] months: Signal<Result<Vec<MonthSummary>, MoneyError>>,
+    locale: Locale,
+) -> impl IntoView {
+    let rows = move || match months.get() {
+        Err(err) => view! { <tr><td colspan="6">{err.to_string()}</td></tr> }.into_any(),
+        Ok(months) if months.is_empty() => {
+            view! { <tr><td colspan="6">"No transactions in this period"</td></tr> }.into_any()
+        }
+        Ok(months) => {
+            // The first month shown is still compared with the one before it.
+            let shown = latest_months(&months).len();
+            let mut previous: Option<MonthSummary> = None;
+            let rows: Vec<_> = months
+                .into_iter()
+                .map(|summary| {
+                    let income_change = previous.as_ref().and_then(|p| change(p.income, summary.income));
+                    let expense_change = previous.as_ref().and_then(|p| change(p.expenses, summary.expenses));
+                    let net = summary.net();
+                    let row = view! {
+                        <tr>
+                            <th scope="row">{summary.month.format("%b %Y").to_string()}</th>
+                            <td class="income">{summary.income.format(locale)}</td>
+                            {change_cell(income_change, true)}
+                            <td class="expense">{summary.expenses.format(locale)}</td>
+                            {change_cell(expense_change, false)}
+                            <td
+                                class:positive=net.as_ref().is_ok_and(|n| !n.is_negative())
+                                class:negative=net.as_ref().is_ok_and(|n| n.is_negative())
+                            >
+                                {match &net {
+                                    Ok(net) => net.format(locale),
+                                    Err(err) => err.to_string(),
+                                }}
+                            </td>
+                        </tr>
+                    };
+                    previous = Some(summary);
+                    row
+                })
+                .collect();
+            let skip = rows.len() - shown;
+            rows.into_iter().skip(skip).collect_view().into_any()
+        }
+    };
+
+    // Income, expenses and net over the whole period, including any months
+    // too early to be listed.
+    let totals = move || {
+        let months = months.get().ok()?;
+        let currency = months.first()?.income.currency;
+        let label = if months.len() > latest_months(&months).len() {
+            format!("Total, all {} months", months.len())
+        } else {
+            "Total".to_string()
+        };
+        let income = Money::sum(currency, months.iter().map(|m| m.income)).ok()?;
+        let expenses = Money::sum(currency, months.iter().map(|m| m.expenses)).ok()?;
+        let net = income.checked_sub(expenses).ok()?;
+        Some(view! {
+            <tr>
+                <th scope="row">{label}</th>
+                <td class="income">{income.format(locale)}</td>
+                <td></td>
+                <td class="expense">{expenses.format(locale)}</td>
+                <td></td>
+                <td class:positive=!net.is_negative() class:negative=net.is_negative()>{net.format(locale)}</td>
+            </tr>
+        })
+    };
+
+    view! {
+        <div class="period-report">
+            <h3>"Month over Month"</h3>
+            <table>
+                <thead>
+                    <tr>
+                        <th scope="col">"Month"</th>
+                        <th scope="col">"Income"</th>
+                        <th scope="col">"Change"</th>
+                        <th scope="col">"Expenses"</th>
+                        <th scope="col">"Change"</th>
+                        <th scope="col">"Net"</th>
+                    </tr>
+                </thead>
+                <tbody>{rows}</tbody>
+                <tfoot>{totals}</tfoot>
+            </table>
+        </div>
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::money::USD;
+
+    fn date(y: i32, m: u32, d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(y, m, d).unwrap()
+    }
+
+    fn range(from: NaiveDate, to: NaiveDate) -> Option<DateRange> {
+        Some(DateRange {
+            from: Some(from),
+            to: Some(to),
+        })
+    }
+
+    fn usd(minor: i64) -> Money {
+        Money::from_minor(minor, USD)
+    }
+
+    fn entry(on: NaiveDate, transaction_type: TransactionType, minor: i64) -> (Transaction, Money) {
+        let transaction = Transaction {
+            amount: usd(minor),
+            date: local_date(on),
+            transaction_type,
+            ..Default::default()
+        };
+        (transaction, usd(minor))
+    }
+
+    fn summary(month: NaiveDate, income: i64, expenses: i64) -> MonthSummary {
+        MonthSummary {
+            month,
+            income: usd(income),
+            expenses: usd(expenses),
+        }
+    }
+
+    #[test]
+    fn presets_cover_whole_months_and_quarters() {
+        let today = date(2024, 2, 29);
+        assert_eq!(Preset::AllTime.range(today), Some(DateRange::default()));
+        assert_eq!(Preset::ThisMonth.range(today), range(date(2024, 2, 1), date(2024, 2, 29)));
+        assert_eq!(Preset::LastMonth.range(today), range(date(2024, 1, 1), date(2024, 1, 31)));
+        assert_eq!(Preset::ThisQuarter.range(today), range(date(2024, 1, 1), date(2024, 3, 31)));
+        // Last quarter of the previous year.
+        assert_eq!(Preset::LastQuarter.range(today), range(date(2023, 10, 1), date(2023, 12, 31)));
+        assert_eq!(Preset::YearToDate.range(today), range(date(2024, 1, 1), date(2024, 2, 29)));
+        assert_eq!(Preset::LastTwelveMonths.range(today), range(date(2023, 3, 1), date(2024, 2, 29)));
+        assert_eq!(Preset::Custom.range(today), None);
+
+        let today = date(2024, 12, 31);
+        assert_eq!(Preset::LastQuarter.range(today), range(date(2024, 7, 1), date(2024, 9, 30)));
+        assert_eq!(Preset::ThisQuarter.range(today), range(date(2024, 10, 1), date(2024, 12, 31)));
+        assert_eq!(Preset::YearToDate.range(date(2025, 1, 1)), range(date(2025, 1, 1), date(2025, 1, 1)));
+        assert_eq!(Preset::LastTwelveMonths.range(today), range(date(2024, 1, 1), date(2024, 12, 31)));
+    }
+
+    #[test]
+    fn summaries_include_empty_months() {
+        let amounts = [
+            entry(date(2024, 1, 15), TransactionType::Income, 1000),
+            entry(date(2024, 1, 20), TransactionType::Expense, 300),
+            entry(date(2024, 4, 2), TransactionType::Expense, 50),
+        ];
+        let months = monthly_summaries(&amounts, DateRange::default(), USD, date(2024, 4, 30)).unwrap();
+        assert_eq!(
+            months,
+            vec![
+                summary(date(2024, 1, 1), 1000, 300),
+                summary(date(2024, 2, 1), 0, 0),
+                summary(date(2024, 3, 1), 0, 0),
+                summary(date(2024, 4, 1), 0, 50),
+            ]
+        );
+        assert_eq!(months[0].net(), Ok(usd(700)));
+        assert_eq!(monthly_summaries(&[], DateRange::default(), USD, date(2024, 4, 30)), Ok(Vec::new()));
+    }
+
+    #[test]
+    fn summaries_stay_within_the_range_and_stop_at_this_month() {
+        let amounts = [
+            entry(date(2023, 12, 31), TransactionType::Income, 1),
+            entry(date(2024, 2, 10), TransactionType::Expense, 200),
+            entry(date(2024, 3, 1), TransactionType::Income, 5),
+            entry(date(2024, 8, 1), TransactionType::Income, 7),
+        ];
+        let year = DateRange {
+            from: Some(date(2024, 1, 1)),
+            to: Some(date(2024, 12, 31)),
+        };
+        // A scheduled August entry extends the months; the range's end doesn't.
+        let months = monthly_summaries(&amounts, year, USD, date(2024, 3, 15)).unwrap();
+        let listed: Vec<NaiveDate> = months.iter().map(|m| m.month).collect();
+        assert_eq!(listed.first(), Some(&date(2024, 1, 1)));
+        assert_eq!(listed.last(), Some(&date(2024, 8, 1)));
+        assert_eq!(months[0], summary(date(2024, 1, 1), 0, 0));
+
+        let early = [entry(date(2024, 2, 10), TransactionType::Expense, 200)];
+        let months = monthly_summaries(&early, year, USD, date(2024, 3, 15)).unwrap();
+        assert_eq!(
+            months,
+            vec![
+                summary(date(2024, 1, 1), 0, 0),
+                summary(date(2024, 2, 1), 0, 200),
+                summary(date(2024, 3, 1), 0, 0),
+            ]
+        );
+
+        let february = DateRange {
+            from: Some(date(2024, 2, 1)),
+            to: Some(date(2024, 2, 29)),
+        };
+        assert_eq!(
+            monthly_summaries(&amounts, february, USD, date(2024, 3, 15)),
+            Ok(vec![summary(date(2024, 2, 1), 0, 200)])
+        );
+    }
+
+    #[test]
+    fn change_is_relative_to_the_size_of_the_previous_amount() {
+        assert_eq!(change(usd(100), usd(150)), Some(50.0));
+        assert_eq!(change(usd(200), usd(100)), Some(-50.0));
+        assert_eq!(change(usd(-100), usd(-50)), Some(50.0));
+        assert_eq!(change(usd(-100), usd(-200)), Some(-100.0));
+        assert_eq!(change(usd(0), usd(100)), None);
+        assert_eq!(change(usd(100), usd(0)), Some(-100.0));
+    }
+
+    #[test]
+    fn latest_months_keeps_the_newest_twelve() {
+        let months: Vec<MonthSummary> =
+            (0..15).map(|i| summary(add_months(date(2023, 1, 1), i), 0, 0)).collect();
+        let latest = latest_months(&months);
+        assert_eq!(latest.len(), MAX_REPORT_MONTHS);
+        assert_eq!(latest[0].month, date(2023, 4, 1));
+        assert_eq!(latest[11].month, date(2024, 3, 1));
+        assert_eq!(latest_months(&months[..3]), &months[..3]);
+        assert!(latest_months(&[]).is_empty());
+    }
+}
@@ -0,0 +1,484 @@
+use chrono::{Local, NaiveDate};
+use leptos::prelude::*;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+use wasm_bindgen_futures::JsFuture;
+
+use crate::money::{Currency, Money, MoneyError, CURRENCIES, USD};
+
+const BASE_CURRENCY_KEY: &str = "lesson-06.base-currency";
+
+/// Rates are kept to this many decimal places so conversions stay within
+/// `i128`.
+const MAX_RATE_DECIMALS: usize = 10;
+
+/// One unit of `from` buys `rate` units of `to`, from `effective` onward.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ExchangeRate {
+    #[serde(default = "new_id")]
+    pub id: String,
+    pub effective: NaiveDate,
+    pub from: Currency,
+    pub to: Currency,
+    /// Decimal text exactly as entered, e.g. `1.0854`.
+    pub rate: String,
+}
+
+fn new_id() -> String {
+    Uuid::new_v4().to_string()
+}
+
+/// A positive rate held exactly as `num / den`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Rate {
+    num: i128,
+    den: i128,
+}
+
+impl Rate {
+    pub const ONE: Rate = Rate { num: 1, den: 1 };
+
+    pub fn parse(text: &str) -> Option<Rate> {
+        let text = text.trim();
+        let (whole, fraction) = text.split_once('.').unwrap_or((text, ""));
+        let all_digits = |s: &str| s.chars().all(|c| c.is_ascii_digit());
+        if whole.len() > 12 || fraction.len() > MAX_RATE_DECIMALS || !all_digits(whole) || !all_digits(fraction) {
+            return None;
+        }
+        let num: i128 = format!("{whole}{fraction}").parse().ok()?;
+        let rate = Rate {
+            num,
+            den: 10i128.pow(fraction.len() as u32),
+        };
+        (num > 0).then_some(rate)
+    }
+
+    pub fn inverse(self) -> Rate {
+        Rate {
+            num: self.den,
+            den: self.num,
+        }
+    }
+
+    /// Converts to `to`, rounding half away from zero to its minor unit.
+    pub fn convert(self, amount: Money, to: Currency) -> Result<Money, MoneyError> {
+        let n = (amount.minor as i128)
+            .checked_mul(self.num)
+            .and_then(|n| n.checked_mul(10i128.pow(to.exponent)))
+            .ok_or(MoneyError::Overflow)?;
+        let d = self
+            .den
+            .checked_mul(10i128.pow(amount.currency.exponent))
+            .ok_or(MoneyError::Overflow)?;
+        let rounded = n
+            .abs()
+            .checked_mul(2)
+            .and_then(|twice| twice.checked_add(d))
+            .ok_or(MoneyError::Overflow)?
+            / (2 * d)
+            * n.signum();
+        let minor = i64::try_from(rounded).map_err(|_| MoneyError::Overflow)?;
+        Ok(Money::from_minor(minor, to))
+    }
+
+    /// Up to six decimal places, trailing zeros trimmed.
+    pub fn format(self) -> String {
+        let scaled = (self.num * 2_000_000 + self.den) / (2 * self.den);
+        let text = format!("{}.{:06}", scaled / 1_000_000, scaled % 1_000_000);
+        text.trim_end_matches('0').trim_end_matches('.').to_string()
+    }
+}
+
+/// The rate behind one conversion, shown next to the converted amount.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Applied {
+    pub rate: Rate,
+    pub effective: Option<NaiveDate>,
+}
+
+impl Applied {
+    pub fn describe(&self, from: Currency, to: Currency) -> String {
+        match self.effective {
+            Some(date) => format!(
+                "1 {} = {} {} ({})",
+                from.code,
+                self.rate.format(),
+                to.code,
+                date.format("%b %d, %Y")
+            ),
+            None => String::new(),
+        }
+    }
+}
+
+/// The newest rate between the two currencies in effect on `on`. A rate
+/// entered the other way round is inverted; on the same date the direct
+/// rate wins.
+pub fn lookup(rates: &[ExchangeRate], from: Currency, to: Currency, on: NaiveDate) -> Option<Applied> {
+    if from == to {
+        return Some(Applied {
+            rate: Rate::ONE,
+            effective: None,
+        });
+    }
+    rates
+        .iter()
+        .filter(|r| r.effective <= on)
+        .filter_map(|r| {
+            let rate = Rate::parse(&r.rate)?;
+            if r.from == from && r.to == to {
+                Some((r.effective, true, rate))
+            } else if r.from == to && r.to == from {
+                Some((r.effective, false, rate.inverse()))
+            } else {
+                None
+            }
+        })
+        .max_by_key(|(effective, direct, _)| (*effective, *direct))
+        .map(|(effective, _, rate)| Applied {
+            rate,
+            effective: Some(effective),
+        })
+}
+
+pub fn convert(
+    rates: &[ExchangeRate],
+    amount: Money,
+    to: Currency,
+    on: NaiveDate,
+) -> Result<(Money, Applied), MoneyError> {
+    let applied = lookup(rates, amount.currency, to, on)
+        .ok_or(MoneyError::MissingRate(amount.currency.code, to.code))?;
+    Ok((applied.rate.convert(amount, to)?, applied))
+}
+
+fn parse_rate(effective: &str, from: &str, to: &str, rate: &str) -> Result<ExchangeRate, String> {
+    let effective = NaiveDate::parse_from_str(effective.trim(), "%Y-%m-%d")
+        .map_err(|_| format!("Invalid date {:?}, expected YYYY-MM-DD", effective.trim()))?;
+    let currency = |code: &str| Currency::from_code(code.trim()).ok_or_else(|| format!("Unknown currency {:?}", code.trim()));
+    let (from, to) = (currency(from)?, currency(to)?);
+    if from == to {
+        return Err(format!("{} to itself is not a rate", from.code));
+    }
+    Rate::parse(rate).ok_or_else(|| format!("Invalid rate {:?}", rate.trim()))?;
+    Ok(ExchangeRate {
+        id: new_id(),
+        effective,
+        from,
+        to,
+        rate: rate.trim().to_string(),
+    })
+}
+
+/// Reads either a JSON array of rates or CSV lines of
+/// `effective,from,to,rate`, with an optional header row.
+pub fn import(text: &str) -> Result<Vec<ExchangeRate>, String> {
+    if text.trim_start().starts_with('[') {
+        let rates: Vec<ExchangeRate> = serde_json::from_str(text).map_err(|e| format!("Invalid rates file: {}", e))?;
+        return rates
+            .into_iter()
+            .map(|r| parse_rate(&r.effective.to_string(), r.from.code, r.to.code, &r.rate))
+            .collect();
+    }
+    text.lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .filter(|(i, line)| !(*i == 0 && line.to_ascii_lowercase().contains("rate")))
+        .map(|(i, line)| match line.split(',').collect::<Vec<_>>()[..] {
+            [effective, from, to, rate] => {
+                parse_rate(effective, from, to, rate).map_err(|err| format!("Line {}: {}", i + 1, err))
+            }
+            _ => Err(format!("Line {}: expected effective,from,to,rate", i + 1)),
+        })
+        .collect()
+}
+
+fn local_storage() -> Option<web_sys::Storage> {
+    window().local_storage().ok().flatten()
+}
+
+pub fn load_base_currency() -> Currency {
+    local_storage()
+        .and_then(|storage| storage.get_item(BASE_CURRENCY_KEY).ok().flatten())
+        .and_then(|code| Currency::from_code(&code))
+        .unwrap_or(USD)
+}
+
+pub fn save_base_currency(currency: Currency) {
+    if let Some(storage) = local_storage() {
+        let _ = storage.set_item(BASE_CURRENCY_KEY, currency.code);
+    }
+}
+
+async fn read_file(file: web_sys::File) -> Result<String, String> {
+    JsFuture::from(file.text())
+        .await
+        .ok()
+        .and_then(|value| value.as_string())
+        .ok_or_else(|| format!("Could not read {}", file.name()))
+}
+
+pub fn currency_options(selected: Signal<Currency>) -> impl IntoView {
+    CURRENCIES
+        .iter()
+        .map(|currency| {
+            let currency = *currency;
+            view! {
+                <option value=currency.code selected=move || selected.get() == currency>
+                    {currency.code}
+                </option>
+            }
+        })
+        .collect_view()
+}
+
+#[component]
+pub fn ExchangeRates(rates: ReadSignal<Vec<ExchangeRate>>, set_rates: WriteSignal<Vec<ExchangeRate>>) -> impl IntoView {
+    let (effective, set_effective) = signal(Local::now().date_naive().to_string());
+    let (from, set_from) = signal(Currency::from_code("EUR").unwrap_or(USD));
+    let (to, set_to) = signal(USD);
+    let (rate, set_rate) = signal(String::new());
+    let (message, set_message) = signal::<Option<String>>(None);
+
+    let add_rate = move |_| {
+        match parse_rate(&effective.get(), from.get().code, to.get().code, &rate.get()) {
+            Ok(new_rate) => {
+                set_rates.update(|rates| rates.push(new_rate));
+                set_rate.set(String::new());
+                set_message.set(None);
+            }
+            Err(err) => set_message.set(Some(err)),
+        }
+    };
+
+    let import_rates = move |ev: leptos::ev::Event| {
+        let input: web_sys::HtmlInputElement = event_target(&ev);
+        let Some(file) = input.files().and_then(|files| files.get(0)) else {
+            return;
+        };
+        input.set_value("");
+        leptos::task::spawn_local(async move {
+            match read_file(file).await.and_then(|text| import(&text)) {
+                Ok(imported) => {
+                    set_message.set(Some(format!("Imported {} rates", imported.len())));
+                    set_rates.update(|rates| rates.extend(imported));
+                }
+                Err(err) => set_message.set(Some(err)),
+            }
+        });
+    };
+
+    let sorted_rates = move || {
+        let mut sorted = rates.get();
+        sorted.sort_by(|a, b| b.effective.cmp(&a.effective).then(a.from.code.cmp(b.from.code)));
+        sorted
+    };
+
+    view! {
+        <div class="rates-section">
+            <h2>"Exchange Rates"</h2>
+            <div class="rates-form">
+                <input
+                    type="date"
+                    prop:value=effective
+                    on:input=move |ev| set_effective.set(event_target_value(&ev))
+                />
+                <span>"1"</span>
+                <select on:change=move |ev| {
+                    if let Some(currency) = Currency::from_code(&event_target_value(&ev)) {
+                        set_from.set(currency);
+                    }
+                }>
+                    {currency_options(from.into())}
+                </select>
+                <span>"="</span>
+                <input
+                    type="text"
+                    inputmode="decimal"
+                    placeholder="1.0854"
+                    prop:value=rate
+                    on:input=move |ev| set_rate.set(event_target_value(&ev))
+                />
+                <select on:change=move |ev| {
+                    if let Some(currency) = Currency::from_code(&event_target_value(&ev)) {
+                        set_to.set(currency);
+                    }
+                }>
+                    {currency_options(to.into())}
+                </select>
+                <button class="submit-btn" on:click=add_rate>"Add Rate"</button>
+                <label class="reset-btn file-btn">
+                    "Import…"
+                    <input type="file" accept=".csv,.json,text/csv,application/json" on:change=import_rates />
+                </label>
+            </div>
+            {move || message.get().map(|message| view! { <p class="rates-message">{message}</p> })}
+            <ul class="rates-list">
+                <For
+                    each=sorted_rates
+                    key=|rate| rate.id.clone()
+                    children=move |rate| {
+                        let id = rate.id.clone();
+                        view! {
+                            <li>
+                                <span class="date">{rate.effective.format("%b %d, %Y").to_string()}</span>
+                                <span>{format!("1 {} = {} {}", rate.from.code, rate.rate, rate.to.code)}</span>
+                                <button
+                                    class="delete-btn"
+                                    on:click=move |_| set_rates.update(|rates| rates.retain(|r| r.id != id))
+                                >
+                                    "Delete"
+                                </button>
+                            </li>
+                        }
+                    }
+                />
+            </ul>
+        </div>
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn currency(code: &str) -> Currency {
+        Currency::from_code(code).unwrap()
+    }
+
+    fn date(m: u32, d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(2024, m, d).unwrap()
+    }
+
+    fn rate(effective: NaiveDate, from: &str, to: &str, rate: &str) -> ExchangeRate {
+        ExchangeRate {
+            id: format!("{from}-{to}-{effective}"),
+            effective,
+            from: currency(from),
+            to: currency(to),
+            rate: rate.to_string(),
+        }
+    }
+
+    fn money(minor: i64, code: &str) -> Money {
+        Money::from_minor(minor, currency(code))
+    }
+
+    #[test]
+    fn parse_accepts_positive_decimals_only() {
+        assert_eq!(Rate::parse("1.0854"), Some(Rate { num: 10854, den: 10000 }));
+        assert_eq!(Rate::parse(" 2 "), Some(Rate { num: 2, den: 1 }));
+        assert_eq!(Rate::parse(".5"), Some(Rate { num: 5, den: 10 }));
+        assert_eq!(Rate::parse("0.0000000001"), Some(Rate { num: 1, den: 10_000_000_000 }));
+        for text in ["", ".", "0", "0.000", "-1", "+1", "1e3", "1,5", "abc", "0.00000000001", "1234567890123"] {
+            assert_eq!(Rate::parse(text), None, "{:?}", text);
+        }
+    }
+
+    #[test]
+    fn format_rounds_to_six_places() {
+        let format = |text: &str| Rate::parse(text).unwrap().format();
+        assert_eq!(format("1.0854"), "1.0854");
+        assert_eq!(format("2"), "2");
+        assert_eq!(format("1.2345675"), "1.234568");
+        assert_eq!(format("0.0000004"), "0");
+        assert_eq!(format("0.0000005"), "0.000001");
+        assert_eq!(Rate::parse("1.0854").unwrap().inverse().format(), "0.921319");
+        assert_eq!(Rate::parse("3").unwrap().inverse().format(), "0.333333");
+    }
+
+    #[test]
+    fn convert_rounds_half_away_from_zero_in_the_target_currency() {
+        let convert = |rate: &str, amount: Money, to: &str| Rate::parse(rate).unwrap().convert(amount, currency(to));
+        assert_eq!(convert("1.0854", money(1000, "USD"), "EUR"), Ok(money(1085, "EUR")));
+        assert_eq!(convert("0.5", money(5, "USD"), "EUR"), Ok(money(3, "EUR")));
+        assert_eq!(convert("0.5", money(-5, "USD"), "EUR"), Ok(money(-3, "EUR")));
+        assert_eq!(convert("0.5", money(3, "USD"), "EUR"), Ok(money(2, "EUR")));
+        // Cents to whole yen and back.
+        assert_eq!(convert("150.5", money(1, "USD"), "JPY"), Ok(money(2, "JPY")));
+        assert_eq!(convert("150.5", money(101, "USD"), "JPY"), Ok(money(152, "JPY")));
+        assert_eq!(convert("0.005", money(1, "JPY"), "USD"), Ok(money(1, "USD")));
+        assert_eq!(convert("0.0049", money(1, "JPY"), "USD"), Ok(money(0, "USD")));
+        let yen_to_dollars = Rate::parse("150").unwrap().inverse();
+        assert_eq!(yen_to_dollars.convert(money(100, "JPY"), USD), Ok(money(67, "USD")));
+        assert_eq!(convert("1000", money(i64::MAX, "USD"), "EUR"), Err(MoneyError::Overflow));
+        assert_eq!(convert("0.001", money(i64::MIN, "JPY"), "USD"), Ok(money(-922337203685477581, "USD")));
+    }
+
+    #[test]
+    fn lookup_takes_the_latest_rate_in_effect() {
+        let (usd, eur) = (currency("USD"), currency("EUR"));
+        let rates = vec![
+            rate(date(1, 1), "USD", "EUR", "0.9"),
+            rate(date(2, 1), "EUR", "USD", "1.25"),
+            rate(date(3, 1), "EUR", "USD", "2"),
+            rate(date(3, 1), "USD", "EUR", "0.95"),
+            rate(date(4, 1), "USD", "EUR", "not a rate"),
+            rate(date(1, 1), "USD", "GBP", "0.8"),
+        ];
+        let applied = |from, to, on| lookup(&rates, from, to, on).map(|a| (a.rate, a.effective));
+
+        assert_eq!(applied(usd, eur, NaiveDate::from_ymd_opt(2023, 12, 31).unwrap()), None);
+        assert_eq!(applied(usd, eur, date(1, 1)), Some((Rate::parse("0.9").unwrap(), Some(date(1, 1)))));
+        assert_eq!(applied(usd, eur, date(1, 31)), Some((Rate::parse("0.9").unwrap(), Some(date(1, 1)))));
+        // Only the opposite direction is newer, so it is inverted.
+        assert_eq!(
+            applied(usd, eur, date(2, 15)),
+            Some((Rate::parse("1.25").unwrap().inverse(), Some(date(2, 1))))
+        );
+        // On the same day the direct rate wins, in either direction.
+        assert_eq!(applied(usd, eur, date(3, 1)), Some((Rate::parse("0.95").unwrap(), Some(date(3, 1)))));
+        assert_eq!(applied(eur, usd, date(3, 1)), Some((Rate::parse("2").unwrap(), Some(date(3, 1)))));
+        // An unreadable rate is skipped.
+        assert_eq!(applied(usd, eur, date(5, 1)), Some((Rate::parse("0.95").unwrap(), Some(date(3, 1)))));
+        assert_eq!(applied(eur, currency("GBP"), date(5, 1)), None);
+        assert_eq!(applied(eur, eur, date(5, 1)), Some((Rate::ONE, None)));
+
+        assert_eq!(
+            convert(&rates, money(1000, "USD"), eur, date(2, 15)).map(|(converted, _)| converted),
+            Ok(money(800, "EUR"))
+        );
+        assert_eq!(
+            convert(&rates, money(1000, "EUR"), currency("GBP"), date(2, 15)),
+            Err(MoneyError::MissingRate("EUR", "GBP"))
+        );
+    }
+
+    #[test]
+    fn import_reads_csv_and_json() {
+        let csv = "effective,from,to,rate\n2024-01-01,USD,EUR,0.9\n\n2024-02-01, eur , usd ,1.1\n";
+        let rates = import(csv).unwrap();
+        assert_eq!(rates.len(), 2);
+        assert_eq!((rates[1].effective, rates[1].from, rates[1].to), (date(2, 1), currency("EUR"), USD));
+        assert_eq!(rates[1].rate, "1.1");
+
+        let json = r#"[{"effective": "2024-01-01", "from": "USD", "to": "JPY", "rate": "150"}]"#;
+        let rates = import(json).unwrap();
+        assert_eq!((rates[0].to, rates[0].rate.as_str()), (currency("JPY"), "150"));
+        assert!(!rates[0].id.is_empty());
+    }
+
+    #[test]
+    fn import_errors_name_the_line_and_problem() {
+        for (text, expected) in [
+            ("2024-01-01,USD,EUR,0.9\n01/02/2024,USD,EUR,0.9", "Line 2: Invalid date \"01/02/2024\", expected YYYY-MM-DD"),
+            ("2024-01-01,USD,XXX,0.9", "Line 1: Unknown currency \"XXX\""),
+            ("2024-01-01,USD,usd,1", "Line 1: USD to itself is not a rate"),
+            ("2024-01-01,USD,EUR,-0.9", "Line 1: Invalid rate \"-0.9\""),
+            ("2024-01-01,USD,EUR", "Line 1: expected effective,from,to,rate"),
+            ("2024-01-01,USD,EUR,1,2", "Line 1: expected effective,from,to,rate"),
+        ] {
+            assert_eq!(import(text), Err(expected.to_string()));
+        }
+
+        let bad_rate = r#"[{"effective": "2024-01-01", "from": "USD", "to": "EUR", "rate": "0"}]"#;
+        assert_eq!(import(bad_rate), Err("Invalid rate \"0\"".to_string()));
+        for json in [
+            r#"[{"effective": "2024-01-01", "from": "USD", "to": "XXX", "rate": "1"}]"#,
+            r#"[{"effective": "2024-13-01", "from": "USD", "to": "EUR", "rate": "1"}]"#,
+            "[1, 2",
+        ] {
+            assert!(import(json).unwrap_err().starts_with("Invalid rates file: "), "{}", json);
+        }
+    }
+}
@@ -0,0 +1,637 @@
+use chrono::{Days, Months, NaiveDate};
+use leptos::prelude::*;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::import::UNCATEGORIZED;
+use crate::money::{Currency, Locale, Money, USD};
+use crate::period::{local_date, today};
+use crate::rates::currency_options;
+use crate::{Transaction, TransactionType};
+
+/// How many days ahead the upcoming list looks.
+const UPCOMING_DAYS: u64 = 30;
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub enum Frequency {
+    Weekly,
+    Biweekly,
+    Monthly,
+    Quarterly,
+    Yearly,
+}
+
+impl Frequency {
+    pub const ALL: [Frequency; 5] = [
+        Frequency::Weekly,
+        Frequency::Biweekly,
+        Frequency::Monthly,
+        Frequency::Quarterly,
+        Frequency::Yearly,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Frequency::Weekly => "Weekly",
+            Frequency::Biweekly => "Every two weeks",
+            Frequency::Monthly => "Monthly",
+            Frequency::Quarterly => "Quarterly",
+            Frequency::Yearly => "Yearly",
+        }
+    }
+
+    /// The `n`th date of a schedule starting on `start`. Counting from the
+    /// start each time means a schedule on the 31st returns to the 31st
+    /// after a shorter month, where it falls on the last day.
+    fn nth(self, start: NaiveDate, n: u32) -> Option<NaiveDate> {
+        match self {
+            Frequency::Weekly => start.checked_add_days(Days::new(7 * n as u64)),
+            Frequency::Biweekly => start.checked_add_days(Days::new(14 * n as u64)),
+            Frequency::Monthly => start.checked_add_months(Months::new(n)),
+            Frequency::Quarterly => start.checked_add_months(Months::new(3 * n)),
+            Frequency::Yearly => start.checked_add_months(Months::new(12 * n)),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub enum Change {
+    Skip,
+    Modify { date: NaiveDate, amount: Money },
+}
+
+/// A change to the single occurrence scheduled on `scheduled`.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Exception {
+    pub scheduled: NaiveDate,
+    pub change: Change,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Recurring {
+    pub id: String,
+    pub description: String,
+    pub amount: Money,
+    pub category: String,
+    pub transaction_type: TransactionType,
+    pub frequency: Frequency,
+    pub start: NaiveDate,
+    /// Last day an occurrence may be scheduled on, if the schedule ends.
+    pub end: Option<NaiveDate>,
+    /// Occurrences scheduled up to this date have been added or skipped.
+    pub materialized_through: Option<NaiveDate>,
+    /// Scheduled dates after `materialized_through` that were already added,
+    /// because a one-off change made them due before an earlier occurrence.
+    #[serde(default)]
+    pub posted_early: Vec<NaiveDate>,
+    pub exceptions: Vec<Exception>,
+}
+
+impl Default for Recurring {
+    fn default() -> Self {
+        Self {
+            id: Uuid::new_v4().to_string(),
+            description: String::new(),
+            amount: Money::zero(USD),
+            category: String::new(),
+            transaction_type: TransactionType::Expense,
+            frequency: Frequency::Monthly,
+            start: today(),
+            end: None,
+            materialized_through: None,
+            posted_early: Vec::new(),
+            exceptions: Vec::new(),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Occurrence {
+    pub recurring_id: String,
+    pub description: String,
+    pub transaction_type: TransactionType,
+    pub scheduled: NaiveDate,
+    /// When it happens, which a one-off change may have moved.
+    pub date: NaiveDate,
+    pub amount: Money,
+    pub skipped: bool,
+    pub modified: bool,
+}
+
+impl Recurring {
+    fn occurrence(&self, scheduled: NaiveDate) -> Occurrence {
+        let change = self.exceptions.iter().find(|e| e.scheduled == scheduled).map(|e| e.change);
+        let (date, amount) = match change {
+            Some(Change::Modify { date, amount }) => (date, amount),
+            _ => (scheduled, self.amount),
+        };
+        Occurrence {
+            recurring_id: self.id.clone(),
+            description: self.description.clone(),
+            transaction_type: self.transaction_type.clone(),
+            scheduled,
+            date,
+            amount,
+            skipped: change == Some(Change::Skip),
+            modified: matches!(change, Some(Change::Modify { .. })),
+        }
+    }
+
+    /// Every scheduled date, in order.
+    fn dates(&self) -> impl Iterator<Item = NaiveDate> + '_ {
+        (0..)
+            .map_while(|n| self.frequency.nth(self.start, n))
+            .take_while(|date| self.end.is_none_or(|end| *date <= end))
+    }
+
+    /// First scheduled date not yet added or skipped.
+    fn pending_from(&self) -> NaiveDate {
+        self.materialized_through.and_then(|d| d.succ_opt()).unwrap_or(self.start)
+    }
+
+    /// Occurrences not added yet, scheduled up to `to`, with one-off changes
+    /// applied.
+    pub fn pending(&self, to: NaiveDate) -> Vec<Occurrence> {
+        let from = self.pending_from();
+        self.dates()
+            .take_while(|date| *date <= to)
+            .filter(|date| *date >= from && !self.posted_early.contains(date))
+            .map(|date| self.occurrence(date))
+            .collect()
+    }
+
+    pub fn next(&self) -> Option<Occurrence> {
+        let from = self.pending_from();
+        self.dates()
+            .filter(|date| *date >= from && !self.posted_early.contains(date))
+            .map(|date| self.occurrence(date))
+            .find(|o| !o.skipped)
+    }
+
+    /// Records a one-off change, replacing any earlier one for the same
+    /// occurrence. `None` restores the occurrence as scheduled.
+    pub fn set_change(&mut self, scheduled: NaiveDate, change: Option<Change>) {
+        self.exceptions.retain(|e| e.scheduled != scheduled);
+        if let Some(change) = change {
+            self.exceptions.push(Exception { scheduled, change });
+        }
+    }
+
+    fn transaction(&self, occurrence: &Occurrence) -> Transaction {
+        Transaction {
+            description: self.description.clone(),
+            amount: occurrence.amount,
+            category: self.category.clone(),
+            date: local_date(occurrence.date),
+            transaction_type: self.transaction_type.clone(),
+            ..Default::default()
+        }
+    }
+}
+
+/// Adds every occurrence due by `today` as a transaction. Skipped
+/// occurrences are passed over once their date has come. Each schedule's
+/// `materialized_through` only moves past a run of handled occurrences, so
+/// one moved later holds it back while those after it still get added when
+/// due.
+pub fn materialize(schedules: &mut [Recurring], today: NaiveDate) -> Vec<Transaction> {
+    let mut due = Vec::new();
+    for schedule in schedules.iter_mut() {
+        // Looking past today finds occurrences that were moved earlier.
+        let horizon = schedule.exceptions.iter().map(|e| e.scheduled).max().unwrap_or(today).max(today);
+        let from = schedule.pending_from();
+        let dates: Vec<_> = schedule
+            .dates()
+            .take_while(|date| *date <= horizon)
+            .filter(|date| *date >= from)
+            .collect();
+        let mut contiguous = true;
+        for scheduled in dates {
+            let occurrence = schedule.occurrence(scheduled);
+            let handled = if schedule.posted_early.contains(&scheduled) {
+                true
+            } else if occurrence.skipped {
+                scheduled <= today
+            } else if occurrence.date <= today {
+                due.push(schedule.transaction(&occurrence));
+                if !contiguous {
+                    schedule.posted_early.push(scheduled);
+                }
+                true
+            } else {
+                false
+            };
+            contiguous &= handled;
+            if contiguous {
+                schedule.materialized_through = Some(scheduled);
+            }
+        }
+        let through = schedule.materialized_through;
+        schedule.posted_early.retain(|date| through.is_none_or(|through| *date > through));
+    }
+    due
+}
+
+#[component]
+pub fn RecurringEditor(
+    recurring: ReadSignal<Vec<Recurring>>,
+    set_recurring: WriteSignal<Vec<Recurring>>,
+    #[prop(into)] default_currency: Signal<Currency>,
+    locale: Locale,
+) -> impl IntoView {
+    let new_draft = move || Recurring {
+        amount: Money::zero(default_currency.get_untracked()),
+        ..Default::default()
+    };
+    let (draft, set_draft) = signal(new_draft());
+    let (amount_input, set_amount_input) = signal(String::new());
+    let (editing, set_editing) = signal(false);
+    let (error, set_error) = signal::<Option<String>>(None);
+    // The occurrence being changed, as (schedule id, scheduled date), with
+    // the values in its inputs.
+    let (changing, set_changing) = signal::<Option<(String, NaiveDate)>>(None);
+    let (change_date, set_change_date) = signal(String::new());
+    let (change_amount, set_change_amount) = signal(String::new());
+
+    let upcoming = move || {
+        let horizon = today() + Days::new(UPCOMING_DAYS);
+        let mut occurrences: Vec<_> = recurring.with(|schedules| {
+            schedules.iter().flat_map(|schedule| schedule.pending(horizon)).collect()
+        });
+        occurrences.sort_by_key(|o| (o.date, o.scheduled));
+        occurrences
+    };
+
+    let update_schedule = move |id: &str, update: &dyn Fn(&mut Recurring)| {
+        set_recurring.update(|schedules| {
+            if let Some(schedule) = schedules.iter_mut().find(|s| s.id == id) {
+                update(schedule);
+            }
+        });
+    };
+
+    let reset = move || {
+        set_draft.set(new_draft());
+        set_amount_input.set(String::new());
+        set_editing.set(false);
+        set_error.set(None);
+    };
+
+    // Editing a schedule keeps which occurrences were already added and any
+    // one-off changes.
+    let save = move |_| {
+        let mut schedule = draft.get();
+        if schedule.description.trim().is_empty() {
+            set_error.set(Some("Enter a description".to_string()));
+            return;
+        }
+        match Money::parse(&amount_input.get(), schedule.amount.currency, locale) {
+            Ok(amount) if amount.is_positive() => schedule.amount = amount,
+            Ok(_) => {
+                set_error.set(Some("Amount must be greater than zero".to_string()));
+                return;
+            }
+            Err(err) => {
+                set_error.set(Some(err.to_string()));
+                return;
+            }
+        }
+        if schedule.end.is_some_and(|end| end < schedule.start) {
+            set_error.set(Some("The schedule ends before it starts".to_string()));
+            return;
+        }
+        if schedule.category.trim().is_empty() {
+            schedule.category = UNCATEGORIZED.to_string();
+        }
+        set_recurring.update(|schedules| match schedules.iter_mut().find(|s| s.id == schedule.id) {
+            Some(existing) => *existing = schedule,
+            None => schedules.push(schedule),
+        });
+        reset();
+    };
+
+    let edit = move |schedule: Recurring| {
+        set_amount_input.set(schedule.amount.format_number(locale));
+        set_draft.set(schedule);
+        set_editing.set(true);
+        set_error.set(None);
+    };
+
+    let start_change = move |occurrence: Occurrence| {
+        set_change_date.set(occurrence.date.to_string());
+        set_change_amount.set(occurrence.amount.format_number(locale));
+        set_changing.set(Some((occurrence.recurring_id, occurrence.scheduled)));
+        set_error.set(None);
+    };
+
+    // Saving the scheduled values unchanged removes the one-off change.
+    let save_change = move |occurrence: Occurrence| {
+        let date = match NaiveDate::parse_from_str(&change_date.get(), "%Y-%m-%d") {
+            Ok(date) => date,
+            Err(_) => {
+                set_error.set(Some("Enter a date for this occurrence".to_string()));
+                return;
+            }
+        };
+        let amount = match Money::parse(&change_amount.get(), occurrence.amount.currency, locale) {
+            Ok(amount) if amount.is_positive() => amount,
+            Ok(_) => {
+                set_error.set(Some("Amount must be greater than zero".to_string()));
+                return;
+            }
+            Err(err) => {
+                set_error.set(Some(err.to_string()));
+                return;
+            }
+        };
+        update_schedule(&occurrence.recurring_id, &|schedule| {
+            let change = (date != occurrence.scheduled || amount != schedule.amount)
+                .then_some(Change::Modify { date, amount });
+            schedule.set_change(occurrence.scheduled, change);
+        });
+        set_changing.set(None);
+        set_error.set(None);
+    };
+
+    let toggle_skip = move |occurrence: Occurrence| {
+        update_schedule(&occurrence.recurring_id, &|schedule| {
+            let change = (!occurrence.skipped).then_some(Change::Skip);
+            schedule.set_change(occurrence.scheduled, change);
+        });
+    };
+
+    view! {
+        <div class="recurring-section">
+            <h2>"Recurring Transactions"</h2>
+            <p class="rules-hint">
+                "Each occurrence is added to your transactions on its date. Skip or change a single occurrence from the upcoming list."
+            </p>
+
+            <div class="rule-form">
+                <input
+                    type="text"
+                    placeholder="Description"
+                    prop:value=move || draft.get().description
+                    on:input=move |ev| set_draft.update(|s| s.description = event_target_value(&ev))
+                />
+                <input
+                    type="text"
+                    inputmode="decimal"
+                    placeholder="Amount"
+                    prop:value=amount_input
+                    on:input=move |ev| set_amount_input.set(event_target_value(&ev))
+                />
+                <select on:change=move |ev| {
+                    if let Some(currency) = Currency::from_code(&event_target_value(&ev)) {
+                        set_draft.update(|s| s.amount.currency = currency);
+                    }
+                }>
+                    {currency_options(Signal::derive(move || draft.get().amount.currency))}
+                </select>
+                <input
+                    type="text"
+                    placeholder="Category"
+                    list="categories"
+                    prop:value=move || draft.get().category
+                    on:input=move |ev| set_draft.update(|s| s.category = event_target_value(&ev))
+                />
+                <select on:change=move |ev| {
+                    let kind = if event_target_value(&ev) == "income" { TransactionType::Income } else { TransactionType::Expense };
+                    set_draft.update(|s| s.transaction_type = kind);
+                }>
+                    <option value="expense" selected=move || draft.get().transaction_type == TransactionType::Expense>
+                        "Expense"
+                    </option>
+                    <option value="income" selected=move || draft.get().transaction_type == TransactionType::Income>
+                        "Income"
+                    </option>
+                </select>
+                <select on:change=move |ev| {
+                    let value = event_target_value(&ev);
+                    if let Some(frequency) = Frequency::ALL.into_iter().find(|f| f.label() == value) {
+                        set_draft.update(|s| s.frequency = frequency);
+                    }
+                }>
+                    {Frequency::ALL.into_iter().map(|f| view! {
+                        <option value=f.label() selected=move || draft.get().frequency == f>{f.label()}</option>
+                    }).collect_view()}
+                </select>
+                <label>
+                    "From "
+                    <input
+                        type="date"
+                        prop:value=move || draft.get().start.to_string()
+                        on:input=move |ev| {
+                            if let Ok(date) = NaiveDate::parse_from_str(&event_target_value(&ev), "%Y-%m-%d") {
+                                set_draft.update(|s| s.start = date);
+                            }
+                        }
+                    />
+                </label>
+                <label>
+                    "Until "
+                    <input
+                        type="date"
+                        prop:value=move || draft.get().end.map(|d| d.to_string()).unwrap_or_default()
+                        on:input=move |ev| {
+                            let end = NaiveDate::parse_from_str(&event_target_value(&ev), "%Y-%m-%d").ok();
+                            set_draft.update(|s| s.end = end);
+                        }
+                    />
+                </label>
+                <button class="submit-btn" on:click=save>
+                    {move || if editing.get() { "Update Schedule" } else { "Add Schedule" }}
+                </button>
+                <Show when=move || editing.get()>
+                    <button class="reset-btn" on:click=move |_| reset()>"Cancel"</button>
+                </Show>
+            </div>
+            {move || error.get().map(|message| view! { <p class="form-error">{message}</p> })}
+
+            <div class="recurring-columns">
+                <div>
+                    <h3>"Upcoming"</h3>
+                    <ul class="upcoming-list">
+                        {move || {
+                            let occurrences = upcoming();
+                            if occurrences.is_empty() {
+                                return view! {
+                                    <li class="empty">{format!("Nothing due in the next {} days", UPCOMING_DAYS)}</li>
+                                }
+                                .into_any();
+                            }
+                            occurrences.into_iter().map(|occurrence| {
+                                let is_income = occurrence.transaction_type == TransactionType::Income;
+                                let is_changing = changing.get() == Some((occurrence.recurring_id.clone(), occurrence.scheduled));
+                                let (for_skip, for_change, for_save) = (occurrence.clone(), occurrence.clone(), occurrence.clone());
+                                let actions = if is_changing {
+                                    view! {
+                                        <input
+                                            type="date"
+                                            prop:value=change_date
+                                            on:input=move |ev| set_change_date.set(event_target_value(&ev))
+                                        />
+                                        <input
+                                            type="text"
+                                            inputmode="decimal"
+                                            prop:value=change_amount
+                                            on:input=move |ev| set_change_amount.set(event_target_value(&ev))
+                                        />
+                                        <button class="edit-btn" on:click=move |_| save_change(for_save.clone())>"Save"</button>
+                                        <button class="delete-btn" on:click=move |_| set_changing.set(None)>"Cancel"</button>
+                                    }
+                                    .into_any()
+                                } else {
+                                    view! {
+                                        <button class="edit-btn" on:click=move |_| toggle_skip(for_skip.clone())>
+                                            {if occurrence.skipped { "Restore" } else { "Skip" }}
+                                        </button>
+                                        {(!occurrence.skipped).then(|| view! {
+                                            <button class="edit-btn" on:click=move |_| start_change(for_change.clone())>
+                                                "Change"
+                                            </button>
+                                        })}
+                                    }
+                                    .into_any()
+                                };
+                                view! {
+                                    <li class:skipped=occurrence.skipped>
+                                        <span class="date">{occurrence.date.format("%b %d").to_string()}</span>
+                                        <span class="upcoming-description">
+                                            {occurrence.description.clone()}
+                                            {occurrence.modified.then(|| view! {
+                                                <span class="modified">
+                                                    {format!(" (changed from {})", occurrence.scheduled.format("%b %d"))}
+                                                </span>
+                                            })}
+                                        </span>
+                                        <span class:income=is_income class:expense=!is_income>
+                                            {if is_income { "+" } else { "-" }}
+                                            {occurrence.amount.format_abs(locale)}
+                                        </span>
+                                        <span class="upcoming-actions">{actions}</span>
+                                    </li>
+                                }
+                            }).collect_view().into_any()
+                        }}
+                    </ul>
+                </div>
+
+                <div>
+                    <h3>"Schedules"</h3>
+                    <ul class="schedule-list">
+                        {move || recurring.get().into_iter().map(|schedule| {
+                            let delete_id = schedule.id.clone();
+                            let schedule_for_edit = schedule.clone();
+                            let next = schedule.next().map_or("finished".to_string(), |o| format!("next {}", o.date.format("%b %d, %Y")));
+                            view! {
+                                <li>
+                                    <span class="rule-summary">
+                                        <strong>{schedule.description.clone()}</strong>
+                                        {format!(
+                                            " · {} · {} · {}",
+                                            schedule.amount.format(locale),
+                                            schedule.frequency.label(),
+                                            next,
+                                        )}
+                                    </span>
+                                    <button class="edit-btn" on:click=move |_| edit(schedule_for_edit.clone())>"Edit"</button>
+                                    <button
+                                        class="delete-btn"
+                                        on:click=move |_| set_recurring.update(|schedules| schedules.retain(|s| s.id != delete_id))
+                                    >
+                                        "Delete"
+                                    </button>
+                                </li>
+                            }
+                        }).collect_view()}
+                    </ul>
+                </div>
+            </div>
+        </div>
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(m: u32, d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(2024, m, d).unwrap()
+    }
+
+    fn usd(dollars: i64) -> Money {
+        Money::from_minor(dollars * 100, USD)
+    }
+
+    fn monthly(start: NaiveDate) -> Recurring {
+        Recurring {
+            description: "Rent".to_string(),
+            amount: usd(10),
+            start,
+            ..Default::default()
+        }
+    }
+
+    /// Materializes on `today` and lists the added transactions as
+    /// (date, dollars).
+    fn run(schedule: &mut Recurring, today: NaiveDate) -> Vec<(NaiveDate, i64)> {
+        materialize(std::slice::from_mut(schedule), today)
+            .into_iter()
+            .map(|t| (t.date.date_naive(), t.amount.minor / 100))
+            .collect()
+    }
+
+    #[test]
+    fn month_end_schedules_fall_on_the_last_day() {
+        let mut schedule = monthly(date(1, 31));
+        assert_eq!(
+            run(&mut schedule, date(4, 30)),
+            vec![(date(1, 31), 10), (date(2, 29), 10), (date(3, 31), 10), (date(4, 30), 10)]
+        );
+        assert_eq!(schedule.materialized_through, Some(date(4, 30)));
+        assert_eq!(run(&mut schedule, date(5, 30)), vec![]);
+        assert_eq!(run(&mut schedule, date(5, 31)), vec![(date(5, 31), 10)]);
+    }
+
+    #[test]
+    fn skipped_occurrences_are_passed_over_once_due() {
+        let mut schedule = monthly(date(1, 15));
+        schedule.set_change(date(2, 15), Some(Change::Skip));
+        schedule.set_change(date(4, 15), Some(Change::Skip));
+        assert_eq!(run(&mut schedule, date(3, 20)), vec![(date(1, 15), 10), (date(3, 15), 10)]);
+        // A skip still ahead can be undone, so it holds the schedule.
+        assert_eq!(schedule.materialized_through, Some(date(3, 15)));
+        assert_eq!(run(&mut schedule, date(4, 20)), vec![]);
+        assert_eq!(schedule.materialized_through, Some(date(4, 15)));
+    }
+
+    #[test]
+    fn an_occurrence_moved_earlier_is_added_on_its_new_date() {
+        let mut schedule = monthly(date(1, 15));
+        let change = Change::Modify { date: date(2, 1), amount: usd(20) };
+        schedule.set_change(date(3, 15), Some(change));
+        assert_eq!(run(&mut schedule, date(2, 5)), vec![(date(1, 15), 10), (date(2, 1), 20)]);
+        assert_eq!(schedule.materialized_through, Some(date(1, 15)));
+        assert_eq!(schedule.posted_early, vec![date(3, 15)]);
+        assert!(schedule.pending(date(3, 31)).iter().all(|o| o.scheduled != date(3, 15)));
+
+        assert_eq!(run(&mut schedule, date(2, 20)), vec![(date(2, 15), 10)]);
+        assert_eq!(schedule.materialized_through, Some(date(3, 15)));
+        assert!(schedule.posted_early.is_empty());
+        assert_eq!(run(&mut schedule, date(4, 14)), vec![]);
+    }
+
+    #[test]
+    fn an_occurrence_moved_later_does_not_hold_back_the_rest() {
+        let mut schedule = monthly(date(1, 15));
+        let change = Change::Modify { date: date(3, 20), amount: usd(10) };
+        schedule.set_change(date(2, 15), Some(change));
+        assert_eq!(run(&mut schedule, date(3, 16)), vec![(date(1, 15), 10), (date(3, 15), 10)]);
+        assert_eq!(schedule.materialized_through, Some(date(1, 15)));
+        assert_eq!(run(&mut schedule, date(3, 19)), vec![]);
+        assert_eq!(run(&mut schedule, date(3, 21)), vec![(date(3, 20), 10)]);
+        assert_eq!(schedule.materialized_through, Some(date(3, 15)));
+        assert!(schedule.posted_early.is_empty());
+    }
+}
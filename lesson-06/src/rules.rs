@@ -0,0 +1,468 @@
+use leptos::prelude::*;
+use regex::{Regex, RegexBuilder};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::money::{Currency, Locale, Money};
+use crate::{Transaction, TransactionType};
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub enum TextMatch {
+    Contains,
+    Regex,
+}
+
+/// Assigns `category` to transactions that pass every condition that is
+/// set. Rules are tried in `priority` order and the first match wins.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Rule {
+    pub id: String,
+    pub priority: u32,
+    pub enabled: bool,
+    pub category: String,
+    pub text_match: TextMatch,
+    /// Matched case-insensitively against the description; empty matches
+    /// everything.
+    pub pattern: String,
+    pub transaction_type: Option<TransactionType>,
+    /// Inclusive bounds. A bound only applies to amounts in its currency.
+    pub min: Option<Money>,
+    pub max: Option<Money>,
+}
+
+impl Default for Rule {
+    fn default() -> Self {
+        Self {
+            id: Uuid::new_v4().to_string(),
+            priority: 0,
+            enabled: true,
+            category: String::new(),
+            text_match: TextMatch::Contains,
+            pattern: String::new(),
+            transaction_type: None,
+            min: None,
+            max: None,
+        }
+    }
+}
+
+impl Rule {
+    pub fn compile(&self) -> Result<Option<Regex>, String> {
+        match self.text_match {
+            TextMatch::Contains => Ok(None),
+            TextMatch::Regex => RegexBuilder::new(&self.pattern)
+                .case_insensitive(true)
+                .build()
+                .map(Some)
+                .map_err(|err| format!("Invalid pattern: {}", err)),
+        }
+    }
+
+    fn matches(&self, regex: Option<&Regex>, description: &str, amount: Money, kind: &TransactionType) -> bool {
+        let text_ok = match regex {
+            Some(regex) => regex.is_match(description),
+            None => description.to_lowercase().contains(&self.pattern.to_lowercase()),
+        };
+        let type_ok = self.transaction_type.as_ref().is_none_or(|t| t == kind);
+        let in_bound = |bound: Option<Money>, ok: fn(i64, i64) -> bool| {
+            bound.is_none_or(|b| b.currency != amount.currency || ok(amount.minor, b.minor))
+        };
+        text_ok && type_ok && in_bound(self.min, |a, b| a >= b) && in_bound(self.max, |a, b| a <= b)
+    }
+
+    pub fn describe(&self, locale: Locale) -> String {
+        let mut parts = Vec::new();
+        if !self.pattern.is_empty() {
+            parts.push(match self.text_match {
+                TextMatch::Contains => format!("description contains \"{}\"", self.pattern),
+                TextMatch::Regex => format!("description matches /{}/", self.pattern),
+            });
+        }
+        if let Some(kind) = &self.transaction_type {
+            parts.push(kind.as_str().to_lowercase());
+        }
+        match (self.min, self.max) {
+            (Some(min), Some(max)) => parts.push(format!("{} to {}", min.format(locale), max.format(locale))),
+            (Some(min), None) => parts.push(format!("at least {}", min.format(locale))),
+            (None, Some(max)) => parts.push(format!("at most {}", max.format(locale))),
+            (None, None) => {}
+        }
+        if parts.is_empty() {
+            "every transaction".to_string()
+        } else {
+            parts.join(", ")
+        }
+    }
+}
+
+/// Enabled rules in priority order, with their patterns compiled once.
+/// Rules whose pattern does not compile are left out.
+pub struct RuleSet(Vec<(Rule, Option<Regex>)>);
+
+impl RuleSet {
+    pub fn new(rules: &[Rule]) -> RuleSet {
+        let mut compiled: Vec<_> = rules
+            .iter()
+            .filter(|rule| rule.enabled && !rule.category.trim().is_empty())
+            .filter_map(|rule| Some((rule.clone(), rule.compile().ok()?)))
+            .collect();
+        compiled.sort_by_key(|(rule, _)| rule.priority);
+        RuleSet(compiled)
+    }
+
+    pub fn category_for(&self, description: &str, amount: Money, kind: &TransactionType) -> Option<String> {
+        self.0
+            .iter()
+            .find(|(rule, regex)| rule.matches(regex.as_ref(), description, amount, kind))
+            .map(|(rule, _)| rule.category.clone())
+    }
+}
+
+/// Renumbers priorities to follow the list order.
+fn renumber(rules: &mut [Rule]) {
+    for (i, rule) in rules.iter_mut().enumerate() {
+        rule.priority = i as u32;
+    }
+}
+
+#[component]
+pub fn RulesEditor(
+    rules: ReadSignal<Vec<Rule>>,
+    set_rules: WriteSignal<Vec<Rule>>,
+    transactions: ReadSignal<Vec<Transaction>>,
+    #[prop(into)] default_currency: Signal<Currency>,
+    locale: Locale,
+) -> impl IntoView {
+    let (draft, set_draft) = signal(Rule::default());
+    let (min_input, set_min_input) = signal(String::new());
+    let (max_input, set_max_input) = signal(String::new());
+    let (editing, set_editing) = signal(false);
+    let (error, set_error) = signal::<Option<String>>(None);
+
+    let sorted_rules = move || {
+        let mut sorted = rules.get();
+        sorted.sort_by_key(|rule| rule.priority);
+        sorted
+    };
+
+    // The rule as it stands in the form, or why it is not valid yet.
+    let parsed = move || {
+        let mut rule = draft.get();
+        let currency = default_currency.get();
+        let bound = |text: String| match text.trim() {
+            "" => Ok(None),
+            text => Money::parse(text, currency, locale).map(Some).map_err(|err| err.to_string()),
+        };
+        rule.min = bound(min_input.get())?;
+        rule.max = bound(max_input.get())?;
+        rule.compile()?;
+        Ok::<_, String>(rule)
+    };
+
+    // Existing transactions the rule in the form would match, with the
+    // category each would move from.
+    let test_results = move || {
+        let Ok(rule) = parsed() else {
+            return Vec::new();
+        };
+        let Ok(regex) = rule.compile() else {
+            return Vec::new();
+        };
+        transactions
+            .get()
+            .into_iter()
+            .filter(|t| rule.matches(regex.as_ref(), &t.description, t.amount, &t.transaction_type))
+            .collect::<Vec<_>>()
+    };
+
+    let reset = move || {
+        set_draft.set(Rule::default());
+        set_min_input.set(String::new());
+        set_max_input.set(String::new());
+        set_editing.set(false);
+        set_error.set(None);
+    };
+
+    let save = move |_| {
+        let rule = match parsed() {
+            Ok(rule) if rule.category.trim().is_empty() => {
+                set_error.set(Some("Choose a category for the rule".to_string()));
+                return;
+            }
+            Ok(rule) => rule,
+            Err(err) => {
+                set_error.set(Some(err));
+                return;
+            }
+        };
+        set_rules.update(|rules| {
+            rules.sort_by_key(|r| r.priority);
+            match rules.iter_mut().find(|r| r.id == rule.id) {
+                Some(existing) => *existing = rule,
+                None => rules.push(rule),
+            }
+            renumber(rules);
+        });
+        reset();
+    };
+
+    let edit = move |rule: Rule| {
+        set_min_input.set(rule.min.map(|m| m.format_number(locale)).unwrap_or_default());
+        set_max_input.set(rule.max.map(|m| m.format_number(locale)).unwrap_or_default());
+        set_draft.set(rule);
+        set_editing.set(true);
+        set_error.set(None);
+    };
+
+    let move_rule = move |id: String, offset: isize| {
+        set_rules.update(|rules| {
+            rules.sort_by_key(|r| r.priority);
+            if let Some(i) = rules.iter().position(|r| r.id == id) {
+                let j = i as isize + offset;
+                if j >= 0 && (j as usize) < rules.len() {
+                    rules.swap(i, j as usize);
+                }
+            }
+            renumber(rules);
+        });
+    };
+
+    view! {
+        <div class="rules-section">
+            <h2>"Categorisation Rules"</h2>
+            <p class="rules-hint">
+                "Rules fill in the category when a transaction is added without one or imported. The first matching rule wins."
+            </p>
+
+            <div class="rule-form">
+                <input
+                    type="text"
+                    placeholder="Category"
+                    list="categories"
+                    prop:value=move || draft.get().category
+                    on:input=move |ev| set_draft.update(|r| r.category = event_target_value(&ev))
+                />
+                <select on:change=move |ev| {
+                    let text_match = if event_target_value(&ev) == "regex" { TextMatch::Regex } else { TextMatch::Contains };
+                    set_draft.update(|r| r.text_match = text_match);
+                }>
+                    <option value="contains" selected=move || draft.get().text_match == TextMatch::Contains>
+                        "Description contains"
+                    </option>
+                    <option value="regex" selected=move || draft.get().text_match == TextMatch::Regex>
+                        "Description matches regex"
+                    </option>
+                </select>
+                <input
+                    type="text"
+                    placeholder="Text or pattern"
+                    prop:value=move || draft.get().pattern
+                    on:input=move |ev| set_draft.update(|r| r.pattern = event_target_value(&ev))
+                />
+                <select on:change=move |ev| {
+                    let kind = match event_target_value(&ev).as_str() {
+                        "income" => Some(TransactionType::Income),
+                        "expense" => Some(TransactionType::Expense),
+                        _ => None,
+                    };
+                    set_draft.update(|r| r.transaction_type = kind);
+                }>
+                    <option value="" selected=move || draft.get().transaction_type.is_none()>"Any type"</option>
+                    <option value="income" selected=move || draft.get().transaction_type == Some(TransactionType::Income)>
+                        "Income"
+                    </option>
+                    <option value="expense" selected=move || draft.get().transaction_type == Some(TransactionType::Expense)>
+                        "Expense"
+                    </option>
+                </select>
+                <input
+                    type="text"
+                    inputmode="decimal"
+                    placeholder=move || format!("Min {}", default_currency.get().code)
+                    prop:value=min_input
+                    on:input=move |ev| set_min_input.set(event_target_value(&ev))
+                />
+                <input
+                    type="text"
+                    inputmode="decimal"
+                    placeholder=move || format!("Max {}", default_currency.get().code)
+                    prop:value=max_input
+                    on:input=move |ev| set_max_input.set(event_target_value(&ev))
+                />
+                <button class="submit-btn" on:click=save>
+                    {move || if editing.get() { "Update Rule" } else { "Add Rule" }}
+                </button>
+                <Show when=move || editing.get()>
+                    <button class="reset-btn" on:click=move |_| reset()>"Cancel"</button>
+                </Show>
+            </div>
+            {move || error.get().map(|message| view! { <p class="form-error">{message}</p> })}
+
+            <Show when=move || !draft.get().pattern.is_empty() || draft.get().transaction_type.is_some()>
+                <div class="rule-test">
+                    <h3>
+                        {move || match parsed() {
+                            Ok(_) => format!("Test: matches {} existing transactions", test_results().len()),
+                            Err(err) => err,
+                        }}
+                    </h3>
+                    <ul>
+                        {move || {
+                            let category = draft.get().category;
+                            test_results().into_iter().take(20).map(|t| {
+                                let change = if t.category == category || category.is_empty() {
+                                    t.category.clone()
+                                } else {
+                                    format!("{} → {}", t.category, category)
+                                };
+                                view! {
+                                    <li>
+                                        <span>{t.description}</span>
+                                        <span>{t.amount.format(locale)}</span>
+                                        <span class="category">{change}</span>
+                                    </li>
+                                }
+                            }).collect_view()
+                        }}
+                    </ul>
+                </div>
+            </Show>
+
+            <ol class="rules-list">
+                {move || sorted_rules().into_iter().map(|rule| {
+                    let id = rule.id.clone();
+                    let (up_id, down_id, toggle_id, delete_id) = (id.clone(), id.clone(), id.clone(), id);
+                    let rule_for_edit = rule.clone();
+                    view! {
+                        <li class:disabled=!rule.enabled>
+                            <input
+                                type="checkbox"
+                                title="Enabled"
+                                prop:checked=rule.enabled
+                                on:change=move |ev| {
+                                    let enabled = event_target_checked(&ev);
+                                    set_rules.update(|rules| {
+                                        if let Some(r) = rules.iter_mut().find(|r| r.id == toggle_id) {
+                                            r.enabled = enabled;
+                                        }
+                                    });
+                                }
+                            />
+                            <span class="rule-summary">
+                                <strong>{rule.category.clone()}</strong>
+                                " when "
+                                {rule.describe(locale)}
+                            </span>
+                            <button class="edit-btn" title="Higher priority" on:click=move |_| move_rule(up_id.clone(), -1)>"↑"</button>
+                            <button class="edit-btn" title="Lower priority" on:click=move |_| move_rule(down_id.clone(), 1)>"↓"</button>
+                            <button class="edit-btn" on:click=move |_| edit(rule_for_edit.clone())>"Edit"</button>
+                            <button
+                                class="delete-btn"
+                                on:click=move |_| set_rules.update(|rules| {
+                                    rules.retain(|r| r.id != delete_id);
+                                    renumber(rules);
+                                })
+                            >
+                                "Delete"
+                            </button>
+                        </li>
+                    }
+                }).collect_view()}
+            </ol>
+        </div>
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::money::USD;
+
+    fn eur() -> Currency {
+        Currency::from_code("EUR").unwrap()
+    }
+
+    fn rule(priority: u32, category: &str, pattern: &str) -> Rule {
+        Rule {
+            priority,
+            category: category.to_string(),
+            pattern: pattern.to_string(),
+            ..Rule::default()
+        }
+    }
+
+    fn regex(priority: u32, category: &str, pattern: &str) -> Rule {
+        Rule {
+            text_match: TextMatch::Regex,
+            ..rule(priority, category, pattern)
+        }
+    }
+
+    fn usd(minor: i64) -> Money {
+        Money::from_minor(minor, USD)
+    }
+
+    fn expense(rules: &RuleSet, description: &str, amount: Money) -> Option<String> {
+        rules.category_for(description, amount, &TransactionType::Expense)
+    }
+
+    #[test]
+    fn rules_run_in_priority_order_and_the_first_match_wins() {
+        let rules = RuleSet::new(&[
+            rule(2, "Shopping", "amazon"),
+            rule(1, "Books", "amazon books"),
+            rule(3, "Everything else", ""),
+            rule(2, "Later at the same priority", "amazon"),
+        ]);
+        assert_eq!(expense(&rules, "AMAZON BOOKS #123", usd(1000)).as_deref(), Some("Books"));
+        assert_eq!(expense(&rules, "Amazon Marketplace", usd(1000)).as_deref(), Some("Shopping"));
+        assert_eq!(expense(&rules, "Bakery", usd(1000)).as_deref(), Some("Everything else"));
+        assert_eq!(expense(&RuleSet::new(&[]), "Bakery", usd(1000)), None);
+    }
+
+    #[test]
+    fn disabled_blank_and_invalid_rules_are_skipped() {
+        let rules = RuleSet::new(&[
+            Rule { enabled: false, ..rule(0, "Disabled", "coffee") },
+            rule(1, "  ", "coffee"),
+            regex(2, "Broken", "coffee("),
+            rule(3, "Food", "coffee"),
+        ]);
+        assert_eq!(rules.0.len(), 1);
+        assert_eq!(expense(&rules, "Coffee", usd(300)).as_deref(), Some("Food"));
+        assert!(regex(0, "Broken", "(").compile().unwrap_err().starts_with("Invalid pattern: "));
+    }
+
+    #[test]
+    fn patterns_ignore_case() {
+        let rules = RuleSet::new(&[regex(0, "Transport", r"^uber\s+(trip|eats)"), rule(1, "Fuel", "SHELL")]);
+        assert_eq!(expense(&rules, "UBER   Trip 42", usd(100)).as_deref(), Some("Transport"));
+        assert_eq!(expense(&rules, "Pay UBER trip", usd(100)), None);
+        assert_eq!(expense(&rules, "shell station", usd(100)).as_deref(), Some("Fuel"));
+    }
+
+    #[test]
+    fn transaction_type_must_match_when_set() {
+        let rules = RuleSet::new(&[Rule { transaction_type: Some(TransactionType::Income), ..rule(0, "Refunds", "store") }]);
+        assert_eq!(rules.category_for("Store", usd(100), &TransactionType::Income).as_deref(), Some("Refunds"));
+        assert_eq!(rules.category_for("Store", usd(100), &TransactionType::Expense), None);
+    }
+
+    #[test]
+    fn bounds_are_inclusive_and_only_apply_in_their_currency() {
+        let bounded = Rule { min: Some(usd(1000)), max: Some(usd(5000)), ..rule(0, "Medium", "") };
+        let check = |amount| bounded.matches(None, "Anything", amount, &TransactionType::Expense);
+        assert!(!check(usd(999)));
+        assert!(check(usd(1000)));
+        assert!(check(usd(5000)));
+        assert!(!check(usd(5001)));
+        // Dollar bounds say nothing about euro amounts.
+        assert!(check(Money::from_minor(1, eur())));
+        assert!(check(Money::from_minor(1_000_000, eur())));
+
+        let mixed = Rule { min: Some(Money::from_minor(2000, eur())), max: Some(usd(5000)), ..rule(0, "Mixed", "") };
+        assert!(mixed.matches(None, "x", usd(100), &TransactionType::Expense));
+        assert!(!mixed.matches(None, "x", usd(5001), &TransactionType::Expense));
+        assert!(!mixed.matches(None, "x", Money::from_minor(1999, eur()), &TransactionType::Expense));
+    }
+}
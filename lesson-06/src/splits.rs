@@ -0,0 +1,355 @@
+use leptos::prelude::*;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::money::{Currency, Locale, Money, MoneyError};
+use crate::Transaction;
+
+/// Part of a transaction's amount put towards one category.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Split {
+    pub category: String,
+    pub amount: Money,
+}
+
+/// One row of the split editor, as typed. `id` keeps the row's inputs in
+/// place while the rows around it are edited or removed.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SplitInput {
+    pub id: String,
+    pub category: String,
+    pub amount: String,
+}
+
+impl Default for SplitInput {
+    fn default() -> SplitInput {
+        SplitInput {
+            id: Uuid::new_v4().to_string(),
+            category: String::new(),
+            amount: String::new(),
+        }
+    }
+}
+
+impl SplitInput {
+    pub fn from_split(split: &Split, locale: Locale) -> SplitInput {
+        SplitInput {
+            category: split.category.clone(),
+            amount: split.amount.format_number(locale),
+            ..Default::default()
+        }
+    }
+}
+
+/// `amount` shared out by category: across the splits in proportion, or
+/// all of it under the transaction's category. `amount` is the transaction's
+/// own amount or a conversion of it; any rounding difference goes to the
+/// last split so the parts always add up.
+pub fn allocate(t: &Transaction, amount: Money) -> Result<Vec<(String, Money)>, MoneyError> {
+    let Some((last, rest)) = t.splits.split_last().filter(|_| t.amount.minor != 0) else {
+        return Ok(vec![(t.category.clone(), amount)]);
+    };
+    let mut parts = Vec::with_capacity(t.splits.len());
+    let mut remaining = amount;
+    for split in rest {
+        let n = split.amount.minor as i128 * amount.minor as i128;
+        let d = t.amount.minor as i128;
+        let share = (n.abs() * 2 + d.abs()) / (2 * d.abs()) * n.signum() * d.signum();
+        let share = Money::from_minor(i64::try_from(share).map_err(|_| MoneyError::Overflow)?, amount.currency);
+        remaining = remaining.checked_sub(share)?;
+        parts.push((split.category.clone(), share));
+    }
+    parts.push((last.category.clone(), remaining));
+    Ok(parts)
+}
+
+/// Every category the transaction counts towards.
+pub fn categories(t: &Transaction) -> Vec<&str> {
+    if t.splits.is_empty() {
+        vec![t.category.as_str()]
+    } else {
+        t.splits.iter().map(|s| s.category.as_str()).collect()
+    }
+}
+
+/// The splits typed for a transaction of `total`, or why they are not
+/// valid. No rows means the transaction is not split.
+pub fn parse_splits(inputs: &[SplitInput], total: Money, locale: Locale) -> Result<Vec<Split>, String> {
+    if inputs.is_empty() {
+        return Ok(Vec::new());
+    }
+    if inputs.len() < 2 {
+        return Err("A split needs at least two parts".to_string());
+    }
+    let splits = inputs
+        .iter()
+        .enumerate()
+        .map(|(i, input)| {
+            let category = input.category.trim();
+            if category.is_empty() {
+                return Err(format!("Split {} needs a category", i + 1));
+            }
+            match Money::parse(&input.amount, total.currency, locale) {
+                Ok(amount) if amount.is_positive() => Ok(Split {
+                    category: category.to_string(),
+                    amount,
+                }),
+                Ok(_) => Err(format!("Split {} must be greater than zero", i + 1)),
+                Err(err) => Err(format!("Split {}: {}", i + 1, err)),
+            }
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    let sum = Money::sum(total.currency, splits.iter().map(|s| s.amount)).map_err(|err| err.to_string())?;
+    if sum != total {
+        return Err(format!(
+            "The splits add up to {}, not the total of {}",
+            sum.format(locale),
+            total.format(locale)
+        ));
+    }
+    Ok(splits)
+}
+
+/// What is left of `total` once the typed splits are taken off, when
+/// every amount typed so far is valid.
+fn remaining(inputs: &[SplitInput], total: Money, locale: Locale) -> Option<Money> {
+    inputs
+        .iter()
+        .filter(|input| !input.amount.trim().is_empty())
+        .try_fold(total, |left, input| left.checked_sub(Money::parse(&input.amount, total.currency, locale).ok()?).ok())
+}
+
+#[component]
+pub fn SplitEditor(
+    inputs: ReadSignal<Vec<SplitInput>>,
+    set_inputs: WriteSignal<Vec<SplitInput>>,
+    /// The transaction's amount as typed so far.
+    #[prop(into)]
+    total: Signal<Option<Money>>,
+    #[prop(into)] currency: Signal<Currency>,
+    locale: Locale,
+) -> impl IntoView {
+    let edit_row = move |id: &str, update: &dyn Fn(&mut SplitInput)| {
+        set_inputs.update(|inputs| {
+            if let Some(input) = inputs.iter_mut().find(|input| input.id == id) {
+                update(input);
+            }
+        });
+    };
+
+    view! {
+        <div class="split-editor">
+            <For
+                each=move || inputs.get()
+                key=|input| input.id.clone()
+                children=move |input| {
+                    let SplitInput { id, category, amount } = input;
+                    let (category_id, amount_id) = (id.clone(), id.clone());
+                    view! {
+                        <div class="split-row">
+                            <input
+                                type="text"
+                                placeholder="Category"
+                                list="categories"
+                                prop:value=category
+                                on:input=move |ev| {
+                                    let value = event_target_value(&ev);
+                                    edit_row(&category_id, &|input| input.category = value.clone());
+                                }
+                            />
+                            <input
+                                type="text"
+                                inputmode="decimal"
+                                placeholder=move || Money::zero(currency.get()).format_number(locale)
+                                prop:value=amount
+                                on:input=move |ev| {
+                                    let value = event_target_value(&ev);
+                                    edit_row(&amount_id, &|input| input.amount = value.clone());
+                                }
+                            />
+                            <button
+                                class="delete-btn"
+                                title="Remove this split"
+                                on:click=move |_| set_inputs.update(|inputs| inputs.retain(|input| input.id != id))
+                            >
+                                "✕"
+                            </button>
+                        </div>
+                    }
+                }
+            />
+            <div class="split-footer">
+                <button
+                    class="edit-btn"
+                    on:click=move |_| set_inputs.update(|inputs| {
+                        // Starting a split begins with two rows.
+                        let rows = if inputs.is_empty() { 2 } else { 1 };
+                        inputs.extend((0..rows).map(|_| SplitInput::default()));
+                    })
+                >
+                    {move || if inputs.get().is_empty() { "Split across categories" } else { "Add split" }}
+                </button>
+                {move || {
+                    let inputs = inputs.get();
+                    let left = remaining(&inputs, total.get()?, locale)?;
+                    (!inputs.is_empty()).then(|| view! {
+                        <span class="split-remaining" class:balanced=left.minor == 0 class:negative=left.is_negative()>
+                            {if left.is_negative() {
+                                format!("{} over the total", left.format_abs(locale))
+                            } else {
+                                format!("{} left to assign", left.format(locale))
+                            }}
+                        </span>
+                    })
+                }}
+            </div>
+        </div>
+    }
+}
+
+#[component]
+pub fn SplitBreakdown(splits: Vec<Split>, locale: Locale) -> impl IntoView {
+    let (expanded, set_expanded) = signal(false);
+    let count = splits.len();
+
+    view! {
+        <div class="split-breakdown">
+            <button
+                class="split-toggle"
+                aria-expanded=move || expanded.get().to_string()
+                on:click=move |_| set_expanded.update(|e| *e = !*e)
+            >
+                {move || format!("{} {} splits", if expanded.get() { "▾" } else { "▸" }, count)}
+            </button>
+            <Show when=move || expanded.get()>
+                <ul>
+                    {splits.iter().map(|split| view! {
+                        <li>
+                            <span class="category">{split.category.clone()}</span>
+                            <span>{split.amount.format(locale)}</span>
+                        </li>
+                    }).collect_view()}
+                </ul>
+            </Show>
+        </div>
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::money::USD;
+
+    const EUR: Currency = Currency { code: "EUR", symbol: "€", exponent: 2 };
+    const JPY: Currency = Currency { code: "JPY", symbol: "¥", exponent: 0 };
+
+    fn split(category: &str, minor: i64) -> Split {
+        Split {
+            category: category.to_string(),
+            amount: Money::from_minor(minor, USD),
+        }
+    }
+
+    fn split_transaction(splits: Vec<Split>) -> Transaction {
+        Transaction {
+            amount: Money::sum(USD, splits.iter().map(|s| s.amount)).unwrap(),
+            category: splits[0].category.clone(),
+            splits,
+            ..Default::default()
+        }
+    }
+
+    fn parts(parts: &[(&str, i64)], currency: Currency) -> Vec<(String, Money)> {
+        parts
+            .iter()
+            .map(|(category, minor)| (category.to_string(), Money::from_minor(*minor, currency)))
+            .collect()
+    }
+
+    fn input(category: &str, amount: &str) -> SplitInput {
+        SplitInput {
+            category: category.to_string(),
+            amount: amount.to_string(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn unsplit_transactions_keep_the_whole_amount() {
+        let t = Transaction {
+            amount: Money::from_minor(1_000, USD),
+            category: "Food".to_string(),
+            ..Default::default()
+        };
+        let converted = Money::from_minor(912, EUR);
+        assert_eq!(allocate(&t, converted), Ok(parts(&[("Food", 912)], EUR)));
+    }
+
+    #[test]
+    fn converted_amounts_are_shared_in_proportion() {
+        let t = split_transaction(vec![split("Food", 7_000), split("Home", 3_000)]);
+        assert_eq!(allocate(&t, t.amount), Ok(parts(&[("Food", 7_000), ("Home", 3_000)], USD)));
+        assert_eq!(
+            allocate(&t, Money::from_minor(9_123, EUR)),
+            Ok(parts(&[("Food", 6_386), ("Home", 2_737)], EUR))
+        );
+        assert_eq!(
+            allocate(&t, Money::from_minor(13_456, JPY)),
+            Ok(parts(&[("Food", 9_419), ("Home", 4_037)], JPY))
+        );
+    }
+
+    #[test]
+    fn rounding_goes_to_the_last_split() {
+        let t = split_transaction(vec![split("A", 3_333), split("B", 3_333), split("C", 3_334)]);
+        let shared = allocate(&t, Money::from_minor(5_001, EUR)).unwrap();
+        assert_eq!(shared, parts(&[("A", 1_667), ("B", 1_667), ("C", 1_667)], EUR));
+        let shared = allocate(&t, Money::from_minor(2, EUR)).unwrap();
+        assert_eq!(shared, parts(&[("A", 1), ("B", 1), ("C", 0)], EUR));
+    }
+
+    #[test]
+    fn negative_amounts_round_like_positive_ones() {
+        let t = split_transaction(vec![split("Food", 7_000), split("Home", 3_000)]);
+        assert_eq!(
+            allocate(&t, Money::from_minor(-9_123, EUR)),
+            Ok(parts(&[("Food", -6_386), ("Home", -2_737)], EUR))
+        );
+        let halves = split_transaction(vec![split("A", 5_000), split("B", 5_000)]);
+        assert_eq!(allocate(&halves, Money::from_minor(1, EUR)), Ok(parts(&[("A", 1), ("B", 0)], EUR)));
+        assert_eq!(allocate(&halves, Money::from_minor(-1, EUR)), Ok(parts(&[("A", -1), ("B", 0)], EUR)));
+    }
+
+    #[test]
+    fn parses_splits_that_add_up() {
+        let total = Money::from_minor(10_000, USD);
+        let locale = Locale::default();
+        assert_eq!(parse_splits(&[], total, locale), Ok(Vec::new()));
+        assert_eq!(
+            parse_splits(&[input(" Food ", "70"), input("Home", "30.00")], total, locale),
+            Ok(vec![split("Food", 7_000), split("Home", 3_000)])
+        );
+    }
+
+    #[test]
+    fn rejects_invalid_splits() {
+        let total = Money::from_minor(10_000, USD);
+        let locale = Locale::default();
+        let check = |inputs: &[SplitInput], message: &str| {
+            assert_eq!(parse_splits(inputs, total, locale), Err(message.to_string()));
+        };
+        check(&[input("Food", "100")], "A split needs at least two parts");
+        check(&[input("Food", "70"), input(" ", "30")], "Split 2 needs a category");
+        check(&[input("Food", "0"), input("Home", "100")], "Split 1 must be greater than zero");
+        check(&[input("Food", "-10"), input("Home", "110")], "Split 1 must be greater than zero");
+        check(&[input("Food", "7o"), input("Home", "30")], "Split 1: Not a valid amount");
+        check(
+            &[input("Food", "70"), input("Home", "20")],
+            "The splits add up to $90.00, not the total of $100.00",
+        );
+        check(
+            &[input("Food", "70"), input("Home", "30.01")],
+            "The splits add up to $100.01, not the total of $100.00",
+        );
+    }
+}
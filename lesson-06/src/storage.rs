@@ -0,0 +1,198 @@
+use std::cell::{Cell, RefCell};
+use std::rc::Rc;
+
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use wasm_bindgen::closure::Closure;
+use wasm_bindgen::{JsCast, JsValue};
+use wasm_bindgen_futures::JsFuture;
+use web_sys::{IdbDatabase, IdbObjectStoreParameters, IdbRequest, IdbTransaction, IdbTransactionMode};
+
+use crate::budget::Budget;
+use crate::rates::ExchangeRate;
+use crate::recurring::Recurring;
+use crate::rules::Rule;
+use crate::Transaction;
+
+const DATABASE: &str = "lesson-06";
+/// Bumped whenever an object store is added or reshaped; `upgrade` brings
+/// older databases forward one version at a time.
+const SCHEMA_VERSION: u32 = 5;
+pub const TRANSACTIONS: &str = "transactions";
+pub const RATES: &str = "rates";
+pub const RULES: &str = "rules";
+pub const BUDGETS: &str = "budgets";
+pub const RECURRING: &str = "recurring";
+
+/// Why the database could not be opened.
+pub enum OpenError {
+    /// IndexedDB is missing or refused the connection.
+    Unavailable,
+    /// Bringing the stores forward from `from_version` failed. The upgrade
+    /// was rolled back, leaving the data as it was.
+    Upgrade { from_version: u32, message: String },
+}
+
+pub struct Database {
+    db: IdbDatabase,
+    /// True when the database did not exist before this open.
+    pub created: bool,
+}
+
+fn create_store(db: &IdbDatabase, name: &str) -> Result<(), JsValue> {
+    let params = IdbObjectStoreParameters::new();
+    params.set_key_path(&JsValue::from_str("id"));
+    db.create_object_store_with_optional_parameters(name, &params)?;
+    Ok(())
+}
+
+fn upgrade(db: &IdbDatabase, old_version: u32) -> Result<(), JsValue> {
+    if old_version < 1 {
+        create_store(db, TRANSACTIONS)?;
+    }
+    if old_version < 2 {
+        create_store(db, RATES)?;
+    }
+    if old_version < 3 {
+        create_store(db, RULES)?;
+    }
+    if old_version < 4 {
+        create_store(db, BUDGETS)?;
+    }
+    if old_version < 5 {
+        create_store(db, RECURRING)?;
+    }
+    Ok(())
+}
+
+/// Opens the database. `on_blocked` runs if an upgrade has to wait for
+/// another tab to let go of an older version; opening carries on once it
+/// does.
+pub async fn open(on_blocked: impl FnOnce() + 'static) -> Result<Database, OpenError> {
+    let factory = leptos::prelude::window()
+        .indexed_db()
+        .ok()
+        .flatten()
+        .ok_or(OpenError::Unavailable)?;
+    let request = factory
+        .open_with_u32(DATABASE, SCHEMA_VERSION)
+        .map_err(|_| OpenError::Unavailable)?;
+
+    let created = Rc::new(Cell::new(false));
+    let upgrade_error = Rc::new(RefCell::new(None::<OpenError>));
+    let on_upgrade = {
+        let request = request.clone();
+        let created = created.clone();
+        let upgrade_error = upgrade_error.clone();
+        Closure::once_into_js(move |event: web_sys::IdbVersionChangeEvent| {
+            let old_version = event.old_version() as u32;
+            created.set(old_version == 0);
+            let upgraded = request.result().and_then(|db| upgrade(db.unchecked_ref(), old_version));
+            if let Err(err) = upgraded {
+                // Aborting the version change rolls back the stores already
+                // created and fails the open request.
+                if let Some(tx) = request.transaction() {
+                    let _ = tx.abort();
+                }
+                upgrade_error.replace(Some(OpenError::Upgrade {
+                    from_version: old_version,
+                    message: describe(&err),
+                }));
+            }
+        })
+    };
+    request.set_onupgradeneeded(Some(on_upgrade.unchecked_ref()));
+    let on_blocked = Closure::once_into_js(on_blocked);
+    request.set_onblocked(Some(on_blocked.unchecked_ref()));
+
+    let db: IdbDatabase = match finish(&request).await {
+        Ok(db) => db.unchecked_into(),
+        Err(_) => return Err(upgrade_error.take().unwrap_or(OpenError::Unavailable)),
+    };
+    // Step aside when another tab opens a newer version, so its upgrade is
+    // not blocked by this connection.
+    let on_version_change = {
+        let db = db.clone();
+        Closure::once_into_js(move || db.close())
+    };
+    db.set_onversionchange(Some(on_version_change.unchecked_ref()));
+    Ok(Database {
+        db,
+        created: created.get(),
+    })
+}
+
+/// Everything the tracker keeps, as read at startup.
+pub struct Stored {
+    pub transactions: Vec<Transaction>,
+    pub rates: Vec<ExchangeRate>,
+    pub rules: Vec<Rule>,
+    pub budgets: Vec<Budget>,
+    pub recurring: Vec<Recurring>,
+}
+
+impl Database {
+    pub async fn load_all(&self) -> Result<Stored, JsValue> {
+        Ok(Stored {
+            transactions: self.get_all(TRANSACTIONS).await?,
+            rates: self.get_all(RATES).await?,
+            rules: self.get_all(RULES).await?,
+            budgets: self.get_all(BUDGETS).await?,
+            recurring: self.get_all(RECURRING).await?,
+        })
+    }
+
+    /// Records that fail to deserialize are skipped rather than failing the
+    /// whole load.
+    async fn get_all<T: DeserializeOwned>(&self, store: &str) -> Result<Vec<T>, JsValue> {
+        let tx = self.db.transaction_with_str(store)?;
+        let values: js_sys::Array = finish(&tx.object_store(store)?.get_all()?).await?.unchecked_into();
+        Ok(values
+            .iter()
+            .filter_map(|value| js_sys::JSON::stringify(&value).ok())
+            .filter_map(|json| serde_json::from_str(&String::from(json)).ok())
+            .collect())
+    }
+
+    pub async fn replace_all<T: Serialize>(&self, store: &str, values: &[T]) -> Result<(), JsValue> {
+        let tx = self.db.transaction_with_str_and_mode(store, IdbTransactionMode::Readwrite)?;
+        let object_store = tx.object_store(store)?;
+        object_store.clear()?;
+        for value in values {
+            let json = serde_json::to_string(value).map_err(|err| JsValue::from_str(&err.to_string()))?;
+            object_store.put(&js_sys::JSON::parse(&json)?)?;
+        }
+        complete(&tx).await
+    }
+}
+
+impl Drop for Database {
+    fn drop(&mut self) {
+        self.db.close();
+    }
+}
+
+fn describe(err: &JsValue) -> String {
+    err.dyn_ref::<js_sys::Error>()
+        .map(|err| String::from(err.message()))
+        .or_else(|| err.as_string())
+        .unwrap_or_else(|| format!("{:?}", err))
+}
+
+async fn finish(request: &IdbRequest) -> Result<JsValue, JsValue> {
+    let promise = js_sys::Promise::new(&mut |resolve, reject| {
+        request.set_onsuccess(Some(&resolve));
+        request.set_onerror(Some(&reject));
+    });
+    JsFuture::from(promise).await?;
+    request.result()
+}
+
+async fn complete(tx: &IdbTransaction) -> Result<(), JsValue> {
+    let promise = js_sys::Promise::new(&mut |resolve, reject| {
+        tx.set_oncomplete(Some(&resolve));
+        tx.set_onerror(Some(&reject));
+        tx.set_onabort(Some(&reject));
+    });
+    JsFuture::from(promise).await.map(|_| ())
+}